@@ -0,0 +1,736 @@
+// colors with 8 bit channels instead of three unconstrained i32 values
+// Color is an sRGB color with alpha like the colors of CSS, every channel from 0 to 255
+// FloatColor has the same channels from 0.0 to 1.0, Hsl and Hsv are the cylindrical models
+// of the color pickers, Lab is CIELAB, where distances match how different colors look
+//
+// colors parse from and print as hex strings like #ff8000 and from the CSS color names
+// delta_e76 is the plain distance in Lab, delta_e2000 the CIEDE2000 formula that
+// corrects it where the eye is more or less sensitive, below about 1 the difference can't be seen
+use std::fmt;
+use std::str::FromStr;
+
+/// an sRGB color with alpha, 0 alpha is fully transparent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+/// the channels of a color from 0.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatColor {
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64,
+    pub alpha: f64,
+}
+
+/// hue in degrees from 0 to 360, saturation, lightness and alpha from 0.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub hue: f64,
+    pub saturation: f64,
+    pub lightness: f64,
+    pub alpha: f64,
+}
+
+/// hue in degrees from 0 to 360, saturation, value and alpha from 0.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub hue: f64,
+    pub saturation: f64,
+    pub value: f64,
+    pub alpha: f64,
+}
+
+/// CIELAB with the D65 white point, lightness from 0 to 100
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+// a channel from 0.0 to 1.0 to 0 to 255, NaN becomes 0
+fn to_byte(channel: f64) -> u8 {
+    if channel.is_nan() {
+        0
+    } else {
+        (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
+
+fn to_unit(channel: u8) -> f64 {
+    f64::from(channel) / 255.0
+}
+
+/// why a tuple or a string isn't a color
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColorError {
+    // the name of the channel and its value outside 0 to 255
+    ChannelOutOfRange(&'static str, i32),
+    // the characters after the # that aren't hexadecimal digits
+    InvalidHexDigits(String),
+    // the number of hex digits, only 3, 4, 6 and 8 are colors
+    InvalidHexLength(usize),
+    UnknownName(String),
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorError::ChannelOutOfRange(channel, value) => {
+                write!(f, "{} is {}, it has to be from 0 to 255", channel, value)
+            }
+            ColorError::InvalidHexDigits(digits) => write!(f, "{:?} aren't hexadecimal digits", digits),
+            ColorError::InvalidHexLength(length) => write!(f, "a hex color has 3, 4, 6 or 8 digits, not {}", length),
+            ColorError::UnknownName(name) => write!(f, "{:?} is neither a hex color nor a CSS color name", name),
+        }
+    }
+}
+
+impl std::error::Error for ColorError {}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    /// an opaque color
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Color {
+        Color::rgba(red, green, blue, 255)
+    }
+
+    pub const fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Color {
+        Color {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    pub fn with_alpha(self, alpha: u8) -> Color {
+        Color { alpha, ..self }
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.alpha == 255
+    }
+
+    pub fn to_float(self) -> FloatColor {
+        FloatColor {
+            red: to_unit(self.red),
+            green: to_unit(self.green),
+            blue: to_unit(self.blue),
+            alpha: to_unit(self.alpha),
+        }
+    }
+
+    /// the CSS color with this name, ignoring case, like "rebeccapurple" or "transparent"
+    pub fn named(name: &str) -> Option<Color> {
+        let name = name.to_ascii_lowercase();
+        if name == "transparent" {
+            return Some(Color::TRANSPARENT);
+        }
+        let index = NAMED.binary_search_by_key(&name.as_str(), |&(name, _)| name).ok()?;
+        let [_, red, green, blue] = NAMED[index].1.to_be_bytes();
+        Some(Color::rgb(red, green, blue))
+    }
+
+    /// the CSS name of this color if it has one, gray rather than grey
+    pub fn name(&self) -> Option<&'static str> {
+        if *self == Color::TRANSPARENT {
+            return Some("transparent");
+        }
+        if !self.is_opaque() {
+            return None;
+        }
+        let rgb = u32::from_be_bytes([0, self.red, self.green, self.blue]);
+        NAMED
+            .iter()
+            .find(|&&(name, value)| value == rgb && !name.contains("grey"))
+            .map(|&(name, _)| name)
+    }
+
+    /// #rrggbb, or #rrggbbaa if the color isn't opaque
+    pub fn to_hex(&self) -> String {
+        if self.is_opaque() {
+            format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.red, self.green, self.blue, self.alpha)
+        }
+    }
+
+    pub fn to_hsl(self) -> Hsl {
+        Hsl::from(self)
+    }
+
+    pub fn to_hsv(self) -> Hsv {
+        Hsv::from(self)
+    }
+
+    pub fn to_lab(self) -> Lab {
+        Lab::from(self)
+    }
+
+    /// this color painted over the background, the source-over compositing of CSS
+    pub fn over(self, background: Color) -> Color {
+        let (top, bottom) = (self.to_float(), background.to_float());
+        let alpha = top.alpha + bottom.alpha * (1.0 - top.alpha);
+        if alpha == 0.0 {
+            return Color::TRANSPARENT;
+        }
+        let channel = |top_channel: f64, bottom_channel: f64| {
+            (top_channel * top.alpha + bottom_channel * bottom.alpha * (1.0 - top.alpha)) / alpha
+        };
+        FloatColor {
+            red: channel(top.red, bottom.red),
+            green: channel(top.green, bottom.green),
+            blue: channel(top.blue, bottom.blue),
+            alpha,
+        }
+        .to_color()
+    }
+
+    /// the color a fraction of the way from this one to the other, alpha included
+    pub fn mix(self, other: Color, fraction: f64) -> Color {
+        let (a, b) = (self.to_float(), other.to_float());
+        let fraction = fraction.clamp(0.0, 1.0);
+        let between = |from: f64, to: f64| from + (to - from) * fraction;
+        FloatColor {
+            red: between(a.red, b.red),
+            green: between(a.green, b.green),
+            blue: between(a.blue, b.blue),
+            alpha: between(a.alpha, b.alpha),
+        }
+        .to_color()
+    }
+
+    /// CIE76, the distance of the two colors in Lab, alpha is ignored
+    pub fn delta_e76(&self, other: &Color) -> f64 {
+        self.to_lab().delta_e76(&other.to_lab())
+    }
+
+    /// CIEDE2000, how different the two colors look, alpha is ignored
+    pub fn delta_e2000(&self, other: &Color) -> f64 {
+        self.to_lab().delta_e2000(&other.to_lab())
+    }
+}
+
+impl Default for Color {
+    fn default() -> Color {
+        Color::BLACK
+    }
+}
+
+/// three i32 values like the old tuple struct, each has to be from 0 to 255
+impl TryFrom<(i32, i32, i32)> for Color {
+    type Error = ColorError;
+
+    fn try_from((red, green, blue): (i32, i32, i32)) -> Result<Color, ColorError> {
+        let channel =
+            |name: &'static str, value: i32| u8::try_from(value).map_err(|_| ColorError::ChannelOutOfRange(name, value));
+        Ok(Color::rgb(channel("red", red)?, channel("green", green)?, channel("blue", blue)?))
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+/// #rgb, #rgba, #rrggbb, #rrggbbaa or a CSS color name
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Color, ColorError> {
+        let s = s.trim();
+        let Some(hex) = s.strip_prefix('#') else {
+            return Color::named(s).ok_or_else(|| ColorError::UnknownName(String::from(s)));
+        };
+        let invalid: String = hex.chars().filter(|c| !c.is_ascii_hexdigit()).collect();
+        if !invalid.is_empty() {
+            return Err(ColorError::InvalidHexDigits(invalid));
+        }
+        let digit = |index: usize| u8::from_str_radix(&hex[index..index + 1], 16).expect("checked to be hexadecimal");
+        let pair = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).expect("checked to be hexadecimal");
+        // in the short forms every digit stands for itself twice, #f80 is #ff8800
+        match hex.len() {
+            3 | 4 => {
+                let alpha = if hex.len() == 4 { digit(3) * 17 } else { 255 };
+                Ok(Color::rgba(digit(0) * 17, digit(1) * 17, digit(2) * 17, alpha))
+            }
+            6 | 8 => {
+                let alpha = if hex.len() == 8 { pair(6) } else { 255 };
+                Ok(Color::rgba(pair(0), pair(2), pair(4), alpha))
+            }
+            length => Err(ColorError::InvalidHexLength(length)),
+        }
+    }
+}
+
+impl FloatColor {
+    /// rounded to the nearest 8 bit color, channels outside 0.0 to 1.0 are clamped
+    pub fn to_color(self) -> Color {
+        Color::rgba(to_byte(self.red), to_byte(self.green), to_byte(self.blue), to_byte(self.alpha))
+    }
+}
+
+impl From<Color> for FloatColor {
+    fn from(color: Color) -> FloatColor {
+        color.to_float()
+    }
+}
+
+impl From<FloatColor> for Color {
+    fn from(color: FloatColor) -> Color {
+        color.to_color()
+    }
+}
+
+// the hue in degrees and the largest and smallest channel, shared by HSL and HSV
+fn hue_max_min(color: &FloatColor) -> (f64, f64, f64) {
+    let FloatColor { red, green, blue, .. } = *color;
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == red {
+        60.0 * ((green - blue) / delta).rem_euclid(6.0)
+    } else if max == green {
+        60.0 * ((blue - red) / delta + 2.0)
+    } else {
+        60.0 * ((red - green) / delta + 4.0)
+    };
+    (hue, max, min)
+}
+
+// red, green and blue from the hue, the chroma and what is added to every channel
+fn from_hue(hue: f64, chroma: f64, offset: f64, alpha: f64) -> Color {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (red, green, blue) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    FloatColor {
+        red: red + offset,
+        green: green + offset,
+        blue: blue + offset,
+        alpha,
+    }
+    .to_color()
+}
+
+impl From<Color> for Hsl {
+    fn from(color: Color) -> Hsl {
+        let color = color.to_float();
+        let (hue, max, min) = hue_max_min(&color);
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        Hsl {
+            hue,
+            saturation,
+            lightness,
+            alpha: color.alpha,
+        }
+    }
+}
+
+impl From<Hsl> for Color {
+    fn from(hsl: Hsl) -> Color {
+        let saturation = hsl.saturation.clamp(0.0, 1.0);
+        let lightness = hsl.lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        from_hue(hsl.hue, chroma, lightness - chroma / 2.0, hsl.alpha)
+    }
+}
+
+impl From<Color> for Hsv {
+    fn from(color: Color) -> Hsv {
+        let color = color.to_float();
+        let (hue, max, min) = hue_max_min(&color);
+        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+        Hsv {
+            hue,
+            saturation,
+            value: max,
+            alpha: color.alpha,
+        }
+    }
+}
+
+impl From<Hsv> for Color {
+    fn from(hsv: Hsv) -> Color {
+        let value = hsv.value.clamp(0.0, 1.0);
+        let chroma = value * hsv.saturation.clamp(0.0, 1.0);
+        from_hue(hsv.hue, chroma, value - chroma, hsv.alpha)
+    }
+}
+
+// sRGB is gamma encoded, Lab is computed from the linear light
+fn linear(channel: f64) -> f64 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl From<Color> for Lab {
+    fn from(color: Color) -> Lab {
+        let FloatColor { red, green, blue, .. } = color.to_float();
+        let (red, green, blue) = (linear(red), linear(green), linear(blue));
+        // linear sRGB to CIE XYZ, relative to the D65 white
+        let x = (0.4124564 * red + 0.3575761 * green + 0.1804375 * blue) / 0.95047;
+        let y = 0.2126729 * red + 0.7151522 * green + 0.0721750 * blue;
+        let z = (0.0193339 * red + 0.1191920 * green + 0.9503041 * blue) / 1.08883;
+        let f = |t: f64| {
+            let delta: f64 = 6.0 / 29.0;
+            if t > delta.powi(3) {
+                t.cbrt()
+            } else {
+                t / (3.0 * delta * delta) + 4.0 / 29.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+}
+
+impl Lab {
+    pub fn delta_e76(&self, other: &Lab) -> f64 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)).sqrt()
+    }
+
+    /// CIEDE2000 as in Sharma, Wu and Dalal (2005), with kL = kC = kH = 1
+    pub fn delta_e2000(&self, other: &Lab) -> f64 {
+        let (l1, a1, b1) = (self.l, self.a, self.b);
+        let (l2, a2, b2) = (other.l, other.a, other.b);
+        let pow7 = |value: f64| value.powi(7);
+        let twenty_five_7 = pow7(25.0);
+
+        // a is stretched for unsaturated colors, where the eye sees hue differences less
+        let c_bar = ((a1.hypot(b1)) + (a2.hypot(b2))) / 2.0;
+        let g = 0.5 * (1.0 - (pow7(c_bar) / (pow7(c_bar) + twenty_five_7)).sqrt());
+        let (a1, a2) = ((1.0 + g) * a1, (1.0 + g) * a2);
+        let (c1, c2) = (a1.hypot(b1), a2.hypot(b2));
+        let hue = |b: f64, a: f64| {
+            if a == 0.0 && b == 0.0 {
+                0.0
+            } else {
+                b.atan2(a).to_degrees().rem_euclid(360.0)
+            }
+        };
+        let (h1, h2) = (hue(b1, a1), hue(b2, a2));
+
+        let delta_l = l2 - l1;
+        let delta_c = c2 - c1;
+        let delta_h = if c1 * c2 == 0.0 {
+            0.0
+        } else if (h2 - h1).abs() <= 180.0 {
+            h2 - h1
+        } else if h2 - h1 > 180.0 {
+            h2 - h1 - 360.0
+        } else {
+            h2 - h1 + 360.0
+        };
+        let delta_big_h = 2.0 * (c1 * c2).sqrt() * (delta_h.to_radians() / 2.0).sin();
+
+        let l_bar = (l1 + l2) / 2.0;
+        let c_bar = (c1 + c2) / 2.0;
+        let h_bar = if c1 * c2 == 0.0 {
+            h1 + h2
+        } else if (h1 - h2).abs() <= 180.0 {
+            (h1 + h2) / 2.0
+        } else if h1 + h2 < 360.0 {
+            (h1 + h2 + 360.0) / 2.0
+        } else {
+            (h1 + h2 - 360.0) / 2.0
+        };
+
+        let cos = |degrees: f64| degrees.to_radians().cos();
+        let t = 1.0 - 0.17 * cos(h_bar - 30.0) + 0.24 * cos(2.0 * h_bar) + 0.32 * cos(3.0 * h_bar + 6.0)
+            - 0.20 * cos(4.0 * h_bar - 63.0);
+        let delta_theta = 30.0 * (-((h_bar - 275.0) / 25.0).powi(2)).exp();
+        let r_c = 2.0 * (pow7(c_bar) / (pow7(c_bar) + twenty_five_7)).sqrt();
+        let s_l = 1.0 + 0.015 * (l_bar - 50.0).powi(2) / (20.0 + (l_bar - 50.0).powi(2)).sqrt();
+        let s_c = 1.0 + 0.045 * c_bar;
+        let s_h = 1.0 + 0.015 * c_bar * t;
+        // the rotation term fixes the blue region, where the other terms are off
+        let r_t = -(2.0 * delta_theta).to_radians().sin() * r_c;
+
+        let (l, c, h) = (delta_l / s_l, delta_c / s_c, delta_big_h / s_h);
+        (l * l + c * c + h * h + r_t * c * h).sqrt()
+    }
+}
+
+// the 148 named colors of CSS Color Module Level 4, sorted for the binary search
+// grey and gray are both there for every gray
+const NAMED: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[test]
+    fn hex_strings_and_names() {
+        assert_eq!("#ff8000".parse(), Ok(Color::rgb(255, 128, 0)));
+        assert_eq!("#F80".parse(), Ok(Color::rgb(255, 136, 0)));
+        assert_eq!("#ff800080".parse(), Ok(Color::rgba(255, 128, 0, 128)));
+        assert_eq!("RebeccaPurple".parse(), Ok(Color::rgb(0x66, 0x33, 0x99)));
+        assert_eq!("transparent".parse(), Ok(Color::TRANSPARENT));
+        assert_eq!("#12345".parse::<Color>(), Err(ColorError::InvalidHexLength(5)));
+        assert_eq!("#1g2h3".parse::<Color>(), Err(ColorError::InvalidHexDigits(String::from("gh"))));
+        assert_eq!(
+            "reddish".parse::<Color>().unwrap_err().to_string(),
+            "\"reddish\" is neither a hex color nor a CSS color name"
+        );
+
+        assert_eq!(Color::rgb(255, 128, 0).to_string(), "#ff8000");
+        assert_eq!(Color::rgba(255, 128, 0, 128).to_string(), "#ff800080");
+        assert_eq!(Color::rgb(128, 128, 128).name(), Some("gray"));
+        assert_eq!(Color::rgb(0, 255, 255).name(), Some("aqua"));
+        assert!(NAMED.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        assert_eq!(Color::try_from((0, 0, 0)), Ok(Color::BLACK));
+        assert_eq!(Color::try_from((-5, 900, 0)), Err(ColorError::ChannelOutOfRange("red", -5)));
+        assert_eq!(
+            Color::try_from((0, 900, 0)).unwrap_err().to_string(),
+            "green is 900, it has to be from 0 to 255"
+        );
+    }
+
+    #[test]
+    fn hsl_and_hsv_round_trip() {
+        let orange = Color::rgb(255, 128, 0);
+        let hsl = orange.to_hsl();
+        assert!(close(hsl.hue, 30.1, 0.05) && close(hsl.saturation, 1.0, 1e-9) && close(hsl.lightness, 0.5, 1e-9));
+        let hsv = orange.to_hsv();
+        assert!(close(hsv.hue, 30.1, 0.05) && close(hsv.value, 1.0, 1e-9));
+        assert_eq!(
+            Color::from(Hsl {
+                hue: 120.0,
+                saturation: 1.0,
+                lightness: 0.25,
+                alpha: 1.0
+            }),
+            Color::rgb(0, 128, 0)
+        );
+        // every 8 bit color comes back unchanged
+        for red in (0..=255).step_by(15) {
+            for green in (0..=255).step_by(17) {
+                for blue in (0..=255).step_by(51) {
+                    let color = Color::rgba(red, green, blue, 200);
+                    assert_eq!(Color::from(color.to_hsl()), color);
+                    assert_eq!(Color::from(color.to_hsv()), color);
+                    assert_eq!(color.to_float().to_color(), color);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blending_over_a_background() {
+        let half_red = Color::rgba(255, 0, 0, 128);
+        assert_eq!(half_red.over(Color::WHITE), Color::rgb(255, 127, 127));
+        assert_eq!(Color::rgb(1, 2, 3).over(Color::WHITE), Color::rgb(1, 2, 3));
+        assert_eq!(Color::TRANSPARENT.over(Color::TRANSPARENT), Color::TRANSPARENT);
+        assert_eq!(half_red.over(half_red.with_alpha(0)), half_red);
+        assert_eq!(Color::BLACK.mix(Color::WHITE, 0.5), Color::rgb(128, 128, 128));
+    }
+
+    #[test]
+    fn perceptual_distances() {
+        let red = Color::rgb(255, 0, 0).to_lab();
+        assert!(close(red.l, 53.24, 0.01) && close(red.a, 80.09, 0.01) && close(red.b, 67.20, 0.01));
+        let white = Color::WHITE.to_lab();
+        assert!(close(white.l, 100.0, 1e-3) && close(white.a, 0.0, 1e-3) && close(white.b, 0.0, 1e-3));
+        assert!(close(Color::BLACK.delta_e76(&Color::WHITE), 100.0, 1e-3));
+
+        // test data of Sharma, Wu and Dalal for CIEDE2000
+        let lab = |l, a, b| Lab { l, a, b };
+        let pairs = [
+            (lab(50.0, 2.6772, -79.7751), lab(50.0, 0.0, -82.7485), 2.0425),
+            (lab(50.0, -1.3802, -84.2814), lab(50.0, 0.0, -82.7485), 1.0000),
+            (lab(50.0, 0.0, 0.0), lab(50.0, -1.0, 2.0), 2.3669),
+            (lab(50.0, 2.49, -0.001), lab(50.0, -2.49, 0.0009), 7.1792),
+            (lab(50.0, 2.5, 0.0), lab(73.0, 25.0, -18.0), 27.1492),
+            (lab(60.2574, -34.0099, 36.2677), lab(60.4626, -34.1751, 39.4387), 1.2644),
+        ];
+        for (a, b, expected) in pairs {
+            assert!(close(a.delta_e2000(&b), expected, 1e-4), "{:?} {:?}", a, b);
+            assert!(close(b.delta_e2000(&a), expected, 1e-4));
+        }
+    }
+}
@@ -0,0 +1,174 @@
+// checking the syntax of email addresses
+// this is the common subset of RFC 5321 and RFC 5322 that mail servers accept in practice:
+// a local part of letters, digits and the printable symbols of RFC 5322, separated by single dots,
+// an @ and a domain name of at least two labels like example.io
+// quoted local parts, comments and IP address literals like user@[10.0.0.1] are rejected
+//
+// whether the address really exists can only be found out by sending a mail to it
+use std::fmt;
+
+const MAX_LENGTH: usize = 254;
+const MAX_LOCAL_PART: usize = 64;
+const MAX_LABEL: usize = 63;
+
+/// what is wrong with an email address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmailError {
+    Empty,
+    TooLong(usize),
+    MissingAt,
+    EmptyLocalPart,
+    LocalPartTooLong(usize),
+    // a character at a byte position that isn't allowed there
+    InvalidCharacter(char, usize),
+    // a dot at the start or the end of the local part or two dots in a row
+    MisplacedDot(usize),
+    EmptyDomain,
+    // the domain needs at least two labels, like example.io
+    SingleLabelDomain,
+    InvalidLabel(String),
+}
+
+impl fmt::Display for EmailError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmailError::Empty => write!(f, "the email address is empty"),
+            EmailError::TooLong(length) => {
+                write!(f, "the email address has {} characters, at most {} are allowed", length, MAX_LENGTH)
+            }
+            EmailError::MissingAt => write!(f, "the email address has no @"),
+            EmailError::EmptyLocalPart => write!(f, "there is nothing before the @"),
+            EmailError::LocalPartTooLong(length) => write!(
+                f,
+                "the part before the @ has {} characters, at most {} are allowed",
+                length, MAX_LOCAL_PART
+            ),
+            EmailError::InvalidCharacter(c, position) => {
+                write!(f, "{:?} at position {} isn't allowed", c, position)
+            }
+            EmailError::MisplacedDot(position) => write!(
+                f,
+                "the dot at position {} starts, ends or doubles a dot in the part before the @",
+                position
+            ),
+            EmailError::EmptyDomain => write!(f, "there is nothing after the @"),
+            EmailError::SingleLabelDomain => write!(f, "the domain needs a dot, like example.io"),
+            EmailError::InvalidLabel(label) => write!(f, "the domain label {:?} isn't valid", label),
+        }
+    }
+}
+
+impl std::error::Error for EmailError {}
+
+// the characters RFC 5322 allows in an unquoted local part besides letters, digits and dots
+fn is_local_symbol(c: char) -> bool {
+    "!#$%&'*+-/=?^_`{|}~".contains(c)
+}
+
+/// checks the syntax of an email address
+pub fn validate(email: &str) -> Result<(), EmailError> {
+    if email.is_empty() {
+        return Err(EmailError::Empty);
+    }
+    let length = email.chars().count();
+    if length > MAX_LENGTH {
+        return Err(EmailError::TooLong(length));
+    }
+    // the last @ separates the domain, an @ in the local part is caught as an invalid character
+    let at = email.rfind('@').ok_or(EmailError::MissingAt)?;
+    let (local, domain) = (&email[..at], &email[at + 1..]);
+
+    if local.is_empty() {
+        return Err(EmailError::EmptyLocalPart);
+    }
+    if local.len() > MAX_LOCAL_PART {
+        return Err(EmailError::LocalPartTooLong(local.len()));
+    }
+    let mut previous_dot = true;
+    for (position, c) in local.char_indices() {
+        if c == '.' {
+            if previous_dot {
+                return Err(EmailError::MisplacedDot(position));
+            }
+            previous_dot = true;
+        } else if c.is_ascii_alphanumeric() || is_local_symbol(c) {
+            previous_dot = false;
+        } else {
+            return Err(EmailError::InvalidCharacter(c, position));
+        }
+    }
+    if previous_dot {
+        return Err(EmailError::MisplacedDot(local.len() - 1));
+    }
+
+    if domain.is_empty() {
+        return Err(EmailError::EmptyDomain);
+    }
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2 {
+        return Err(EmailError::SingleLabelDomain);
+    }
+    let mut offset = at + 1;
+    for label in &labels {
+        // hostname labels: letters, digits and hyphens, no hyphen at either end
+        if label.is_empty() || label.len() > MAX_LABEL || label.starts_with('-') || label.ends_with('-') {
+            return Err(EmailError::InvalidLabel(label.to_string()));
+        }
+        if let Some((position, c)) = label.char_indices().find(|&(_, c)| !(c.is_ascii_alphanumeric() || c == '-')) {
+            return Err(EmailError::InvalidCharacter(c, offset + position));
+        }
+        offset += label.len() + 1;
+    }
+    // top level domains are never all digits, so 10.0.0.1 is not a domain
+    let top_level = labels[labels.len() - 1];
+    if top_level.chars().all(|c| c.is_ascii_digit()) {
+        return Err(EmailError::InvalidLabel(top_level.to_string()));
+    }
+    Ok(())
+}
+
+/// the form two addresses are compared in
+/// domains are case-insensitive, and so is the local part at nearly every mail provider
+pub fn normalize(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_addresses_pass() {
+        for email in [
+            "the_last_man@example.io",
+            "first.last+tag@mail.example.co.uk",
+            "o'hara@x-y.de",
+            "a@b.cd",
+        ] {
+            assert_eq!(validate(email), Ok(()), "{}", email);
+        }
+    }
+
+    #[test]
+    fn invalid_addresses_say_what_is_wrong() {
+        assert_eq!(validate(""), Err(EmailError::Empty));
+        assert_eq!(validate("user.example.io"), Err(EmailError::MissingAt));
+        assert_eq!(validate("@example.io"), Err(EmailError::EmptyLocalPart));
+        assert_eq!(validate("user@"), Err(EmailError::EmptyDomain));
+        assert_eq!(validate("user@localhost"), Err(EmailError::SingleLabelDomain));
+        assert_eq!(validate(".user@example.io"), Err(EmailError::MisplacedDot(0)));
+        assert_eq!(validate("us..er@example.io"), Err(EmailError::MisplacedDot(3)));
+        assert_eq!(validate("user.@example.io"), Err(EmailError::MisplacedDot(4)));
+        assert_eq!(validate("us er@example.io"), Err(EmailError::InvalidCharacter(' ', 2)));
+        assert_eq!(validate("a@b@example.io"), Err(EmailError::InvalidCharacter('@', 1)));
+        // the underscores of user_x@some_weird_place.io are fine before the @ but not in a domain
+        assert_eq!(
+            validate("user_x@some_weird_place.io"),
+            Err(EmailError::InvalidCharacter('_', 11))
+        );
+        assert_eq!(validate("user@-example.io"), Err(EmailError::InvalidLabel(String::from("-example"))));
+        assert_eq!(validate("user@10.0.0.1"), Err(EmailError::InvalidLabel(String::from("1"))));
+        let long = format!("{}@example.io", "a".repeat(65));
+        assert_eq!(validate(&long), Err(EmailError::LocalPartTooLong(65)));
+    }
+}
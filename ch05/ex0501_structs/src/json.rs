@@ -0,0 +1,327 @@
+// a small JSON (RFC 8259) parser and string escaping, just enough for the user files
+// every parsed value remembers the line it starts on, so errors can point to it
+// numbers are kept as their text, a u64 like a timestamp would lose digits as an f64
+// the parser calls itself for every nested array or object, so the nesting is limited
+// a file of a million [ would overflow the stack instead of being a syntax error
+use std::fmt;
+
+/// how deep arrays and objects can be nested, the user files need 3
+pub const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// a value and the line it starts on, counting from 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json {
+    pub line: usize,
+    pub value: Value,
+}
+
+impl Json {
+    /// the value of a key if this is an object, the last one if the key is there twice
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match &self.value {
+            Value::Object(members) => members.iter().rev().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+/// the text as a JSON string with quotes
+pub fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if u32::from(c) < 0x20 => quoted.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+pub fn parse(text: &str) -> Result<Json, SyntaxError> {
+    let mut parser = Parser {
+        text,
+        position: 0,
+        line: 1,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.whitespace();
+    if parser.position < text.len() {
+        return Err(parser.error("there is more after the end of the JSON value"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+    line: usize,
+    // the arrays and objects the parser is in
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> SyntaxError {
+        SyntaxError {
+            line: self.line,
+            message: String::from(message),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SyntaxError> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(&format!("expected {:?} but found {:?}", expected, c))),
+            None => Err(self.error(&format!("expected {:?} but the text ended", expected))),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, SyntaxError> {
+        if self.text[self.position..].starts_with(word) {
+            self.position += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("expected a JSON value"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, SyntaxError> {
+        self.whitespace();
+        let line = self.line;
+        let value = match self.peek() {
+            Some('{') => self.object()?,
+            Some('[') => self.array()?,
+            Some('"') => Value::String(self.string()?),
+            Some('t') => self.keyword("true", Value::Bool(true))?,
+            Some('f') => self.keyword("false", Value::Bool(false))?,
+            Some('n') => self.keyword("null", Value::Null)?,
+            Some('-' | '0'..='9') => self.number()?,
+            Some(c) => return Err(self.error(&format!("{:?} can't start a JSON value", c))),
+            None => return Err(self.error("expected a JSON value but the text ended")),
+        };
+        Ok(Json { line, value })
+    }
+
+    // the elements between the brackets, one level deeper
+    fn list<T>(&mut self, close: char, element: impl FnMut(&mut Self) -> Result<T, SyntaxError>) -> Result<Vec<T>, SyntaxError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(&format!("arrays and objects are nested more than {} deep", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let elements = self.elements(close, element);
+        self.depth -= 1;
+        elements
+    }
+
+    // the elements between the brackets, separated by commas
+    fn elements<T>(&mut self, close: char, mut element: impl FnMut(&mut Self) -> Result<T, SyntaxError>) -> Result<Vec<T>, SyntaxError> {
+        self.next();
+        let mut elements = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(close) {
+            self.next();
+            return Ok(elements);
+        }
+        loop {
+            elements.push(element(self)?);
+            self.whitespace();
+            match self.next() {
+                Some(',') => {}
+                Some(c) if c == close => return Ok(elements),
+                _ => return Err(self.error(&format!("expected ',' or {:?}", close))),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, SyntaxError> {
+        self.list(']', |parser| parser.value()).map(Value::Array)
+    }
+
+    fn object(&mut self) -> Result<Value, SyntaxError> {
+        self.list('}', |parser| {
+            parser.whitespace();
+            if parser.peek() != Some('"') {
+                return Err(parser.error("expected a key in quotes"));
+            }
+            let key = parser.string()?;
+            parser.whitespace();
+            parser.expect(':')?;
+            Ok((key, parser.value()?))
+        })
+        .map(Value::Object)
+    }
+
+    fn number(&mut self) -> Result<Value, SyntaxError> {
+        let start = self.position;
+        while matches!(self.peek(), Some('-' | '+' | '.' | 'e' | 'E' | '0'..='9')) {
+            self.next();
+        }
+        let number = &self.text[start..self.position];
+        // the grammar of RFC 8259: -? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?
+        let digits = number.strip_prefix('-').unwrap_or(number);
+        let (mantissa, exponent) = match digits.find(['e', 'E']) {
+            Some(index) => (&digits[..index], Some(&digits[index + 1..])),
+            None => (digits, None),
+        };
+        let (integer, fraction) = match mantissa.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (mantissa, None),
+        };
+        let all_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+        let valid = all_digits(integer)
+            && (integer == "0" || !integer.starts_with('0'))
+            && fraction.is_none_or(all_digits)
+            && exponent.is_none_or(|exponent| {
+                all_digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent))
+            });
+        if !valid {
+            return Err(self.error(&format!("{:?} isn't a JSON number", number)));
+        }
+        Ok(Value::Number(String::from(number)))
+    }
+
+    fn hex4(&mut self) -> Result<u32, SyntaxError> {
+        let hex = self.text.get(self.position..self.position + 4).filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()));
+        let code = hex
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| self.error("\\u needs four hexadecimal digits"))?;
+        self.position += 4;
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, SyntaxError> {
+        self.next();
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error("the string doesn't end")),
+                Some('"') => return Ok(string),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = self.hex4()?;
+                            // characters beyond the BMP come as a surrogate pair, like \ud83d\ude00
+                            if (0xd800..0xdc00).contains(&code) && self.text[self.position..].starts_with("\\u") {
+                                self.position += 2;
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(self.error("a high surrogate isn't followed by a low one"));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code).ok_or_else(|| self.error("\\u escapes a lone surrogate"))?
+                        }
+                        _ => return Err(self.error("unknown escape in a string")),
+                    };
+                    string.push(escaped);
+                }
+                Some(c) if u32::from(c) < 0x20 => {
+                    return Err(self.error("control characters in strings have to be escaped"))
+                }
+                Some(c) => string.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_know_their_line() {
+        let json = parse("{\n  \"a\": [1, -2.5e3, true],\n  \"b\":\n    null\n}").unwrap();
+        assert_eq!(json.line, 1);
+        let a = json.get("a").unwrap();
+        assert_eq!(a.line, 2);
+        match &a.value {
+            Value::Array(items) => {
+                assert_eq!(items[1].value, Value::Number(String::from("-2.5e3")));
+                assert_eq!(items[2].value, Value::Bool(true));
+            }
+            other => panic!("expected an array, got {:?}", other),
+        }
+        assert_eq!(json.get("b").unwrap().line, 4);
+        assert_eq!(parse("[1,\n2,\n01]").unwrap_err().line, 3);
+        assert_eq!(parse("{\"a\" 1}").unwrap_err().message, "expected ':' but found '1'");
+    }
+
+    #[test]
+    fn strings_round_trip() {
+        let text = "quote \" backslash \\ tab \t newline \n bell \u{7} emoji 😀";
+        let quoted = quote(text);
+        assert_eq!(parse(&quoted).unwrap().value, Value::String(String::from(text)));
+        assert_eq!(
+            parse(r#""\ud83d\ude00 \u00e9""#).unwrap().value,
+            Value::String(String::from("😀 é"))
+        );
+        assert!(parse(r#""\ud83d""#).is_err());
+    }
+
+    #[test]
+    fn deep_nesting_is_a_syntax_error() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        let error = parse(&format!("{{\n\"users\": {}}}", nested(1_000_000))).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.message, "arrays and objects are nested more than 64 deep");
+    }
+}
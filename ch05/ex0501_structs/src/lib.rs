@@ -0,0 +1,26 @@
+//! the library part of the structs example
+//! the User struct lives here so the registry can validate and manage users
+
+/// the User struct of the example
+pub mod user;
+
+/// checking the syntax of email addresses
+pub mod email;
+
+/// a registry of user accounts with unique names and emails
+pub mod registry;
+
+/// SHA-256, HMAC-SHA256 and PBKDF2-HMAC-SHA256
+pub mod sha256;
+
+/// salted password hashes that can be checked but not read back
+pub mod password;
+
+/// a small JSON parser that remembers lines
+pub mod json;
+
+/// saving and loading the accounts as CSV or JSON
+pub mod storage;
+
+/// colors with 8 bit and float channels, HSL, HSV, Lab, hex strings and CSS names
+pub mod color;
@@ -1,14 +1,9 @@
-/// Structs can hold multiple related values that can be of different types
-/// each piece of data has to be named so it’s clear what the values mean
-/// the values of the data an instance can be accessed by the field names
-#[derive(Debug)]
-#[allow(dead_code)]
-struct User {
-    active: bool,
-    user_name: String,
-    email: String,
-    sign_in_count: u64,
-}
+// the User struct moved to the library (src/user.rs)
+// so the registry in src/registry.rs can validate and manage users
+use ex0501_structs::color::Color;
+use ex0501_structs::registry::Registry;
+use ex0501_structs::storage;
+use ex0501_structs::user::User;
 
 /// Tuple structs
 /// tuple structs are structs that look similar to tuples
@@ -16,8 +11,8 @@ struct User {
 /// but don’t have names associated with their fields
 /// defining a tuple struct by with the struct keyword and the struct name 
 /// followed by the types in parentheses
-#[allow(dead_code)]
-struct Color(i32, i32, i32);
+// Color used to be a tuple struct of three i32 values too, which allowed Color(-5, 900, 0)
+// it is a struct with 8 bit channels in the library now (src/color.rs)
 #[allow(dead_code)]
 struct Point(i32, i32, i32);
 
@@ -83,7 +78,14 @@ fn main() {
     println!("------------------------------------------------------------");
     println!("Tuple structs");
     println!("------------------------------------------------------------");
-    let _black = Color(0, 0, 0);
+    let black = Color::rgb(0, 0, 0);
+    println!("{} is {:?}", black, black.name());
+    let half_orange: Color = "#ff800080".parse().unwrap_or_default();
+    println!("{} over white is {}", half_orange, half_orange.over(Color::WHITE));
+    println!(
+        "tomato and orangered differ by {:.1} (CIEDE2000)",
+        Color::named("tomato").unwrap_or_default().delta_e2000(&Color::named("orangered").unwrap_or_default())
+    );
     let _origin = Point(0, 0, 0);
 
     println!("------------------------------------------------------------");
@@ -91,9 +93,52 @@ fn main() {
     println!("------------------------------------------------------------");
     let _subject = AlwaysEqual;
 
+    println!("------------------------------------------------------------");
+    println!("User registry");
+    println!("------------------------------------------------------------");
+    // unlike build_me_an_user the registry checks the name and the email
+    // and makes sure no other account has them already
+    // the default 600000 PBKDF2 iterations take seconds in a debug build, the demo uses fewer
+    let mut registry = Registry::new().with_iterations(10_000);
+    for (name, email) in [
+        ("User Y", "the_last_man@example.io"),
+        ("user y", "someone@example.io"),
+        ("User Z", "user_z@some_weird_place.io"),
+    ] {
+        match registry.register(name, email) {
+            Ok(account) => println!("registered {:?}", account.user),
+            Err(error) => println!("can't register {}: {}", name, error),
+        }
+    }
+    for _ in 0..2 {
+        if let Ok(count) = registry.sign_in("User Y") {
+            println!("User Y signed in, {} times so far", count);
+        }
+    }
+
+    // a password is stored as a salted PBKDF2 hash, five wrong ones in a row lock the account
+    if let Err(error) = registry.set_password("User Y", "secret") {
+        println!("{}", error);
+    }
+    let _ = registry.set_password("User Y", "correct horse battery staple");
+    for password in ["Correct Horse", "correct horse battery staple"] {
+        match registry.authenticate("User Y", password) {
+            Ok(count) => println!("User Y authenticated, {} sign-ins so far", count),
+            Err(error) => println!("{}", error),
+        }
+    }
+    let _ = registry.deactivate("User Y");
+    if let Err(error) = registry.sign_in("User Y") {
+        println!("{}", error);
+    }
+
+    // the accounts as CSV, a file of an older schema version would be migrated while loading it
+    print!("{}", storage::export_csv(&registry));
+
 }
 
 // function to intantiate a new user
+// it accepts any strings, Registry::register checks them
 fn build_me_an_user(name: String, email: String) -> User {
     User {
         active: true,
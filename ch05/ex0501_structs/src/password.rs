@@ -0,0 +1,200 @@
+// storing passwords so they can be checked but not read back
+// a password is never stored, only a PBKDF2-HMAC-SHA256 hash of it with a random salt
+// the salt makes the same password hash differently for every user,
+// so a table of precomputed hashes is useless and equal passwords don't show
+//
+// a stored hash is written as pbkdf2-sha256$<iterations>$<salt in hex>$<hash in hex>
+// so the iterations can be raised later without breaking the hashes stored before
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Read;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use crate::sha256::{self, pbkdf2_hmac_sha256, Sha256, DIGEST};
+
+/// the iterations OWASP recommends for PBKDF2-HMAC-SHA256 (2023)
+pub const DEFAULT_ITERATIONS: u32 = 600_000;
+
+pub const MIN_LENGTH: usize = 8;
+
+const SALT: usize = 16;
+const SCHEME: &str = "pbkdf2-sha256";
+
+/// compares two byte strings in a time that only depends on their length,
+/// so timing the comparison doesn't tell how many bytes at the start were right
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a.iter().zip(b).fold(0u8, |difference, (x, y)| difference | (x ^ y));
+    std::hint::black_box(difference) == 0
+}
+
+// 16 random bytes from the operating system
+// where there is no /dev/urandom they are hashed together from the
+// randomly seeded hasher of the standard library, the time and a counter
+fn random_salt() -> [u8; SALT] {
+    let mut salt = [0u8; SALT];
+    if File::open("/dev/urandom").and_then(|mut file| file.read_exact(&mut salt)).is_ok() {
+        return salt;
+    }
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos());
+    let mut seed = Sha256::new();
+    seed.update(&hasher.finish().to_le_bytes()).update(&nanos.to_le_bytes());
+    salt.copy_from_slice(&seed.finish()[..SALT]);
+    salt
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordError {
+    TooShort(usize),
+    // a stored hash that can't be read, with what is wrong with it
+    Malformed(&'static str),
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PasswordError::TooShort(length) => write!(
+                f,
+                "the password has {} characters, at least {} are needed",
+                length, MIN_LENGTH
+            ),
+            PasswordError::Malformed(reason) => write!(f, "malformed password hash: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PasswordError {}
+
+/// the salted hash of a password
+#[derive(Clone, PartialEq, Eq)]
+pub struct PasswordHash {
+    iterations: u32,
+    salt: Vec<u8>,
+    hash: [u8; DIGEST],
+}
+
+impl PasswordHash {
+    /// hashes the password with a new random salt
+    pub fn new(password: &str, iterations: u32) -> Result<PasswordHash, PasswordError> {
+        let length = password.chars().count();
+        if length < MIN_LENGTH {
+            return Err(PasswordError::TooShort(length));
+        }
+        Ok(PasswordHash::with_salt(password, &random_salt(), iterations))
+    }
+
+    pub fn with_salt(password: &str, salt: &[u8], iterations: u32) -> PasswordHash {
+        let iterations = iterations.max(1);
+        let mut hash = [0; DIGEST];
+        pbkdf2_hmac_sha256(password.as_bytes(), salt, iterations, &mut hash);
+        PasswordHash {
+            iterations,
+            salt: salt.to_vec(),
+            hash,
+        }
+    }
+
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// the password is the one this hash was made from
+    pub fn verify(&self, password: &str) -> bool {
+        let mut hash = [0; DIGEST];
+        pbkdf2_hmac_sha256(password.as_bytes(), &self.salt, self.iterations, &mut hash);
+        constant_time_eq(&hash, &self.hash)
+    }
+}
+
+// the hash itself stays out of debug output and logs
+impl fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PasswordHash")
+            .field("iterations", &self.iterations)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}${}${}${}",
+            SCHEME,
+            self.iterations,
+            sha256::to_hex(&self.salt),
+            sha256::to_hex(&self.hash)
+        )
+    }
+}
+
+impl FromStr for PasswordHash {
+    type Err = PasswordError;
+
+    fn from_str(s: &str) -> Result<PasswordHash, PasswordError> {
+        let mut parts = s.split('$');
+        if parts.next() != Some(SCHEME) {
+            return Err(PasswordError::Malformed("it doesn't start with pbkdf2-sha256"));
+        }
+        let iterations = parts
+            .next()
+            .and_then(|part| part.parse::<u32>().ok())
+            .filter(|&iterations| iterations > 0)
+            .ok_or(PasswordError::Malformed("the iterations aren't a positive number"))?;
+        let salt = parts
+            .next()
+            .and_then(sha256::from_hex)
+            .ok_or(PasswordError::Malformed("the salt isn't hexadecimal"))?;
+        let hash = parts
+            .next()
+            .and_then(sha256::from_hex)
+            .and_then(|hash| <[u8; DIGEST]>::try_from(hash).ok())
+            .ok_or(PasswordError::Malformed("the hash isn't 32 hexadecimal bytes"))?;
+        if parts.next().is_some() {
+            return Err(PasswordError::Malformed("there is more after the hash"));
+        }
+        Ok(PasswordHash { iterations, salt, hash })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_verify_only_their_password() {
+        let hash = PasswordHash::new("correct horse", 10).unwrap();
+        assert!(hash.verify("correct horse"));
+        assert!(!hash.verify("correct horsE"));
+        // a new salt every time
+        assert_ne!(hash, PasswordHash::new("correct horse", 10).unwrap());
+        assert_eq!(PasswordHash::new("short", 10), Err(PasswordError::TooShort(5)));
+        assert!(!format!("{:?}", hash).contains(&sha256::to_hex(&hash.hash)));
+    }
+
+    #[test]
+    fn hashes_round_trip_through_strings() {
+        let hash = PasswordHash::with_salt("password", b"salt", 1);
+        let text = hash.to_string();
+        assert_eq!(
+            text,
+            "pbkdf2-sha256$1$73616c74$120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+        assert_eq!(text.parse::<PasswordHash>(), Ok(hash));
+        assert!(matches!("bcrypt$1$00$00".parse::<PasswordHash>(), Err(PasswordError::Malformed(_))));
+        assert!(matches!("pbkdf2-sha256$0$00$00".parse::<PasswordHash>(), Err(PasswordError::Malformed(_))));
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd") && !constant_time_eq(b"abc", b"ab"));
+    }
+}
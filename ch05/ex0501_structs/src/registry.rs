@@ -0,0 +1,574 @@
+// a registry that manages User accounts
+// build_me_an_user accepts any strings, the registry only accepts
+// user names and emails that are valid and not taken by another account yet
+//
+// user names and emails are compared case-insensitively, "User X" and "user x" are the same user
+// but every account keeps the spelling it was registered with
+// timestamps are SystemTime, the *_at methods take the time so tests don't depend on the clock
+//
+// accounts can have a password, authenticate checks it and counts the sign-in
+// after too many wrong passwords in a row the account is locked for a while,
+// which makes guessing passwords online slow
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::email::{self, EmailError};
+use crate::password::{PasswordError, PasswordHash, DEFAULT_ITERATIONS};
+use crate::user::User;
+
+const MAX_USER_NAME: usize = 32;
+
+/// how many of the latest sign-ins an account remembers
+pub const RECENT_SIGN_INS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    InvalidUserName(String),
+    InvalidEmail(EmailError),
+    UserNameTaken(String),
+    EmailTaken(String),
+    UnknownUser(String),
+    Inactive(String),
+    InvalidPassword(PasswordError),
+    NoPassword(String),
+    PasswordRequired(String),
+    WrongPassword { attempts_left: u32 },
+    LockedOut { until: SystemTime },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::InvalidUserName(reason) => write!(f, "invalid user name: {}", reason),
+            RegistryError::InvalidEmail(error) => write!(f, "invalid email: {}", error),
+            RegistryError::UserNameTaken(name) => write!(f, "the user name {:?} is already taken", name),
+            RegistryError::EmailTaken(email) => write!(f, "the email {:?} already belongs to an account", email),
+            RegistryError::UnknownUser(name) => write!(f, "there is no user {:?}", name),
+            RegistryError::Inactive(name) => write!(f, "the account of {:?} is deactivated", name),
+            RegistryError::InvalidPassword(error) => write!(f, "invalid password: {}", error),
+            RegistryError::NoPassword(name) => write!(f, "{:?} has no password set", name),
+            RegistryError::PasswordRequired(name) => write!(f, "{:?} has a password, it has to be checked", name),
+            RegistryError::WrongPassword { attempts_left } => {
+                write!(f, "wrong password, {} attempts left before the account is locked", attempts_left)
+            }
+            RegistryError::LockedOut { until } => {
+                let seconds = until.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+                write!(f, "too many wrong passwords, the account is locked until {} (unix time)", seconds)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RegistryError::InvalidEmail(error) => Some(error),
+            RegistryError::InvalidPassword(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<EmailError> for RegistryError {
+    fn from(error: EmailError) -> RegistryError {
+        RegistryError::InvalidEmail(error)
+    }
+}
+
+impl From<PasswordError> for RegistryError {
+    fn from(error: PasswordError) -> RegistryError {
+        RegistryError::InvalidPassword(error)
+    }
+}
+
+/// after max_attempts wrong passwords in a row the account is locked for duration
+/// a duration too long for the clock, like Duration::MAX, locks the account until it is unlocked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lockout {
+    pub max_attempts: u32,
+    pub duration: Duration,
+}
+
+impl Default for Lockout {
+    fn default() -> Lockout {
+        Lockout {
+            max_attempts: 5,
+            duration: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+/// checks a user name: 1 to 32 characters, no control characters,
+/// no whitespace at the start or the end
+pub fn validate_user_name(name: &str) -> Result<(), RegistryError> {
+    let invalid = |reason: &str| Err(RegistryError::InvalidUserName(String::from(reason)));
+    if name.is_empty() {
+        return invalid("the name is empty");
+    }
+    if name.chars().count() > MAX_USER_NAME {
+        return invalid("the name is longer than 32 characters");
+    }
+    if name.trim() != name {
+        return invalid("the name starts or ends with whitespace");
+    }
+    if name.chars().any(char::is_control) {
+        return invalid("the name contains control characters");
+    }
+    Ok(())
+}
+
+// the form user names are compared in
+// the time a duration after now, or as late as the clock can count when that is too far
+// halving the duration until it fits still leaves billions of years on a 64 bit clock
+fn saturating_add(now: SystemTime, mut duration: Duration) -> SystemTime {
+    loop {
+        if let Some(time) = now.checked_add(duration) {
+            return time;
+        }
+        duration /= 2;
+    }
+}
+
+fn name_key(name: &str) -> String {
+    name.to_lowercase()
+}
+
+/// a User in the registry, with the times it was created and signed in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub user: User,
+    pub created_at: SystemTime,
+    // the latest sign-ins, the oldest first, at most RECENT_SIGN_INS of them
+    sign_ins: VecDeque<SystemTime>,
+    password: Option<PasswordHash>,
+    // wrong passwords since the last successful sign-in or lockout
+    failed_attempts: u32,
+    locked_until: Option<SystemTime>,
+}
+
+impl Account {
+    pub fn has_password(&self) -> bool {
+        self.password.is_some()
+    }
+
+    pub fn password(&self) -> Option<&PasswordHash> {
+        self.password.as_ref()
+    }
+
+    pub fn failed_attempts(&self) -> u32 {
+        self.failed_attempts
+    }
+
+    pub fn locked_until(&self) -> Option<SystemTime> {
+        self.locked_until
+    }
+
+    fn record_sign_in(&mut self, now: SystemTime) -> u64 {
+        self.user.sign_in_count += 1;
+        if self.sign_ins.len() == RECENT_SIGN_INS {
+            self.sign_ins.pop_front();
+        }
+        self.sign_ins.push_back(now);
+        self.user.sign_in_count
+    }
+
+    pub fn recent_sign_ins(&self) -> impl Iterator<Item = &SystemTime> {
+        self.sign_ins.iter()
+    }
+
+    pub fn last_sign_in(&self) -> Option<SystemTime> {
+        self.sign_ins.back().copied()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Registry {
+    // by the lowercase user name, so iterating goes alphabetically
+    accounts: BTreeMap<String, Account>,
+    // the normalized email to the key of the account
+    by_email: HashMap<String, String>,
+    // the PBKDF2 iterations for new passwords
+    iterations: u32,
+    lockout: Lockout,
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        Registry {
+            accounts: BTreeMap::new(),
+            by_email: HashMap::new(),
+            iterations: DEFAULT_ITERATIONS,
+            lockout: Lockout::default(),
+        }
+    }
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// fewer iterations make hashing faster, and guessing too, so only lower them for tests
+    /// passwords set before keep the iterations they were hashed with
+    pub fn with_iterations(mut self, iterations: u32) -> Registry {
+        self.iterations = iterations;
+        self
+    }
+
+    pub fn with_lockout(mut self, lockout: Lockout) -> Registry {
+        self.lockout = lockout;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// a new active account that has never signed in
+    pub fn register(&mut self, user_name: &str, email: &str) -> Result<&Account, RegistryError> {
+        self.register_at(user_name, email, SystemTime::now())
+    }
+
+    pub fn register_at(&mut self, user_name: &str, email: &str, now: SystemTime) -> Result<&Account, RegistryError> {
+        let user = User {
+            active: true,
+            user_name: String::from(user_name),
+            email: String::from(email),
+            sign_in_count: 0,
+        };
+        self.restore(user, now, None, None)
+    }
+
+    /// adds an account as it was saved before, with the same checks as register
+    pub fn restore(
+        &mut self,
+        user: User,
+        created_at: SystemTime,
+        last_sign_in: Option<SystemTime>,
+        password: Option<PasswordHash>,
+    ) -> Result<&Account, RegistryError> {
+        validate_user_name(&user.user_name)?;
+        email::validate(&user.email)?;
+        let key = name_key(&user.user_name);
+        if self.accounts.contains_key(&key) {
+            return Err(RegistryError::UserNameTaken(user.user_name));
+        }
+        let normalized = email::normalize(&user.email);
+        if self.by_email.contains_key(&normalized) {
+            return Err(RegistryError::EmailTaken(user.email));
+        }
+
+        let account = Account {
+            user,
+            created_at,
+            sign_ins: last_sign_in.into_iter().collect(),
+            password,
+            failed_attempts: 0,
+            locked_until: None,
+        };
+        self.by_email.insert(normalized, key.clone());
+        Ok(self.accounts.entry(key).or_insert(account))
+    }
+
+    pub fn by_name(&self, user_name: &str) -> Option<&Account> {
+        self.accounts.get(&name_key(user_name))
+    }
+
+    pub fn by_email(&self, email: &str) -> Option<&Account> {
+        let key = self.by_email.get(&email::normalize(email))?;
+        self.accounts.get(key)
+    }
+
+    fn account_mut(&mut self, user_name: &str) -> Result<&mut Account, RegistryError> {
+        self.accounts
+            .get_mut(&name_key(user_name))
+            .ok_or_else(|| RegistryError::UnknownUser(String::from(user_name)))
+    }
+
+    /// counts a sign-in and returns the new sign_in_count, deactivated accounts can't sign in
+    /// this is for accounts without a password whose sign-ins are checked elsewhere
+    /// an account with a password has to go through authenticate, and a locked one waits like there
+    pub fn sign_in(&mut self, user_name: &str) -> Result<u64, RegistryError> {
+        self.sign_in_at(user_name, SystemTime::now())
+    }
+
+    pub fn sign_in_at(&mut self, user_name: &str, now: SystemTime) -> Result<u64, RegistryError> {
+        let account = self.account_mut(user_name)?;
+        if !account.user.active {
+            return Err(RegistryError::Inactive(account.user.user_name.clone()));
+        }
+        if account.password.is_some() {
+            return Err(RegistryError::PasswordRequired(account.user.user_name.clone()));
+        }
+        match account.locked_until {
+            Some(until) if until > now => return Err(RegistryError::LockedOut { until }),
+            Some(_) => account.locked_until = None,
+            None => {}
+        }
+        Ok(account.record_sign_in(now))
+    }
+
+    /// sets or replaces the password and lifts a lockout
+    pub fn set_password(&mut self, user_name: &str, password: &str) -> Result<(), RegistryError> {
+        let hash = PasswordHash::new(password, self.iterations)?;
+        let account = self.account_mut(user_name)?;
+        account.password = Some(hash);
+        account.failed_attempts = 0;
+        account.locked_until = None;
+        Ok(())
+    }
+
+    /// checks the password and counts the sign-in, returns the new sign_in_count
+    pub fn authenticate(&mut self, user_name: &str, password: &str) -> Result<u64, RegistryError> {
+        self.authenticate_at(user_name, password, SystemTime::now())
+    }
+
+    pub fn authenticate_at(&mut self, user_name: &str, password: &str, now: SystemTime) -> Result<u64, RegistryError> {
+        let lockout = self.lockout;
+        let account = self.account_mut(user_name)?;
+        if !account.user.active {
+            return Err(RegistryError::Inactive(account.user.user_name.clone()));
+        }
+        match account.locked_until {
+            Some(until) if until > now => return Err(RegistryError::LockedOut { until }),
+            Some(_) => account.locked_until = None,
+            None => {}
+        }
+        let hash = account
+            .password
+            .as_ref()
+            .ok_or_else(|| RegistryError::NoPassword(account.user.user_name.clone()))?;
+
+        if hash.verify(password) {
+            account.failed_attempts = 0;
+            return Ok(account.record_sign_in(now));
+        }
+        account.failed_attempts += 1;
+        if account.failed_attempts >= lockout.max_attempts {
+            let until = saturating_add(now, lockout.duration);
+            account.failed_attempts = 0;
+            account.locked_until = Some(until);
+            return Err(RegistryError::LockedOut { until });
+        }
+        Err(RegistryError::WrongPassword {
+            attempts_left: lockout.max_attempts - account.failed_attempts,
+        })
+    }
+
+    /// lifts a lockout before its time is up
+    pub fn unlock(&mut self, user_name: &str) -> Result<(), RegistryError> {
+        let account = self.account_mut(user_name)?;
+        account.failed_attempts = 0;
+        account.locked_until = None;
+        Ok(())
+    }
+
+    /// returns whether the account was active before
+    pub fn activate(&mut self, user_name: &str) -> Result<bool, RegistryError> {
+        let account = self.account_mut(user_name)?;
+        Ok(std::mem::replace(&mut account.user.active, true))
+    }
+
+    /// returns whether the account was active before
+    pub fn deactivate(&mut self, user_name: &str) -> Result<bool, RegistryError> {
+        let account = self.account_mut(user_name)?;
+        Ok(std::mem::replace(&mut account.user.active, false))
+    }
+
+    pub fn change_email(&mut self, user_name: &str, email: &str) -> Result<(), RegistryError> {
+        email::validate(email)?;
+        let key = name_key(user_name);
+        let normalized = email::normalize(email);
+        // taking an email that differs only in case from the old one is fine
+        if self.by_email.get(&normalized).is_some_and(|owner| *owner != key) {
+            return Err(RegistryError::EmailTaken(String::from(email)));
+        }
+        let account = self.account_mut(user_name)?;
+        let old = email::normalize(&account.user.email);
+        account.user.email = String::from(email);
+        self.by_email.remove(&old);
+        self.by_email.insert(normalized, key);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, user_name: &str) -> Option<Account> {
+        let account = self.accounts.remove(&name_key(user_name))?;
+        self.by_email.remove(&email::normalize(&account.user.email));
+        Some(account)
+    }
+
+    /// all accounts, alphabetically by user name
+    pub fn accounts(&self) -> impl Iterator<Item = &Account> {
+        self.accounts.values()
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &Account> {
+        self.accounts().filter(|account| account.user.active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn names_and_emails_are_unique() {
+        let mut registry = Registry::new();
+        registry.register_at("User Y", "the_last_man@example.io", at(0)).unwrap();
+        assert_eq!(
+            registry.register("user y", "other@example.io").unwrap_err(),
+            RegistryError::UserNameTaken(String::from("user y"))
+        );
+        assert_eq!(
+            registry.register("User Z", "The_Last_Man@Example.IO").unwrap_err(),
+            RegistryError::EmailTaken(String::from("The_Last_Man@Example.IO"))
+        );
+        assert!(matches!(
+            registry.register("User Z", "user_z@some_weird_place.io"),
+            Err(RegistryError::InvalidEmail(EmailError::InvalidCharacter('_', 11)))
+        ));
+        assert!(matches!(registry.register(" User Z", "z@example.io"), Err(RegistryError::InvalidUserName(_))));
+
+        let account = registry.by_email("THE_LAST_MAN@example.io").unwrap();
+        assert_eq!(account.user.user_name, "User Y");
+        assert_eq!(account.created_at, at(0));
+        assert_eq!(registry.by_name("USER Y"), Some(account));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn sign_ins_are_counted_and_remembered() {
+        let mut registry = Registry::new();
+        registry.register_at("User X", "user_x@weird-worlds.io", at(0)).unwrap();
+        for second in 1..=12 {
+            assert_eq!(registry.sign_in_at("user x", at(second)), Ok(second));
+        }
+        let account = registry.by_name("User X").unwrap();
+        assert_eq!(account.user.sign_in_count, 12);
+        assert_eq!(account.last_sign_in(), Some(at(12)));
+        assert_eq!(account.recent_sign_ins().count(), RECENT_SIGN_INS);
+        assert_eq!(account.recent_sign_ins().next(), Some(&at(3)));
+
+        assert_eq!(registry.deactivate("User X"), Ok(true));
+        assert_eq!(
+            registry.sign_in_at("User X", at(13)),
+            Err(RegistryError::Inactive(String::from("User X")))
+        );
+        assert_eq!(registry.active().count(), 0);
+        assert_eq!(registry.activate("User X"), Ok(false));
+        assert_eq!(registry.sign_in_at("User X", at(14)), Ok(13));
+        assert_eq!(
+            registry.sign_in("nobody"),
+            Err(RegistryError::UnknownUser(String::from("nobody")))
+        );
+    }
+
+    #[test]
+    fn changed_and_removed_emails_are_free_again() {
+        let mut registry = Registry::new();
+        registry.register("a", "a@example.io").unwrap();
+        registry.register("b", "b@example.io").unwrap();
+        assert!(matches!(registry.change_email("a", "B@example.io"), Err(RegistryError::EmailTaken(_))));
+        registry.change_email("a", "A@Example.io").unwrap();
+        registry.change_email("a", "new@example.io").unwrap();
+        assert!(registry.by_email("a@example.io").is_none());
+        assert_eq!(registry.by_email("new@example.io").unwrap().user.user_name, "a");
+
+        assert!(registry.remove("b").is_some());
+        registry.register("c", "b@example.io").unwrap();
+        let names: Vec<&str> = registry.accounts().map(|account| account.user.user_name.as_str()).collect();
+        assert_eq!(names, ["a", "c"]);
+    }
+
+    #[test]
+    fn wrong_passwords_lock_the_account() {
+        let lockout = Lockout {
+            max_attempts: 3,
+            duration: Duration::from_secs(60),
+        };
+        let mut registry = Registry::new().with_iterations(10).with_lockout(lockout);
+        registry.register_at("User X", "x@example.io", at(0)).unwrap();
+        assert_eq!(
+            registry.authenticate_at("User X", "whatever!", at(1)),
+            Err(RegistryError::NoPassword(String::from("User X")))
+        );
+        assert!(matches!(registry.set_password("User X", "short"), Err(RegistryError::InvalidPassword(_))));
+        registry.set_password("User X", "correct horse").unwrap();
+        assert_eq!(registry.authenticate_at("User X", "correct horse", at(2)), Ok(1));
+
+        assert_eq!(
+            registry.authenticate_at("User X", "wrong horse", at(3)),
+            Err(RegistryError::WrongPassword { attempts_left: 2 })
+        );
+        assert_eq!(
+            registry.authenticate_at("User X", "wrong horse", at(4)),
+            Err(RegistryError::WrongPassword { attempts_left: 1 })
+        );
+        let locked = Err(RegistryError::LockedOut { until: at(65) });
+        assert_eq!(registry.authenticate_at("User X", "wrong horse", at(5)), locked);
+        // even the right password is turned away while the account is locked
+        assert_eq!(registry.authenticate_at("User X", "correct horse", at(64)), locked);
+        assert_eq!(registry.authenticate_at("User X", "correct horse", at(65)), Ok(2));
+        assert_eq!(registry.by_name("User X").unwrap().failed_attempts(), 0);
+
+        for second in 66..69 {
+            let _ = registry.authenticate_at("User X", "wrong horse", at(second));
+        }
+        registry.unlock("User X").unwrap();
+        assert_eq!(registry.authenticate_at("User X", "correct horse", at(70)), Ok(3));
+    }
+
+    #[test]
+    fn a_lockout_longer_than_the_clock_lasts_until_unlocked() {
+        let lockout = Lockout {
+            max_attempts: 1,
+            duration: Duration::MAX,
+        };
+        let mut registry = Registry::new().with_iterations(10).with_lockout(lockout);
+        registry.register_at("User X", "x@example.io", at(0)).unwrap();
+        registry.set_password("User X", "correct horse").unwrap();
+        assert!(matches!(
+            registry.authenticate_at("User X", "wrong horse", at(1)),
+            Err(RegistryError::LockedOut { .. })
+        ));
+        let until = registry.by_name("User X").unwrap().locked_until().unwrap();
+        assert!(until > at(1) + Duration::from_secs(1_000_000 * 365 * 24 * 60 * 60));
+        assert!(registry.authenticate_at("User X", "correct horse", at(1_000_000_000)).is_err());
+        registry.unlock("User X").unwrap();
+        assert_eq!(registry.authenticate_at("User X", "correct horse", at(2)), Ok(1));
+    }
+
+    #[test]
+    fn sign_in_does_not_skip_the_password_or_the_lockout() {
+        let lockout = Lockout {
+            max_attempts: 1,
+            duration: Duration::from_secs(60),
+        };
+        let mut registry = Registry::new().with_iterations(10).with_lockout(lockout);
+        registry.register_at("User X", "x@example.io", at(0)).unwrap();
+        registry.set_password("User X", "correct horse").unwrap();
+        assert_eq!(
+            registry.sign_in_at("User X", at(1)),
+            Err(RegistryError::PasswordRequired(String::from("User X")))
+        );
+        assert_eq!(registry.by_name("User X").unwrap().user.sign_in_count, 0);
+
+        // a lockout of an account without a password, like one restored from a file
+        registry.register_at("User Y", "y@example.io", at(0)).unwrap();
+        registry.account_mut("User Y").unwrap().locked_until = Some(at(60));
+        let locked = Err(RegistryError::LockedOut { until: at(60) });
+        assert_eq!(registry.sign_in_at("User Y", at(59)), locked);
+        assert_eq!(registry.sign_in_at("User Y", at(60)), Ok(1));
+        assert_eq!(registry.by_name("User Y").unwrap().locked_until(), None);
+    }
+}
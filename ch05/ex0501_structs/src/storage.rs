@@ -0,0 +1,587 @@
+// saving the accounts of a registry as CSV (RFC 4180) or JSON and loading them again
+// every file says which schema version it was written with, in CSV as a comment in the first line
+//
+//     # users schema 3
+//     user_name,email,active,sign_in_count,created_at,last_sign_in,password
+//
+// and in JSON as the "schema" member next to the "users" array
+// files of older versions are migrated while they are loaded, the fields that
+// didn't exist yet get a default: accounts from before created_at was saved were created
+// at the time they are loaded, and they have no last sign-in and no password yet
+//
+// a row that is invalid or a duplicate of an account that is already there is skipped
+// and reported with its line number, the rest of the file is still loaded
+// times are saved as seconds since 1970 (unix time), failed attempts and lockouts are not saved
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::json::{self, Json, Value};
+use crate::password::PasswordHash;
+use crate::registry::{Account, Registry, RegistryError};
+use crate::user::User;
+
+/// the schema version files are written with
+pub const SCHEMA: u32 = 3;
+
+// the columns of every schema version, each one adds to the one before
+// 1: the fields of User, files from before there was a schema comment
+// 2: when the account was created
+// 3: the last sign-in and the password hash
+const COLUMNS: [&[&str]; 3] = [
+    &["user_name", "email", "active", "sign_in_count"],
+    &["user_name", "email", "active", "sign_in_count", "created_at"],
+    &["user_name", "email", "active", "sign_in_count", "created_at", "last_sign_in", "password"],
+];
+
+fn columns(version: u32) -> &'static [&'static str] {
+    COLUMNS[version as usize - 1]
+}
+
+const SCHEMA_COMMENT: &str = "# users schema ";
+
+/// problems with a whole file, nothing of it is loaded
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    UnknownFormat(String),
+    UnsupportedSchema { line: usize, version: String },
+    BadHeader { line: usize, expected: String },
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::Io(error) => write!(f, "{}", error),
+            StorageError::UnknownFormat(path) => write!(f, "{} doesn't end in .csv or .json", path),
+            StorageError::UnsupportedSchema { line, version } => write!(
+                f,
+                "line {}: schema version {:?} isn't supported, only 1 to {}",
+                line, version, SCHEMA
+            ),
+            StorageError::BadHeader { line, expected } => {
+                write!(f, "line {}: the column header should be {}", line, expected)
+            }
+            StorageError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(error: io::Error) -> StorageError {
+        StorageError::Io(error)
+    }
+}
+
+impl From<json::SyntaxError> for StorageError {
+    fn from(error: json::SyntaxError) -> StorageError {
+        StorageError::Syntax {
+            line: error.line,
+            message: error.message,
+        }
+    }
+}
+
+/// what is wrong with a single row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowError {
+    FieldCount { expected: usize, found: usize },
+    NotAnObject,
+    MissingField(&'static str),
+    InvalidField { field: &'static str, value: String },
+    // the registry didn't take the account, like a name that is already taken
+    Rejected(RegistryError),
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RowError::FieldCount { expected, found } => write!(f, "{} fields instead of {}", found, expected),
+            RowError::NotAnObject => write!(f, "a user has to be a JSON object"),
+            RowError::MissingField(field) => write!(f, "{} is missing", field),
+            RowError::InvalidField { field, value } => write!(f, "{:?} isn't a valid {}", value, field),
+            RowError::Rejected(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// a skipped row and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub line: usize,
+    pub error: RowError,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+fn to_seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |duration| duration.as_secs())
+}
+
+// None for a time too far in the future for the clock of the system
+fn from_seconds(seconds: u64) -> Option<SystemTime> {
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(seconds))
+}
+
+// the fields of one row by column name, None for an empty or null field
+type Fields = HashMap<&'static str, Option<String>>;
+
+// the fields of the current schema in its column order
+fn fields_of(account: &Account) -> [Option<String>; 7] {
+    let user = &account.user;
+    [
+        Some(user.user_name.clone()),
+        Some(user.email.clone()),
+        Some(user.active.to_string()),
+        Some(user.sign_in_count.to_string()),
+        Some(to_seconds(account.created_at).to_string()),
+        account.last_sign_in().map(|time| to_seconds(time).to_string()),
+        account.password().map(PasswordHash::to_string),
+    ]
+}
+
+// adds the fields a row of an older version doesn't have yet
+fn migrate(fields: &mut Fields, version: u32, now: SystemTime) {
+    if version < 2 {
+        fields.insert("created_at", Some(to_seconds(now).to_string()));
+    }
+    if version < 3 {
+        fields.insert("last_sign_in", None);
+        fields.insert("password", None);
+    }
+}
+
+// the row, migrated to the current version, as an account of the registry
+fn restore(registry: &mut Registry, mut fields: Fields, version: u32, now: SystemTime) -> Result<(), RowError> {
+    migrate(&mut fields, version, now);
+    let mut take = |field: &'static str| fields.remove(field).flatten();
+    let required = |field: &'static str, value: Option<String>| value.ok_or(RowError::MissingField(field));
+    fn parse<T: std::str::FromStr>(field: &'static str, value: String) -> Result<T, RowError> {
+        value.parse().map_err(|_| RowError::InvalidField { field, value })
+    }
+    fn time(field: &'static str, value: String) -> Result<SystemTime, RowError> {
+        let seconds = parse(field, value.clone())?;
+        from_seconds(seconds).ok_or(RowError::InvalidField { field, value })
+    }
+
+    let user = User {
+        user_name: required("user_name", take("user_name"))?,
+        email: required("email", take("email"))?,
+        active: parse("active", required("active", take("active"))?)?,
+        sign_in_count: parse("sign_in_count", required("sign_in_count", take("sign_in_count"))?)?,
+    };
+    let created_at = time("created_at", required("created_at", take("created_at"))?)?;
+    let last_sign_in = take("last_sign_in")
+        .map(|seconds| time("last_sign_in", seconds))
+        .transpose()?;
+    let password = take("password")
+        .map(|hash| parse::<PasswordHash>("password", hash))
+        .transpose()?;
+    registry
+        .restore(user, created_at, last_sign_in, password)
+        .map(|_| ())
+        .map_err(RowError::Rejected)
+}
+
+// a CSV field, in quotes if it has a comma, a quote or a line break
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        String::from(text)
+    }
+}
+
+// the records of a CSV text with the line each one starts on
+// a quoted field can span lines, two quotes in quotes are one quote
+fn csv_records(text: &str, first_line: usize) -> Result<Vec<(usize, Vec<String>)>, StorageError> {
+    let mut records = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = first_line;
+    while chars.peek().is_some() {
+        let start = line;
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        loop {
+            match chars.next() {
+                None if quoted => {
+                    return Err(StorageError::Syntax {
+                        line: start,
+                        message: String::from("a quoted field doesn't end"),
+                    })
+                }
+                None => {
+                    fields.push(field);
+                    break;
+                }
+                Some('"') if quoted => {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        field.push('"');
+                    } else {
+                        quoted = false;
+                    }
+                }
+                Some('"') if field.is_empty() => quoted = true,
+                Some('\n') if quoted => {
+                    line += 1;
+                    field.push('\n');
+                }
+                Some(c) if quoted => field.push(c),
+                Some(',') => fields.push(std::mem::take(&mut field)),
+                Some('\r') if chars.peek() == Some(&'\n') => {}
+                Some('\n') => {
+                    line += 1;
+                    fields.push(field);
+                    break;
+                }
+                Some(c) => field.push(c),
+            }
+        }
+        // empty lines are skipped
+        if fields.len() > 1 || !fields[0].is_empty() {
+            records.push((start, fields));
+        }
+    }
+    Ok(records)
+}
+
+fn parse_schema(version: &str, line: usize) -> Result<u32, StorageError> {
+    version
+        .trim()
+        .parse::<u32>()
+        .ok()
+        .filter(|version| (1..=SCHEMA).contains(version))
+        .ok_or_else(|| StorageError::UnsupportedSchema {
+            line,
+            version: String::from(version.trim()),
+        })
+}
+
+/// all accounts as CSV, alphabetically by user name
+pub fn export_csv(registry: &Registry) -> String {
+    let mut csv = format!("{}{}\n{}\n", SCHEMA_COMMENT, SCHEMA, columns(SCHEMA).join(","));
+    for account in registry.accounts() {
+        let fields: Vec<String> = fields_of(account)
+            .iter()
+            .map(|field| csv_field(field.as_deref().unwrap_or("")))
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// adds the accounts of a CSV text of any schema version to the registry
+/// returns the rows that were skipped, now is the time accounts from version 1 were created at
+pub fn import_csv(registry: &mut Registry, text: &str, now: SystemTime) -> Result<Vec<Problem>, StorageError> {
+    // a file without the schema comment is from before there were versions
+    let (version, rest, first_line) = match text.strip_prefix('#') {
+        Some(_) => {
+            let (comment, rest) = text.split_once('\n').unwrap_or((text, ""));
+            let version = comment
+                .trim_end()
+                .strip_prefix(SCHEMA_COMMENT)
+                .ok_or_else(|| StorageError::UnsupportedSchema {
+                    line: 1,
+                    version: String::from(comment.trim_end()),
+                })?;
+            (parse_schema(version, 1)?, rest, 2)
+        }
+        None => (1, text, 1),
+    };
+
+    let mut records = csv_records(rest, first_line)?.into_iter();
+    let columns = columns(version);
+    let header_ok = match records.next() {
+        Some((_, header)) => header.iter().map(|name| name.trim()).eq(columns.iter().copied()),
+        None => false,
+    };
+    if !header_ok {
+        return Err(StorageError::BadHeader {
+            line: first_line,
+            expected: columns.join(","),
+        });
+    }
+
+    let mut problems = Vec::new();
+    for (line, record) in records {
+        let row = if record.len() != columns.len() {
+            Err(RowError::FieldCount {
+                expected: columns.len(),
+                found: record.len(),
+            })
+        } else {
+            let fields: Fields = columns
+                .iter()
+                .zip(record)
+                .map(|(&column, value)| (column, Some(value).filter(|value| !value.is_empty())))
+                .collect();
+            restore(registry, fields, version, now)
+        };
+        if let Err(error) = row {
+            problems.push(Problem { line, error });
+        }
+    }
+    Ok(problems)
+}
+
+/// all accounts as JSON, one user per line, alphabetically by user name
+pub fn export_json(registry: &Registry) -> String {
+    let columns = columns(SCHEMA);
+    let users: Vec<String> = registry
+        .accounts()
+        .map(|account| {
+            let fields = fields_of(account);
+            let members: Vec<String> = columns
+                .iter()
+                .zip(fields)
+                .enumerate()
+                .map(|(index, (column, field))| {
+                    let value = match (index, field) {
+                        (_, None) => String::from("null"),
+                        // active, sign_in_count, created_at and last_sign_in aren't strings
+                        (2..=5, Some(field)) => field,
+                        (_, Some(field)) => json::quote(&field),
+                    };
+                    format!("{}: {}", json::quote(column), value)
+                })
+                .collect();
+            format!("    {{{}}}", members.join(", "))
+        })
+        .collect();
+    let users = if users.is_empty() {
+        String::from("[]")
+    } else {
+        format!("[\n{}\n  ]", users.join(",\n"))
+    };
+    format!("{{\n  \"schema\": {},\n  \"users\": {}\n}}\n", SCHEMA, users)
+}
+
+/// adds the accounts of a JSON text of any schema version to the registry, like import_csv
+pub fn import_json(registry: &mut Registry, text: &str, now: SystemTime) -> Result<Vec<Problem>, StorageError> {
+    let root = json::parse(text)?;
+    let version = match root.get("schema") {
+        // a file without a schema is from before there were versions
+        None => 1,
+        Some(Json {
+            line,
+            value: Value::Number(version),
+        }) => parse_schema(version, *line)?,
+        Some(schema) => {
+            return Err(StorageError::UnsupportedSchema {
+                line: schema.line,
+                version: format!("{:?}", schema.value),
+            })
+        }
+    };
+    let users = match root.get("users") {
+        Some(Json {
+            value: Value::Array(users),
+            ..
+        }) => users,
+        _ => {
+            return Err(StorageError::Syntax {
+                line: root.line,
+                message: String::from("the file needs a \"users\" array"),
+            })
+        }
+    };
+
+    let mut problems = Vec::new();
+    for user in users {
+        let row = match user.value {
+            Value::Object(_) => columns(version)
+                .iter()
+                .map(|&column| {
+                    let value = match user.get(column).map(|json| &json.value) {
+                        None | Some(Value::Null) => None,
+                        Some(Value::Bool(value)) => Some(value.to_string()),
+                        Some(Value::Number(value) | Value::String(value)) => Some(value.clone()),
+                        Some(other) => {
+                            return Err(RowError::InvalidField {
+                                field: column,
+                                value: format!("{:?}", other),
+                            })
+                        }
+                    };
+                    Ok((column, value))
+                })
+                .collect::<Result<Fields, RowError>>()
+                .and_then(|fields| restore(registry, fields, version, now)),
+            _ => Err(RowError::NotAnObject),
+        };
+        if let Err(error) = row {
+            problems.push(Problem { line: user.line, error });
+        }
+    }
+    Ok(problems)
+}
+
+// csv or json, by the extension of the path
+fn is_csv(path: &Path) -> Result<bool, StorageError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("csv") => Ok(true),
+        Some(extension) if extension.eq_ignore_ascii_case("json") => Ok(false),
+        _ => Err(StorageError::UnknownFormat(path.display().to_string())),
+    }
+}
+
+/// writes the registry to a .csv or a .json file
+pub fn save<P: AsRef<Path>>(registry: &Registry, path: P) -> Result<(), StorageError> {
+    let path = path.as_ref();
+    let text = if is_csv(path)? {
+        export_csv(registry)
+    } else {
+        export_json(registry)
+    };
+    fs::write(path, text)?;
+    Ok(())
+}
+
+/// adds the accounts of a .csv or a .json file to the registry, returns the rows that were skipped
+pub fn load<P: AsRef<Path>>(registry: &mut Registry, path: P) -> Result<Vec<Problem>, StorageError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    if is_csv(path)? {
+        import_csv(registry, &text, SystemTime::now())
+    } else {
+        import_json(registry, &text, SystemTime::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        from_seconds(seconds).unwrap()
+    }
+
+    fn sample() -> Registry {
+        let mut registry = Registry::new().with_iterations(2);
+        registry.register_at("Smith, \"Jo\"", "jo@example.io", at(100)).unwrap();
+        registry.register_at("User Y", "the_last_man@example.io", at(200)).unwrap();
+        registry.set_password("User Y", "correct horse").unwrap();
+        registry.authenticate_at("User Y", "correct horse", at(300)).unwrap();
+        registry.deactivate("User Y").unwrap();
+        registry
+    }
+
+    fn assert_same_accounts(a: &Registry, b: &Registry) {
+        let a: Vec<_> = a.accounts().map(fields_of).collect();
+        let b: Vec<_> = b.accounts().map(fields_of).collect();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn accounts_round_trip_through_csv_and_json() {
+        let registry = sample();
+        let csv = export_csv(&registry);
+        assert!(csv.starts_with("# users schema 3\nuser_name,email,"));
+        assert!(csv.contains("\"Smith, \"\"Jo\"\"\",jo@example.io,true,0,100,,\n"));
+
+        let mut from_csv = Registry::new();
+        assert_eq!(import_csv(&mut from_csv, &csv, at(0)).unwrap(), []);
+        assert_same_accounts(&registry, &from_csv);
+        assert!(from_csv.by_name("User Y").unwrap().password().unwrap().verify("correct horse"));
+
+        let json = export_json(&registry);
+        let mut from_json = Registry::new();
+        assert_eq!(import_json(&mut from_json, &json, at(0)).unwrap(), []);
+        assert_same_accounts(&registry, &from_json);
+        assert_eq!(export_json(&Registry::new()), "{\n  \"schema\": 3,\n  \"users\": []\n}\n");
+    }
+
+    #[test]
+    fn old_versions_are_migrated() {
+        // version 1 has no schema comment and no created_at
+        let csv = "user_name,email,active,sign_in_count\nUser X,x@example.io,true,4\n";
+        let mut registry = Registry::new();
+        assert_eq!(import_csv(&mut registry, csv, at(1000)).unwrap(), []);
+        let account = registry.by_name("User X").unwrap();
+        assert_eq!((account.created_at, account.user.sign_in_count), (at(1000), 4));
+        assert!(!account.has_password());
+
+        let json = r#"{"schema": 2, "users": [
+            {"user_name": "User Z", "email": "z@example.io", "active": false, "sign_in_count": 0, "created_at": 50}
+        ]}"#;
+        assert_eq!(import_json(&mut registry, json, at(1000)).unwrap(), []);
+        assert_eq!(registry.by_name("User Z").unwrap().created_at, at(50));
+
+        assert!(matches!(
+            import_csv(&mut registry, "# users schema 9\n", at(0)),
+            Err(StorageError::UnsupportedSchema { line: 1, .. })
+        ));
+        assert!(matches!(
+            import_csv(&mut registry, "# users schema 2\nuser_name,email\n", at(0)),
+            Err(StorageError::BadHeader { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn bad_rows_are_reported_with_their_line() {
+        let csv = "# users schema 2\n\
+                   user_name,email,active,sign_in_count,created_at\n\
+                   a,a@example.io,true,1,10\n\
+                   \"multi\nline\",b@example.io,true,1,10\n\
+                   A,other@example.io,true,1,10\n\
+                   c,c@example,true,1,10\n\
+                   d,d@example.io,maybe,1,10\n\
+                   e,e@example.io,true\n\
+                   f,A@example.io,true,1,10\n\
+                   g,g@example.io,true,1,18446744073709551615\n";
+        let mut registry = Registry::new();
+        let problems = import_csv(&mut registry, csv, at(0)).unwrap();
+        let lines: Vec<usize> = problems.iter().map(|problem| problem.line).collect();
+        // the user name with a line break starts on line 4 and ends on 5
+        assert_eq!(lines, [4, 6, 7, 8, 9, 10, 11]);
+        assert_eq!(problems[1].error, RowError::Rejected(RegistryError::UserNameTaken(String::from("A"))));
+        assert_eq!(
+            problems[3].to_string(),
+            "line 8: \"maybe\" isn't a valid active"
+        );
+        assert_eq!(problems[4].error, RowError::FieldCount { expected: 5, found: 3 });
+        assert!(matches!(problems[5].error, RowError::Rejected(RegistryError::EmailTaken(_))));
+        // a time the clock can't hold is an invalid field, not a panic
+        assert_eq!(
+            problems[6].to_string(),
+            "line 11: \"18446744073709551615\" isn't a valid created_at"
+        );
+        assert_eq!(registry.len(), 1);
+
+        let json = "{\"users\": [\n  {\"user_name\": \"x\", \"email\": \"x@example.io\", \"active\": true, \"sign_in_count\": 1},\n  {\"user_name\": \"y\"},\n  7\n]}";
+        let problems = import_json(&mut registry, json, at(0)).unwrap();
+        assert_eq!(
+            problems,
+            [
+                Problem {
+                    line: 3,
+                    error: RowError::MissingField("email")
+                },
+                Problem {
+                    line: 4,
+                    error: RowError::NotAnObject
+                },
+            ]
+        );
+    }
+}
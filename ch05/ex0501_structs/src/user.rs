@@ -0,0 +1,12 @@
+// the User struct of the example, moved here so the registry can manage users
+
+/// Structs can hold multiple related values that can be of different types
+/// each piece of data has to be named so it’s clear what the values mean
+/// the values of the data an instance can be accessed by the field names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub active: bool,
+    pub user_name: String,
+    pub email: String,
+    pub sign_in_count: u64,
+}
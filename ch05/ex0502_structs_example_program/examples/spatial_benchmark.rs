@@ -0,0 +1,51 @@
+// compares the window queries of the R-tree with checking every rectangle
+// cargo run --release --example spatial_benchmark
+use std::time::Instant;
+
+use ex0502_structs_example_program::random::Lcg;
+use ex0502_structs_example_program::rectangle::{Placed, Rectangle};
+use ex0502_structs_example_program::spatial::{scan, RTree};
+
+// rectangles that look scattered, from a linear congruential generator
+fn scattered(count: usize, seed: u64) -> Vec<Placed> {
+    let mut random = Lcg::new(seed);
+    let mut next = move |max| random.below(max);
+    (0..count)
+        .map(|_| Rectangle::new(next(50) + 1, next(50) + 1).at(next(10_000), next(10_000)))
+        .collect()
+}
+
+fn main() {
+    let windows = scattered(1000, 7)
+        .into_iter()
+        .map(|window| Rectangle::new(window.size.width * 4, window.size.height * 4).at(window.x, window.y))
+        .collect::<Vec<Placed>>();
+
+    for count in [1_000, 10_000, 100_000] {
+        let entries: Vec<(Placed, usize)> = scattered(count, 42).into_iter().zip(0..).collect();
+
+        let start = Instant::now();
+        let tree = RTree::bulk_load(entries.clone());
+        let build = start.elapsed();
+
+        let start = Instant::now();
+        let found_by_tree: usize = windows.iter().map(|window| tree.query(window).len()).sum();
+        let with_tree = start.elapsed();
+
+        let start = Instant::now();
+        let found_by_scan: usize = windows.iter().map(|window| scan(&entries, window).len()).sum();
+        let with_scan = start.elapsed();
+
+        assert_eq!(found_by_tree, found_by_scan);
+        println!(
+            "{:>7} rectangles, {} windows: R-tree {:?} (built in {:?}), scan {:?}, {:.1}x faster, {} found",
+            count,
+            windows.len(),
+            with_tree,
+            build,
+            with_scan,
+            with_scan.as_secs_f64() / with_tree.as_secs_f64().max(f64::EPSILON),
+            found_by_tree
+        );
+    }
+}
@@ -0,0 +1,17 @@
+//! the library part of the rectangles example program
+//! the Rectangle struct moved here from main.rs so it can grow without making main.rs longer
+
+/// rectangles with a size and rectangles placed at a position
+pub mod rectangle;
+
+/// packing many rectangles onto as few sheets as possible
+pub mod packing;
+
+/// drawing rectangles and points as SVG images or ASCII art
+pub mod render;
+
+/// an R-tree to find placed rectangles in a window or near a point quickly
+pub mod spatial;
+
+/// a small generator of numbers that look random, for test data and the benchmark
+pub mod random;
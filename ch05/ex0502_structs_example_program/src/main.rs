@@ -1,46 +1,14 @@
-/// example program that calculates the area of a rectangle
-/// let's start with using single variables and then refactor the program to use structs
-/// the rectangles program will take the width and height of a rectangle 
-/// specified in pixels and calculate the area of the rectangle
+//! example program that calculates the area of a rectangle
+//! let's start with using single variables and then refactor the program to use structs
+//! the rectangles program will take the width and height of a rectangle 
+//! specified in pixels and calculate the area of the rectangle
 
-/// a struct to calculate a rectangle from
-#[derive(Debug)]
-struct Rectangle {
-    width: u32,
-    height: u32,
-}
-
-/// definining an area method on the Rectangle struct itself
-/// defining methods for a struct starts with an impl (implementation) block
-/// all functions defined within an impl block are called associated functions
-/// multiple impl blocks are allowed too
-impl Rectangle {
-    // we can define associated functions that don’t have self as their first parameter 
-    // (and thus are not methods) 
-    // functions that aren’t methods are often used for constructors
-    // that will return a new instance of the struct
-    fn square(size: u32) -> Self {
-        Self {
-            width: size,
-            height: size,
-        }
-    }
-
-    // the &self in the signature is short for self: &Self
-    fn area(&self) -> u32 {
-        self.width * self.height
-    }
-
-    // a method can have the same name as one of the struct’s fields
-    fn width(&self) -> bool {
-        self.width > 0
-    }
-
-    // implementing a method on Rectangle that can take another Rectangle instance as a parameter
-    fn can_hold(&self, other_one: &Rectangle) -> bool {
-        self.width > other_one.width && self.height > other_one.height
-    }
-}
+// the Rectangle struct and its methods live in the library (src/rectangle.rs)
+// next to Placed, a rectangle at a position that can intersect with other ones
+use ex0502_structs_example_program::packing::{Heuristic, Packer};
+use ex0502_structs_example_program::rectangle::Rectangle;
+use ex0502_structs_example_program::render::Scene;
+use ex0502_structs_example_program::spatial::RTree;
 
 fn main() {
     // (1) calculating the area of a rectangle in a very simple way
@@ -84,6 +52,39 @@ fn main() {
     
     // using the can_hold method
     println!("Can rect2 hold rect3? {}", rect2.can_hold(&rect3));
+    // turned by 90° a rectangle may fit where it didn't before
+    let plank = Rectangle::new(200, 20);
+    println!("Can rect2 hold the plank? {}, rotated? {}", rect2.can_hold(&plank), rect2.can_hold_rotated(&plank));
+
+    // placing rectangles at a position, they can overlap
+    let placed2 = rect2.at(0, 0);
+    let placed3 = rect3.at(100, 100);
+    println!("rect2 and rect3 overlap in {:?}", placed2.intersection(&placed3));
+    println!("together they cover {:?}", placed2.union(&placed3));
+
+    // cutting rectangles out of sheets of 300 x 300 pixel, as few sheets as possible
+    let parts = [rect2, rect3, plank, Rectangle::square(90), Rectangle::new(120, 80), Rectangle::new(250, 40)];
+    let packing = Packer::new(Rectangle::square(300))
+        .heuristic(Heuristic::MaxRects)
+        .allow_rotation(true)
+        .pack(&parts);
+    for placement in packing.placements() {
+        println!("part {} goes to sheet {} at {:?}", placement.index, placement.sheet, placement.placed);
+    }
+    println!("{} sheets used, {:.0}% covered", packing.sheets.len(), packing.utilization() * 100.0);
+    // drawing the first sheet in the terminal, Scene::to_svg draws it as an image
+    if let Some(sheet) = packing.sheets.first() {
+        print!("{}", Scene::from_sheet(sheet).to_ascii(60));
+
+        // an R-tree finds the placed parts in a window without checking all of them
+        // every sheet has its own positions, so the index holds the parts of this sheet only
+        let index = RTree::bulk_load(sheet.placements.iter().map(|p| (p.placed, p.index)).collect());
+        let window = Rectangle::square(100).at(0, 0);
+        println!("{} parts overlap the top left corner of the first sheet", index.query(&window).len());
+        if let Some((placed, part)) = index.nearest(250, 250) {
+            println!("part {} at {}, {} is the closest one to 250, 250", part, placed.x, placed.y);
+        }
+    }
 
     // calling an associated function that works like a constructor with the :: syntax
     let sq = Rectangle::square(3);
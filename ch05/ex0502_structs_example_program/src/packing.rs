@@ -0,0 +1,363 @@
+// packing many rectangles into sheets of the same size, like cutting parts out of wooden boards
+// can_hold only answers whether one rectangle fits into another
+// the packer places every rectangle at a position on a sheet so that none of them overlap
+// and it tries to use as few sheets as possible
+//
+// every sheet keeps a list of free rectangles, the space that is still empty
+// a rectangle goes into the free rectangle that fits it best, over all sheets that are already open
+// a new sheet is only started when it fits nowhere else
+// placing it splits the free rectangle, how depends on the heuristic:
+// -> Guillotine cuts the rest of the free rectangle into two, every cut goes through the whole piece
+//    like a guillotine paper cutter, the parts can be cut out with straight cuts
+// -> MaxRects keeps every largest empty rectangle, they may overlap each other
+//    it packs tighter but the parts may need more complicated cuts
+use crate::rectangle::{Placed, Rectangle};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Heuristic {
+    #[default]
+    Guillotine,
+    MaxRects,
+}
+
+/// where one of the rectangles went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    // the index of the rectangle in the list that was packed
+    pub index: usize,
+    // the number of the sheet, starting with 0
+    pub sheet: usize,
+    // the position on the sheet, with the size as it lies there
+    pub placed: Placed,
+    // true if it was turned by 90° to fit
+    pub rotated: bool,
+}
+
+/// one sheet with the rectangles on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sheet {
+    pub size: Rectangle,
+    pub placements: Vec<Placement>,
+    free: Vec<Placed>,
+}
+
+impl Sheet {
+    fn new(size: Rectangle) -> Sheet {
+        Sheet {
+            size,
+            placements: Vec::new(),
+            free: vec![size.at(0, 0)],
+        }
+    }
+
+    pub fn used_area(&self) -> u64 {
+        self.placements.iter().map(|placement| placement.placed.area()).sum()
+    }
+
+    /// the share of the sheet that is covered, between 0 and 1
+    pub fn utilization(&self) -> f64 {
+        self.used_area() as f64 / self.size.area() as f64
+    }
+
+    // the best free rectangle for the size, a lower score is better
+    fn best_fit(&self, size: Rectangle, heuristic: Heuristic) -> Option<(Score, usize)> {
+        self.free
+            .iter()
+            .enumerate()
+            .filter(|(_, free)| free.size.can_fit(&size))
+            .map(|(index, free)| (score(free.size, size, heuristic), index))
+            .min()
+    }
+
+    fn place(&mut self, free_index: usize, placement: Placement, heuristic: Heuristic) {
+        match heuristic {
+            Heuristic::Guillotine => {
+                let free = self.free.swap_remove(free_index);
+                self.free.extend(guillotine_split(free, placement.placed.size));
+            }
+            Heuristic::MaxRects => maxrects_split(&mut self.free, &placement.placed),
+        }
+        self.placements.push(placement);
+    }
+}
+
+/// the result of packing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packing {
+    pub sheets: Vec<Sheet>,
+    // the indexes of the rectangles that fit on no sheet, even turned, or that have no area
+    pub unplaced: Vec<usize>,
+}
+
+impl Packing {
+    /// every placement of every sheet, in the order of the packed list
+    pub fn placements(&self) -> Vec<Placement> {
+        let mut placements: Vec<Placement> = self
+            .sheets
+            .iter()
+            .flat_map(|sheet| sheet.placements.iter().copied())
+            .collect();
+        placements.sort_by_key(|placement| placement.index);
+        placements
+    }
+
+    /// the share of all sheets that is covered
+    pub fn utilization(&self) -> f64 {
+        let total: u64 = self.sheets.iter().map(|sheet| sheet.size.area()).sum();
+        let used: u64 = self.sheets.iter().map(|sheet| sheet.used_area()).sum();
+        if total == 0 {
+            0.0
+        } else {
+            used as f64 / total as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packer {
+    sheet: Rectangle,
+    heuristic: Heuristic,
+    allow_rotation: bool,
+}
+
+impl Packer {
+    /// a packer for sheets of the given size, with the guillotine heuristic and without rotation
+    pub fn new(sheet: Rectangle) -> Packer {
+        Packer {
+            sheet,
+            heuristic: Heuristic::default(),
+            allow_rotation: false,
+        }
+    }
+
+    pub fn heuristic(mut self, heuristic: Heuristic) -> Packer {
+        self.heuristic = heuristic;
+        self
+    }
+
+    /// the rectangles may be turned by 90°, for materials without a grain direction
+    pub fn allow_rotation(mut self, allow_rotation: bool) -> Packer {
+        self.allow_rotation = allow_rotation;
+        self
+    }
+
+    pub fn pack(&self, rectangles: &[Rectangle]) -> Packing {
+        let mut packing = Packing {
+            sheets: Vec::new(),
+            unplaced: Vec::new(),
+        };
+        // the large rectangles are hardest to place, so they go first while there is most room
+        let mut order: Vec<usize> = (0..rectangles.len()).collect();
+        order.sort_by_key(|&index| {
+            let rectangle = rectangles[index];
+            std::cmp::Reverse((rectangle.area(), rectangle.width.max(rectangle.height)))
+        });
+
+        for index in order {
+            let rectangle = rectangles[index];
+            let fits_at_all = self.sheet.can_fit(&rectangle)
+                || (self.allow_rotation && self.sheet.can_fit(&rectangle.rotated()));
+            if rectangle.area() == 0 || !fits_at_all {
+                packing.unplaced.push(index);
+                continue;
+            }
+            let (sheet, free_index, rotated) = match self.best_fit(&packing.sheets, rectangle) {
+                Some(fit) => fit,
+                None => {
+                    packing.sheets.push(Sheet::new(self.sheet));
+                    let sheet = packing.sheets.len() - 1;
+                    self.best_fit(&packing.sheets[sheet..], rectangle)
+                        .map(|(_, free_index, rotated)| (sheet, free_index, rotated))
+                        .expect("a rectangle that fits the sheet fits an empty sheet")
+                }
+            };
+            let size = if rotated { rectangle.rotated() } else { rectangle };
+            let free = packing.sheets[sheet].free[free_index];
+            let placement = Placement {
+                index,
+                sheet,
+                placed: size.at(free.x, free.y),
+                rotated,
+            };
+            packing.sheets[sheet].place(free_index, placement, self.heuristic);
+        }
+        packing.unplaced.sort_unstable();
+        packing
+    }
+
+    // the sheet, the free rectangle and whether to turn the rectangle, for the best score
+    fn best_fit(&self, sheets: &[Sheet], rectangle: Rectangle) -> Option<(usize, usize, bool)> {
+        let mut orientations = vec![(rectangle, false)];
+        if self.allow_rotation && rectangle.width != rectangle.height {
+            orientations.push((rectangle.rotated(), true));
+        }
+        sheets
+            .iter()
+            .enumerate()
+            .flat_map(|(number, sheet)| {
+                orientations.iter().filter_map(move |&(size, rotated)| {
+                    sheet
+                        .best_fit(size, self.heuristic)
+                        .map(|(score, free_index)| (score, number, free_index, rotated))
+                })
+            })
+            .min()
+            .map(|(_, sheet, free_index, rotated)| (sheet, free_index, rotated))
+    }
+}
+
+// two numbers, compared in order, the lower the better
+type Score = (u64, u64);
+
+fn score(free: Rectangle, size: Rectangle, heuristic: Heuristic) -> Score {
+    let leftover_width = u64::from(free.width - size.width);
+    let leftover_height = u64::from(free.height - size.height);
+    let short_side = leftover_width.min(leftover_height);
+    match heuristic {
+        // best area fit, the free rectangle with the least area left over
+        Heuristic::Guillotine => (free.area() - size.area(), short_side),
+        // best short side fit, the free rectangle with the smallest gap along one side
+        Heuristic::MaxRects => (short_side, leftover_width.max(leftover_height)),
+    }
+}
+
+// the size was placed in the top left corner of free, the rest is cut into two free rectangles
+// the cut goes along the shorter leftover, that keeps the larger of the two parts as large as possible
+fn guillotine_split(free: Placed, size: Rectangle) -> Vec<Placed> {
+    let right_width = free.size.width - size.width;
+    let bottom_height = free.size.height - size.height;
+    let (right, bottom) = if right_width < bottom_height {
+        // the bottom part spans the whole width
+        (
+            Rectangle::new(right_width, size.height),
+            Rectangle::new(free.size.width, bottom_height),
+        )
+    } else {
+        // the right part spans the whole height
+        (
+            Rectangle::new(right_width, free.size.height),
+            Rectangle::new(size.width, bottom_height),
+        )
+    };
+    [right.at(free.x + size.width, free.y), bottom.at(free.x, free.y + size.height)]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+// every free rectangle the placed one overlaps is replaced by its up to four parts around it
+// then the free rectangles inside other free rectangles are dropped, only the largest ones stay
+fn maxrects_split(free: &mut Vec<Placed>, placed: &Placed) {
+    let mut parts = Vec::new();
+    free.retain(|space| {
+        if !space.intersects(placed) {
+            return true;
+        }
+        let (left, top) = (space.x, space.y);
+        let (width, height) = (space.size.width, space.size.height);
+        if placed.x > left {
+            parts.push(Rectangle::new(placed.x - left, height).at(left, top));
+        }
+        if placed.right() < space.right() {
+            let right = placed.right() as u32;
+            parts.push(Rectangle::new(space.right() as u32 - right, height).at(right, top));
+        }
+        if placed.y > top {
+            parts.push(Rectangle::new(width, placed.y - top).at(left, top));
+        }
+        if placed.bottom() < space.bottom() {
+            let bottom = placed.bottom() as u32;
+            parts.push(Rectangle::new(width, space.bottom() as u32 - bottom).at(left, bottom));
+        }
+        false
+    });
+    free.extend(parts);
+
+    let mut index = 0;
+    while index < free.len() {
+        let space = free[index];
+        let inside_another = free
+            .iter()
+            .enumerate()
+            .any(|(other, larger)| other != index && larger.contains(&space) && (larger != &space || other < index));
+        if inside_another {
+            free.swap_remove(index);
+        } else {
+            index += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Lcg;
+
+    // no overlaps and everything on its sheet
+    fn assert_valid(packing: &Packing, sheet: Rectangle, rectangles: &[Rectangle]) {
+        let placements = packing.placements();
+        for placement in &placements {
+            assert!(sheet.at(0, 0).contains(&placement.placed), "{:?}", placement);
+            let original = rectangles[placement.index];
+            let expected = if placement.rotated { original.rotated() } else { original };
+            assert_eq!(placement.placed.size, expected);
+        }
+        for (i, a) in placements.iter().enumerate() {
+            for b in &placements[i + 1..] {
+                assert!(a.sheet != b.sheet || !a.placed.intersects(&b.placed), "{:?} {:?}", a, b);
+            }
+        }
+        assert_eq!(placements.len() + packing.unplaced.len(), rectangles.len());
+    }
+
+    // sizes that look random
+    fn sizes(count: usize, seed: u64) -> Vec<Rectangle> {
+        let mut random = Lcg::new(seed);
+        (0..count)
+            .map(|_| Rectangle::new(random.below(40) + 1, random.below(40) + 1))
+            .collect()
+    }
+
+    #[test]
+    fn four_squares_fill_one_sheet() {
+        let sheet = Rectangle::square(10);
+        let squares = [Rectangle::square(5); 4];
+        for heuristic in [Heuristic::Guillotine, Heuristic::MaxRects] {
+            let packing = Packer::new(sheet).heuristic(heuristic).pack(&squares);
+            assert_valid(&packing, sheet, &squares);
+            assert_eq!(packing.sheets.len(), 1);
+            assert_eq!(packing.utilization(), 1.0);
+        }
+    }
+
+    #[test]
+    fn rotation_saves_a_sheet() {
+        let sheet = Rectangle::new(10, 4);
+        let parts = [Rectangle::new(10, 2), Rectangle::new(2, 10)];
+        let without = Packer::new(sheet).pack(&parts);
+        assert_eq!(without.sheets.len(), 1);
+        assert_eq!(without.unplaced, [1]);
+
+        let with = Packer::new(sheet).allow_rotation(true).pack(&parts);
+        assert_valid(&with, sheet, &parts);
+        assert_eq!(with.sheets.len(), 1);
+        assert!(with.placements()[1].rotated);
+        assert!(with.unplaced.is_empty());
+    }
+
+    #[test]
+    fn random_parts_never_overlap() {
+        let sheet = Rectangle::new(100, 80);
+        let parts = sizes(200, 7);
+        for heuristic in [Heuristic::Guillotine, Heuristic::MaxRects] {
+            for rotation in [false, true] {
+                let packing = Packer::new(sheet).heuristic(heuristic).allow_rotation(rotation).pack(&parts);
+                assert_valid(&packing, sheet, &parts);
+                assert!(packing.unplaced.is_empty());
+                // the parts cover a bit more than 10 sheets, a good packing doesn't need many more
+                let needed = parts.iter().map(|part| part.area()).sum::<u64>().div_ceil(sheet.area());
+                assert!(packing.sheets.len() as u64 <= needed * 3 / 2, "{:?} {}", heuristic, packing.sheets.len());
+            }
+        }
+    }
+}
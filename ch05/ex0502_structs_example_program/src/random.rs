@@ -0,0 +1,24 @@
+// a linear congruential generator, the constants are the ones of Knuth's MMIX
+// it isn't random enough for anything but test data and benchmarks that look scattered
+// the same seed always gives the same numbers, so a failing test can be run again
+
+#[derive(Debug, Clone)]
+pub struct Lcg {
+    state: u64,
+}
+
+impl Lcg {
+    pub fn new(seed: u64) -> Lcg {
+        Lcg { state: seed }
+    }
+
+    /// the next number below max, max must not be 0
+    pub fn below(&mut self, max: u32) -> u32 {
+        self.state = self
+            .state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        // the high bits are the most random ones
+        ((self.state >> 33) % u64::from(max)) as u32
+    }
+}
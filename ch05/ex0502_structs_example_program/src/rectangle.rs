@@ -0,0 +1,276 @@
+// the Rectangle of the example program and rectangles placed at a position
+// a Rectangle only has a size, a Placed rectangle also has the position of its top left corner
+// positions are in pixels like the sizes, x grows to the right and y grows downwards
+//
+// width * height of two u32 values can overflow a u32, 70000 x 70000 already does
+// so areas are u64, the product of two u32 values always fits into a u64
+// for the same reason the right and bottom edges of a placed rectangle are u64
+
+/// a struct to calculate a rectangle from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rectangle {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// definining an area method on the Rectangle struct itself
+/// defining methods for a struct starts with an impl (implementation) block
+/// all functions defined within an impl block are called associated functions
+/// multiple impl blocks are allowed too
+impl Rectangle {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    // we can define associated functions that don’t have self as their first parameter
+    // (and thus are not methods)
+    // functions that aren’t methods are often used for constructors
+    // that will return a new instance of the struct
+    pub fn square(size: u32) -> Self {
+        Self {
+            width: size,
+            height: size,
+        }
+    }
+
+    // the &self in the signature is short for self: &Self
+    pub fn area(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
+    // a method can have the same name as one of the struct’s fields
+    pub fn width(&self) -> bool {
+        self.width > 0
+    }
+
+    /// the same rectangle turned by 90°
+    pub fn rotated(&self) -> Rectangle {
+        Rectangle::new(self.height, self.width)
+    }
+
+    // implementing a method on Rectangle that can take another Rectangle instance as a parameter
+    // the other one has to be strictly smaller in both directions
+    pub fn can_hold(&self, other_one: &Rectangle) -> bool {
+        self.width > other_one.width && self.height > other_one.height
+    }
+
+    /// like can_hold, but the other one may also be turned by 90°
+    pub fn can_hold_rotated(&self, other_one: &Rectangle) -> bool {
+        self.can_hold(other_one) || self.can_hold(&other_one.rotated())
+    }
+
+    /// the other one fits inside, it may be as wide or as high
+    pub fn can_fit(&self, other_one: &Rectangle) -> bool {
+        self.width >= other_one.width && self.height >= other_one.height
+    }
+
+    /// like can_fit, but the other one may also be turned by 90°
+    pub fn can_fit_rotated(&self, other_one: &Rectangle) -> bool {
+        self.can_fit(other_one) || self.can_fit(&other_one.rotated())
+    }
+
+    /// this rectangle with its top left corner at x, y
+    pub fn at(self, x: u32, y: u32) -> Placed {
+        Placed::new(x, y, self)
+    }
+}
+
+/// a rectangle at a position
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Placed {
+    pub x: u32,
+    pub y: u32,
+    pub size: Rectangle,
+}
+
+impl Placed {
+    pub fn new(x: u32, y: u32, size: Rectangle) -> Placed {
+        Placed { x, y, size }
+    }
+
+    pub fn left(&self) -> u64 {
+        u64::from(self.x)
+    }
+
+    pub fn top(&self) -> u64 {
+        u64::from(self.y)
+    }
+
+    // the first column right of the rectangle
+    pub fn right(&self) -> u64 {
+        self.left() + u64::from(self.size.width)
+    }
+
+    // the first row below the rectangle
+    pub fn bottom(&self) -> u64 {
+        self.top() + u64::from(self.size.height)
+    }
+
+    pub fn area(&self) -> u64 {
+        self.size.area()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.area() == 0
+    }
+
+    // the rectangle between the edges, None if it doesn't fit into u32 coordinates
+    fn from_edges(left: u64, top: u64, right: u64, bottom: u64) -> Option<Placed> {
+        Some(Placed::new(
+            u32::try_from(left).ok()?,
+            u32::try_from(top).ok()?,
+            Rectangle::new(
+                u32::try_from(right - left).ok()?,
+                u32::try_from(bottom - top).ok()?,
+            ),
+        ))
+    }
+
+    /// the pixel at x, y is inside
+    pub fn contains_point(&self, x: u32, y: u32) -> bool {
+        let (x, y) = (u64::from(x), u64::from(y));
+        x >= self.left() && x < self.right() && y >= self.top() && y < self.bottom()
+    }
+
+    /// the other rectangle lies inside, it may touch the edges
+    pub fn contains(&self, other: &Placed) -> bool {
+        other.left() >= self.left()
+            && other.top() >= self.top()
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// the other rectangle lies inside without touching any edge
+    pub fn contains_strictly(&self, other: &Placed) -> bool {
+        other.left() > self.left()
+            && other.top() > self.top()
+            && other.right() < self.right()
+            && other.bottom() < self.bottom()
+    }
+
+    /// the rectangles share some area, touching edges don't count
+    pub fn intersects(&self, other: &Placed) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// the area both rectangles cover, None if they don't overlap
+    pub fn intersection(&self, other: &Placed) -> Option<Placed> {
+        let left = self.left().max(other.left());
+        let top = self.top().max(other.top());
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if left >= right || top >= bottom {
+            return None;
+        }
+        Placed::from_edges(left, top, right, bottom)
+    }
+
+    pub fn overlap_area(&self, other: &Placed) -> u64 {
+        self.intersection(other).map_or(0, |overlap| overlap.area())
+    }
+
+    /// the smallest rectangle that covers both
+    /// None if it would be wider or higher than a u32
+    pub fn union(&self, other: &Placed) -> Option<Placed> {
+        Placed::from_edges(
+            self.left().min(other.left()),
+            self.top().min(other.top()),
+            self.right().max(other.right()),
+            self.bottom().max(other.bottom()),
+        )
+    }
+
+    /// cuts the rectangle into a left part that is width wide and the right part that is left
+    /// None unless both parts have some width and the right part starts within u32
+    pub fn split_at_width(&self, width: u32) -> Option<(Placed, Placed)> {
+        if width == 0 || width >= self.size.width {
+            return None;
+        }
+        let left = Placed::new(self.x, self.y, Rectangle::new(width, self.size.height));
+        let right = Placed::new(
+            self.x.checked_add(width)?,
+            self.y,
+            Rectangle::new(self.size.width - width, self.size.height),
+        );
+        Some((left, right))
+    }
+
+    /// cuts the rectangle into a top part that is height high and the bottom part that is left
+    /// None unless both parts have some height and the bottom part starts within u32
+    pub fn split_at_height(&self, height: u32) -> Option<(Placed, Placed)> {
+        if height == 0 || height >= self.size.height {
+            return None;
+        }
+        let top = Placed::new(self.x, self.y, Rectangle::new(self.size.width, height));
+        let bottom = Placed::new(
+            self.x,
+            self.y.checked_add(height)?,
+            Rectangle::new(self.size.width, self.size.height - height),
+        );
+        Some((top, bottom))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn areas_of_large_rectangles_do_not_overflow() {
+        let huge = Rectangle::new(u32::MAX, u32::MAX);
+        assert_eq!(huge.area(), 18_446_744_065_119_617_025);
+        assert_eq!(Rectangle::square(70_000).area(), 4_900_000_000);
+    }
+
+    #[test]
+    fn rotated_rectangles_may_fit() {
+        let door = Rectangle::new(10, 30);
+        let plank = Rectangle::new(25, 5);
+        assert!(!door.can_hold(&plank));
+        assert!(door.can_hold_rotated(&plank));
+        // can_hold is strict, can_fit is not
+        assert!(!door.can_hold(&door));
+        assert!(door.can_fit(&door));
+        assert!(door.can_fit_rotated(&door.rotated()));
+    }
+
+    #[test]
+    fn intersection_union_and_containment() {
+        let a = Rectangle::new(4, 4).at(0, 0);
+        let b = Rectangle::new(4, 4).at(2, 2);
+        assert_eq!(a.intersection(&b), Some(Rectangle::new(2, 2).at(2, 2)));
+        assert_eq!(a.overlap_area(&b), 4);
+        assert_eq!(a.union(&b), Some(Rectangle::new(6, 6).at(0, 0)));
+        // touching edges don't overlap
+        assert!(!a.intersects(&Rectangle::new(4, 4).at(4, 0)));
+
+        let inner = Rectangle::new(2, 2).at(0, 1);
+        assert!(a.contains(&inner));
+        assert!(!a.contains_strictly(&inner));
+        assert!(a.contains_strictly(&Rectangle::new(2, 2).at(1, 1)));
+        assert!(a.contains_point(3, 3) && !a.contains_point(4, 3));
+    }
+
+    #[test]
+    fn edges_beyond_u32_are_handled() {
+        let far = Rectangle::new(10, 10).at(u32::MAX - 5, 0);
+        assert_eq!(far.right(), u64::from(u32::MAX) + 5);
+        assert_eq!(far.union(&Rectangle::new(1, 1).at(0, 0)), None);
+        assert_eq!(
+            far.intersection(&Rectangle::new(10, 10).at(u32::MAX - 2, 0)),
+            Some(Rectangle::new(7, 10).at(u32::MAX - 2, 0))
+        );
+    }
+
+    #[test]
+    fn splitting_keeps_the_area() {
+        let sheet = Rectangle::new(10, 6).at(1, 1);
+        let (left, right) = sheet.split_at_width(4).unwrap();
+        assert_eq!((left.area() + right.area()), sheet.area());
+        assert_eq!(right, Rectangle::new(6, 6).at(5, 1));
+        let (top, bottom) = sheet.split_at_height(1).unwrap();
+        assert_eq!(bottom, Rectangle::new(10, 5).at(1, 2));
+        assert_eq!(top.size.height, 1);
+        assert_eq!(sheet.split_at_width(10), None);
+    }
+}
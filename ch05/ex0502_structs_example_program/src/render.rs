@@ -0,0 +1,276 @@
+// drawing rectangles and points, to check packings and geometry by eye
+// a Scene collects the shapes and draws them as an SVG image or as ASCII art in the terminal
+//
+// the shapes are added with their coordinates and the value they stand for
+// the label shows the name of the value from its Debug output and its area
+// the rectangles of this crate are drawn at their position with add_placed
+// any other Debug value is drawn by passing its coordinates next to it
+// the Rectangle of ch13 and the points of ch10 live in crates of their own, this one doesn't depend on them
+use std::fmt::{Debug, Write as _};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::packing::Sheet;
+use crate::rectangle::Placed;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Rectangle {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        label: String,
+    },
+    Point {
+        x: f64,
+        y: f64,
+        label: String,
+    },
+}
+
+impl Shape {
+    pub fn label(&self) -> &str {
+        match self {
+            Shape::Rectangle { label, .. } | Shape::Point { label, .. } => label,
+        }
+    }
+
+    // left, top, right, bottom
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        match *self {
+            Shape::Rectangle { x, y, width, height, .. } => (x, y, x + width, y + height),
+            Shape::Point { x, y, .. } => (x, y, x, y),
+        }
+    }
+}
+
+/// the name of a value as its Debug output starts, "Rectangle" for Rectangle { width: 3, height: 5 }
+pub fn debug_name<T: Debug>(value: &T) -> String {
+    let debug = format!("{:?}", value);
+    let end = debug.find([' ', '{', '(']).unwrap_or(debug.len());
+    String::from(&debug[..end])
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Scene {
+    shapes: Vec<Shape>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    /// a sheet of a packing with everything placed on it
+    pub fn from_sheet(sheet: &Sheet) -> Scene {
+        let mut scene = Scene::new();
+        scene.add_placed(&sheet.size.at(0, 0));
+        for placement in &sheet.placements {
+            scene.add_placed(&placement.placed);
+        }
+        scene
+    }
+
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+
+    /// a rectangle of the ch05 library, at its position
+    pub fn add_placed(&mut self, placed: &Placed) -> &mut Scene {
+        self.add_rectangle(
+            f64::from(placed.x),
+            f64::from(placed.y),
+            f64::from(placed.size.width),
+            f64::from(placed.size.height),
+            &placed.size,
+        )
+    }
+
+    /// any Debug value that is drawn as a rectangle at the given position and size
+    pub fn add_rectangle<T: Debug>(&mut self, x: f64, y: f64, width: f64, height: f64, value: &T) -> &mut Scene {
+        let label = format!("{} {}x{} area {}", debug_name(value), width, height, width * height);
+        self.shapes.push(Shape::Rectangle {
+            x,
+            y,
+            width,
+            height,
+            label,
+        });
+        self
+    }
+
+    /// any Debug value that is drawn as a point at the given coordinates
+    /// a point has no area, so its label shows the coordinates instead
+    pub fn add_point<T: Debug>(&mut self, x: f64, y: f64, value: &T) -> &mut Scene {
+        let label = format!("{} ({}, {})", debug_name(value), x, y);
+        self.shapes.push(Shape::Point { x, y, label });
+        self
+    }
+
+    // the area covered by all shapes, left, top, right, bottom
+    fn bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        self.shapes.iter().map(Shape::bounds).reduce(|a, b| {
+            (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))
+        })
+    }
+
+    /// the scene as an SVG image, one unit is one pixel
+    pub fn to_svg(&self) -> String {
+        let (left, top, right, bottom) = self.bounds().unwrap_or((0.0, 0.0, 0.0, 0.0));
+        // a margin so the outlines and the points at the edges are visible
+        let margin = 10.0;
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" font-family="monospace" font-size="10">"#,
+            left - margin,
+            top - margin,
+            right - left + 2.0 * margin,
+            bottom - top + 2.0 * margin
+        );
+        for (index, shape) in self.shapes.iter().enumerate() {
+            let label = escape_xml(shape.label());
+            let hue = (index * 67) % 360;
+            let _ = match *shape {
+                Shape::Rectangle { x, y, width, height, .. } => writeln!(
+                    svg,
+                    r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="hsl({},70%,70%)" fill-opacity="0.4" stroke="black"><title>{}</title></rect>"#,
+                    x, y, width, height, hue, label
+                )
+                .and_then(|_| writeln!(svg, r#"  <text x="{}" y="{}">{}</text>"#, x + 2.0, y + 11.0, label)),
+                Shape::Point { x, y, .. } => writeln!(
+                    svg,
+                    r#"  <circle cx="{}" cy="{}" r="3" fill="hsl({},70%,40%)"><title>{}</title></circle>"#,
+                    x, y, hue, label
+                )
+                .and_then(|_| writeln!(svg, r#"  <text x="{}" y="{}">{}</text>"#, x + 5.0, y - 5.0, label)),
+            };
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn write_svg<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+
+    /// the scene scaled down to a grid that is columns characters wide
+    /// every shape is drawn with its own key character and listed with its label below the grid
+    /// characters are about twice as high as wide, so a row stands for twice as many units as a column
+    pub fn to_ascii(&self, columns: usize) -> String {
+        let Some((left, top, right, bottom)) = self.bounds() else {
+            return String::new();
+        };
+        let columns = columns.max(1);
+        let scale = ((right - left) / columns as f64).max((bottom - top) / (2.0 * columns as f64));
+        // a scene of a single point has no size at all
+        let scale = if scale > 0.0 { scale } else { 1.0 };
+        let rows = (((bottom - top) / (2.0 * scale)).ceil() as usize).max(1);
+        let mut grid = vec![vec!['.'; columns]; rows];
+
+        // the cells from the one holding start up to the one before end, but at least one
+        let cells = |start: f64, end: f64, unit: f64, count: usize| {
+            let first = ((start / unit).floor() as usize).min(count - 1);
+            let last = ((end / unit).ceil() as usize).clamp(first + 1, count);
+            first..last
+        };
+        let mut legend = String::new();
+        for (index, shape) in self.shapes.iter().enumerate() {
+            let key = key(index);
+            let (x0, y0, x1, y1) = shape.bounds();
+            let (x0, x1) = (x0 - left, x1 - left);
+            let (y0, y1) = (y0 - top, y1 - top);
+            let (column_range, row_range) = match shape {
+                Shape::Rectangle { .. } => (cells(x0, x1, scale, columns), cells(y0, y1, 2.0 * scale, rows)),
+                // a point takes the one cell it lies in
+                Shape::Point { .. } => (cells(x0, x0, scale, columns), cells(y0, y0, 2.0 * scale, rows)),
+            };
+            for row in row_range {
+                for column in column_range.clone() {
+                    grid[row][column] = key;
+                }
+            }
+            let _ = writeln!(legend, "{} {}", key, shape.label());
+        }
+
+        let mut ascii: String = grid
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>() + "\n")
+            .collect();
+        ascii.push_str(&legend);
+        ascii
+    }
+}
+
+// A to Z, then a to z, then # for everything after
+fn key(index: usize) -> char {
+    match index {
+        0..=25 => (b'A' + index as u8) as char,
+        26..=51 => (b'a' + (index - 26) as u8) as char,
+        _ => '#',
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rectangle::Rectangle;
+
+    #[derive(Debug)]
+    struct Marker {
+        x: u8,
+        y: u8,
+    }
+
+    #[test]
+    fn labels_name_the_value_and_its_area() {
+        let mut scene = Scene::new();
+        scene.add_placed(&Rectangle::new(3, 5).at(0, 0));
+        let point = Marker { x: 9, y: 4 };
+        scene.add_point(f64::from(point.x), f64::from(point.y), &point);
+        assert_eq!(scene.shapes()[0].label(), "Rectangle 3x5 area 15");
+        assert_eq!(scene.shapes()[1].label(), "Marker (9, 4)");
+        assert_eq!(debug_name(&Some(1)), "Some");
+    }
+
+    #[test]
+    fn ascii_grids_are_scaled_to_the_width() {
+        let mut scene = Scene::new();
+        scene.add_placed(&Rectangle::new(20, 8).at(0, 0));
+        scene.add_placed(&Rectangle::new(10, 4).at(10, 4));
+        scene.add_point(0.0, 8.0, &"corner");
+        let ascii = scene.to_ascii(10);
+        // 20 units on 10 columns, 8 units on 2 rows of 4 units
+        // the point sits on the bottom edge, so it takes the cell of the last row
+        let expected = "AAAAAAAAAA\n\
+                        CAAAABBBBB\n\
+                        A Rectangle 20x8 area 160\n\
+                        B Rectangle 10x4 area 40\n\
+                        C \"corner\" (0, 8)\n";
+        assert_eq!(ascii, expected);
+    }
+
+    #[test]
+    fn svg_has_one_element_per_shape() {
+        let mut scene = Scene::new();
+        scene
+            .add_placed(&Rectangle::new(4, 4).at(1, 1))
+            .add_point(2.0, 2.0, &"a<b");
+        let svg = scene.to_svg();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(r#"viewBox="-9 -9 24 24""#));
+        assert_eq!(svg.matches("<rect ").count(), 1);
+        assert_eq!(svg.matches("<circle ").count(), 1);
+        assert!(svg.contains("&quot;a&lt;b&quot; (2, 2)"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+}
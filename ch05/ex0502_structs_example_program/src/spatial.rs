@@ -0,0 +1,497 @@
+// a spatial index for placed rectangles, an R-tree
+// finding the rectangles in some window by checking every rectangle takes O(n) per query
+// and checking every pair of rectangles O(n²)
+// the R-tree groups rectangles that lie close together into nodes with a bounding box
+// a query only looks into the nodes whose box it touches, so most of the tree is skipped
+//
+// a node holds at most MAX_ENTRIES entries, a full node is split in two along its longer axis
+// bulk_load builds a tree for many rectangles at once with sort-tile-recursive (STR) packing
+// which gives fuller and less overlapping nodes than inserting them one by one
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::rectangle::Placed;
+
+const MAX_ENTRIES: usize = 8;
+
+// a bounding box, in u64 so boxes around rectangles far apart can't overflow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    left: u64,
+    top: u64,
+    right: u64,
+    bottom: u64,
+}
+
+impl Bounds {
+    fn of(placed: &Placed) -> Bounds {
+        Bounds {
+            left: placed.left(),
+            top: placed.top(),
+            right: placed.right(),
+            bottom: placed.bottom(),
+        }
+    }
+
+    fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+
+    fn area(&self) -> u128 {
+        u128::from(self.right - self.left) * u128::from(self.bottom - self.top)
+    }
+
+    fn intersects(&self, other: &Bounds) -> bool {
+        self.left < other.right && other.left < self.right && self.top < other.bottom && other.top < self.bottom
+    }
+
+    fn contains(&self, other: &Bounds) -> bool {
+        self.left <= other.left && self.top <= other.top && self.right >= other.right && self.bottom >= other.bottom
+    }
+
+    // twice the center, which stays an integer
+    fn center(&self) -> (u64, u64) {
+        (self.left + self.right, self.top + self.bottom)
+    }
+
+    // the squared distance from a point to the nearest point of the box, 0 inside
+    fn distance_squared(&self, x: f64, y: f64) -> f64 {
+        let gap = |low: u64, high: u64, value: f64| {
+            if value < low as f64 {
+                low as f64 - value
+            } else if value > high as f64 {
+                value - high as f64
+            } else {
+                0.0
+            }
+        };
+        let dx = gap(self.left, self.right, x);
+        let dy = gap(self.top, self.bottom, y);
+        dx * dx + dy * dy
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node<T> {
+    Leaf(Vec<(Placed, T)>),
+    Inner(Vec<(Bounds, Node<T>)>),
+}
+
+impl<T> Node<T> {
+    // None for an empty leaf
+    fn bounds(&self) -> Option<Bounds> {
+        match self {
+            Node::Leaf(entries) => entries.iter().map(|(placed, _)| Bounds::of(placed)).reduce(|a, b| a.union(&b)),
+            Node::Inner(children) => children.iter().map(|(bounds, _)| *bounds).reduce(|a, b| a.union(&b)),
+        }
+    }
+
+    // adds the entry below this node, returns the new sibling if the node had to be split
+    fn insert(&mut self, placed: Placed, value: T) -> Option<(Bounds, Node<T>)> {
+        match self {
+            Node::Leaf(entries) => {
+                entries.push((placed, value));
+                (entries.len() > MAX_ENTRIES).then(|| {
+                    let second = split_off_half(entries, |(placed, _)| Bounds::of(placed));
+                    let node = Node::Leaf(second);
+                    (node.bounds().expect("half of a full node isn't empty"), node)
+                })
+            }
+            Node::Inner(children) => {
+                let target = Bounds::of(&placed);
+                // the child whose box grows least, on a tie the smaller one
+                let index = (0..children.len())
+                    .min_by_key(|&index| {
+                        let bounds = children[index].0;
+                        (bounds.union(&target).area() - bounds.area(), bounds.area())
+                    })
+                    .expect("inner nodes have children");
+                let split = children[index].1.insert(placed, value);
+                children[index].0 = children[index].1.bounds().expect("the child just got an entry");
+                if let Some(sibling) = split {
+                    children.push(sibling);
+                }
+                (children.len() > MAX_ENTRIES).then(|| {
+                    let second = split_off_half(children, |(bounds, _)| *bounds);
+                    let node = Node::Inner(second);
+                    (node.bounds().expect("half of a full node isn't empty"), node)
+                })
+            }
+        }
+    }
+
+    fn remove(&mut self, placed: &Placed, value: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        match self {
+            Node::Leaf(entries) => {
+                let index = entries.iter().position(|(p, v)| p == placed && v == value)?;
+                Some(entries.swap_remove(index).1)
+            }
+            Node::Inner(children) => {
+                let target = Bounds::of(placed);
+                for index in 0..children.len() {
+                    if !children[index].0.contains(&target) {
+                        continue;
+                    }
+                    if let Some(removed) = children[index].1.remove(placed, value) {
+                        // an empty child is dropped, the others get their smaller box
+                        match children[index].1.bounds() {
+                            Some(bounds) => children[index].0 = bounds,
+                            None => {
+                                children.swap_remove(index);
+                            }
+                        }
+                        return Some(removed);
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    fn query<'a>(&'a self, window: &Placed, bounds: &Bounds, found: &mut Vec<(&'a Placed, &'a T)>) {
+        match self {
+            Node::Leaf(entries) => {
+                found.extend(
+                    entries
+                        .iter()
+                        .filter(|(placed, _)| placed.intersects(window))
+                        .map(|(placed, value)| (placed, value)),
+                );
+            }
+            Node::Inner(children) => {
+                for (child_bounds, child) in children {
+                    if child_bounds.intersects(bounds) {
+                        child.query(window, bounds, found);
+                    }
+                }
+            }
+        }
+    }
+
+    fn for_each<'a>(&'a self, visit: &mut impl FnMut(&'a Placed, &'a T)) {
+        match self {
+            Node::Leaf(entries) => entries.iter().for_each(|(placed, value)| visit(placed, value)),
+            Node::Inner(children) => children.iter().for_each(|(_, child)| child.for_each(visit)),
+        }
+    }
+}
+
+// sorts the items along the axis their centers spread most on and splits off the upper half
+fn split_off_half<E>(items: &mut Vec<E>, bounds: impl Fn(&E) -> Bounds) -> Vec<E> {
+    let centers: Vec<(u64, u64)> = items.iter().map(|item| bounds(item).center()).collect();
+    let spread = |axis: fn(&(u64, u64)) -> u64| {
+        let values = centers.iter().map(axis);
+        values.clone().max().unwrap_or(0) - values.min().unwrap_or(0)
+    };
+    if spread(|c| c.0) >= spread(|c| c.1) {
+        items.sort_by_key(|item| bounds(item).center().0);
+    } else {
+        items.sort_by_key(|item| bounds(item).center().1);
+    }
+    items.split_off(items.len() / 2)
+}
+
+// sort-tile-recursive: sorted by x the items are cut into vertical slices,
+// each slice sorted by y is cut into groups of MAX_ENTRIES, one group for every node
+fn tile<E>(mut items: Vec<E>, bounds: impl Fn(&E) -> Bounds) -> Vec<Vec<E>> {
+    let nodes = items.len().div_ceil(MAX_ENTRIES);
+    let slices = (nodes as f64).sqrt().ceil() as usize;
+    let per_slice = (slices * MAX_ENTRIES).max(1);
+    items.sort_by_key(|item| bounds(item).center().0);
+
+    let mut groups = Vec::with_capacity(nodes);
+    while !items.is_empty() {
+        let rest = items.split_off(per_slice.min(items.len()));
+        let mut slice = std::mem::replace(&mut items, rest);
+        slice.sort_by_key(|item| bounds(item).center().1);
+        while !slice.is_empty() {
+            let rest = slice.split_off(MAX_ENTRIES.min(slice.len()));
+            groups.push(std::mem::replace(&mut slice, rest));
+        }
+    }
+    groups
+}
+
+/// an R-tree of placed rectangles, each with a value like a name or an id
+#[derive(Debug, Clone)]
+pub struct RTree<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+impl<T> Default for RTree<T> {
+    fn default() -> RTree<T> {
+        RTree {
+            root: Node::Leaf(Vec::new()),
+            len: 0,
+        }
+    }
+}
+
+impl<T> RTree<T> {
+    pub fn new() -> RTree<T> {
+        RTree::default()
+    }
+
+    /// builds the tree for all entries at once, faster and better balanced than inserting them
+    pub fn bulk_load(entries: Vec<(Placed, T)>) -> RTree<T> {
+        let len = entries.len();
+        if len <= MAX_ENTRIES {
+            return RTree {
+                root: Node::Leaf(entries),
+                len,
+            };
+        }
+        let mut level: Vec<(Bounds, Node<T>)> = tile(entries, |(placed, _)| Bounds::of(placed))
+            .into_iter()
+            .map(|group| {
+                let node = Node::Leaf(group);
+                (node.bounds().expect("groups aren't empty"), node)
+            })
+            .collect();
+        while level.len() > 1 {
+            level = tile(level, |(bounds, _)| *bounds)
+                .into_iter()
+                .map(|group| {
+                    let node = Node::Inner(group);
+                    (node.bounds().expect("groups aren't empty"), node)
+                })
+                .collect();
+        }
+        let (_, root) = level.pop().expect("a tree of more than MAX_ENTRIES has a level");
+        RTree { root, len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, placed: Placed, value: T) {
+        if let Some(sibling) = self.root.insert(placed, value) {
+            // the root was split, the tree grows one level
+            let old_root = std::mem::replace(&mut self.root, Node::Inner(Vec::new()));
+            let bounds = old_root.bounds().expect("a split root isn't empty");
+            self.root = Node::Inner(vec![(bounds, old_root), sibling]);
+        }
+        self.len += 1;
+    }
+
+    /// removes one entry with this rectangle and value
+    /// nodes that become empty are dropped, the others are not merged again
+    pub fn remove(&mut self, placed: &Placed, value: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let removed = self.root.remove(placed, value)?;
+        self.len -= 1;
+        // a root with a single child is replaced by that child
+        loop {
+            match &mut self.root {
+                Node::Inner(children) if children.len() == 1 => {
+                    let (_, child) = children.pop().expect("there is one child");
+                    self.root = child;
+                }
+                Node::Inner(children) if children.is_empty() => self.root = Node::Leaf(Vec::new()),
+                _ => break,
+            }
+        }
+        Some(removed)
+    }
+
+    /// every entry that shares some area with the window
+    pub fn query(&self, window: &Placed) -> Vec<(&Placed, &T)> {
+        let mut found = Vec::new();
+        self.root.query(window, &Bounds::of(window), &mut found);
+        found
+    }
+
+    /// the entry closest to the point, a rectangle the point lies in has a distance of 0
+    /// the point can be of any type that converts into an f64, like the fields of Point1<T> in ch10
+    pub fn nearest<P: Into<f64>>(&self, x: P, y: P) -> Option<(&Placed, &T)> {
+        self.nearest_k(x, y, 1).pop()
+    }
+
+    /// the k entries closest to the point, the closest first
+    /// the nodes are visited closest first, so the search stops as soon as k entries were found
+    pub fn nearest_k<'a, P: Into<f64>>(&'a self, x: P, y: P, k: usize) -> Vec<(&'a Placed, &'a T)> {
+        let (x, y) = (x.into(), y.into());
+        let mut found = Vec::with_capacity(k);
+        let mut queue = BinaryHeap::new();
+        let mut sequence = 0;
+        let mut push = |queue: &mut BinaryHeap<Queued<'a, T>>, distance: f64, candidate: Candidate<'a, T>| {
+            sequence += 1;
+            queue.push(Queued {
+                distance,
+                sequence,
+                candidate,
+            });
+        };
+        push(&mut queue, 0.0, Candidate::Node(&self.root));
+        while found.len() < k {
+            let Some(queued) = queue.pop() else {
+                break;
+            };
+            match queued.candidate {
+                Candidate::Entry(placed, value) => found.push((placed, value)),
+                Candidate::Node(Node::Leaf(entries)) => {
+                    for (placed, value) in entries {
+                        let distance = Bounds::of(placed).distance_squared(x, y);
+                        push(&mut queue, distance, Candidate::Entry(placed, value));
+                    }
+                }
+                Candidate::Node(Node::Inner(children)) => {
+                    for (bounds, child) in children {
+                        push(&mut queue, bounds.distance_squared(x, y), Candidate::Node(child));
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// every entry, in no particular order
+    pub fn entries(&self) -> Vec<(&Placed, &T)> {
+        let mut entries = Vec::with_capacity(self.len);
+        self.root.for_each(&mut |placed, value| entries.push((placed, value)));
+        entries
+    }
+}
+
+enum Candidate<'a, T> {
+    Node(&'a Node<T>),
+    Entry(&'a Placed, &'a T),
+}
+
+// the queue of nearest_k, the smallest distance comes out first
+// on the same distance the earlier one, so the search is deterministic
+struct Queued<'a, T> {
+    distance: f64,
+    sequence: usize,
+    candidate: Candidate<'a, T>,
+}
+
+impl<T> Ord for Queued<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap, so both comparisons are turned around
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl<T> PartialOrd for Queued<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> PartialEq for Queued<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<T> Eq for Queued<'_, T> {}
+
+/// the naive window query that checks every entry, to compare the R-tree against
+pub fn scan<'a, T>(entries: &'a [(Placed, T)], window: &Placed) -> Vec<(&'a Placed, &'a T)> {
+    entries
+        .iter()
+        .filter(|(placed, _)| placed.intersects(window))
+        .map(|(placed, value)| (placed, value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Lcg;
+    use crate::rectangle::Rectangle;
+
+    // rectangles that look scattered, from a linear congruential generator
+    fn scattered(count: usize) -> Vec<(Placed, usize)> {
+        let mut random = Lcg::new(42);
+        let mut next = move |max| random.below(max);
+        (0..count)
+            .map(|id| (Rectangle::new(next(30) + 1, next(30) + 1).at(next(1000), next(1000)), id))
+            .collect()
+    }
+
+    fn ids(found: Vec<(&Placed, &usize)>) -> Vec<usize> {
+        let mut ids: Vec<usize> = found.into_iter().map(|(_, id)| *id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn queries_match_the_naive_scan() {
+        let entries = scattered(2000);
+        let loaded = RTree::bulk_load(entries.clone());
+        let mut inserted = RTree::new();
+        for (placed, id) in entries.iter() {
+            inserted.insert(*placed, *id);
+        }
+        assert_eq!(loaded.len(), 2000);
+        assert_eq!(inserted.entries().len(), 2000);
+        for window in [
+            Rectangle::new(100, 100).at(0, 0),
+            Rectangle::new(5, 500).at(400, 300),
+            Rectangle::new(2000, 2000).at(0, 0),
+            Rectangle::new(10, 10).at(5000, 5000),
+        ] {
+            let expected = ids(scan(&entries, &window));
+            assert_eq!(ids(loaded.query(&window)), expected);
+            assert_eq!(ids(inserted.query(&window)), expected);
+        }
+    }
+
+    #[test]
+    fn nearest_matches_the_closest_by_brute_force() {
+        let entries = scattered(500);
+        let tree = RTree::bulk_load(entries.clone());
+        for (x, y) in [(0_i32, 0_i32), (500, 500), (-50, 1200), (999, 3)] {
+            let (x, y) = (f64::from(x), f64::from(y));
+            let closest = entries
+                .iter()
+                .map(|(placed, _)| Bounds::of(placed).distance_squared(x, y))
+                .fold(f64::INFINITY, f64::min);
+            let nearest = tree.nearest_k(x, y, 3);
+            assert_eq!(Bounds::of(nearest[0].0).distance_squared(x, y), closest);
+            // the three come closest first
+            let distances: Vec<f64> = nearest.iter().map(|(placed, _)| Bounds::of(placed).distance_squared(x, y)).collect();
+            assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+        assert!(RTree::<u8>::new().nearest(1_u8, 2_u8).is_none());
+    }
+
+    #[test]
+    fn removed_entries_are_gone() {
+        let entries = scattered(300);
+        let mut tree = RTree::bulk_load(entries.clone());
+        for (placed, id) in entries.iter().filter(|(_, id)| id % 3 == 0) {
+            assert_eq!(tree.remove(placed, id), Some(*id));
+        }
+        assert_eq!(tree.remove(&entries[0].0, &0), None);
+        assert_eq!(tree.len(), 200);
+        let everything = Rectangle::new(2000, 2000).at(0, 0);
+        assert!(ids(tree.query(&everything)).iter().all(|id| id % 3 != 0));
+        for (placed, id) in &entries {
+            tree.remove(placed, id);
+        }
+        assert!(tree.is_empty());
+        assert!(tree.query(&everything).is_empty());
+    }
+}
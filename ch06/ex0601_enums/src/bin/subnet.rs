@@ -0,0 +1,143 @@
+//! a subnet calculator
+//! prints what there is to know about a CIDR block, and the blocks it splits into
+//!
+//!     cargo run --bin subnet -- 192.168.1.10/24
+//!     cargo run --bin subnet -- 192.168.1.0/24 4     four blocks of the same size
+//!     cargo run --bin subnet -- 2001:db8::/32 /34    all /34 blocks inside it
+use std::env;
+use std::process;
+
+use ex0601_enums::cidr::Cidr;
+
+// more blocks than this are not listed
+const MAX_ROWS: usize = 256;
+
+// how the block is cut
+enum Split {
+    Count(usize),
+    Prefix(u8),
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<(Cidr, Option<Split>), String> {
+    let block = args.next().ok_or_else(String::new)?;
+    let block: Cidr = block.parse().map_err(|error| format!("{}", error))?;
+    let split = match args.next() {
+        None => None,
+        Some(arg) => Some(match arg.strip_prefix('/') {
+            Some(prefix) => Split::Prefix(
+                prefix
+                    .parse()
+                    .map_err(|_| format!("{:?} isn't a prefix length", arg))?,
+            ),
+            None => Split::Count(
+                arg.parse()
+                    .map_err(|_| format!("{:?} isn't a number of blocks", arg))?,
+            ),
+        }),
+    };
+    if let Some(arg) = args.next() {
+        return Err(format!("unexpected argument {:?}", arg));
+    }
+    Ok((block, split))
+}
+
+// the counts don't fit in a u128 only for ::/0
+fn count(count: Option<u128>) -> String {
+    count.map_or_else(|| String::from("2^128"), |count| count.to_string())
+}
+
+fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = header.iter().map(|title| title.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    };
+    line(header.to_vec());
+    let dashes: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    line(dashes.iter().map(String::as_str).collect());
+    for row in rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
+
+fn main() {
+    let (block, split) = parse_args(env::args().skip(1)).unwrap_or_else(|error| {
+        if !error.is_empty() {
+            eprintln!("{}", error);
+        }
+        eprintln!("usage: subnet CIDR [COUNT | /PREFIX]");
+        process::exit(2);
+    });
+
+    let (first_host, last_host) = block.host_range();
+    let mut facts = vec![
+        ("address", block.address().to_string()),
+        ("network", block.network().to_string()),
+        ("netmask", block.netmask().to_string()),
+        ("wildcard", block.hostmask().to_string()),
+        (
+            "broadcast",
+            block
+                .broadcast()
+                .map_or_else(|| String::from("none"), |address| address.to_string()),
+        ),
+        ("first host", first_host.to_string()),
+        ("last host", last_host.to_string()),
+        ("addresses", count(block.address_count())),
+        ("hosts", count(block.host_count())),
+    ];
+    if let Some(supernet) = block.supernet() {
+        facts.push(("supernet", supernet.to_string()));
+    }
+    let rows: Vec<Vec<String>> = facts
+        .into_iter()
+        .map(|(name, value)| vec![String::from(name), value])
+        .collect();
+    print_table(&["", &block.to_string()], &rows);
+
+    let subnets: Vec<Cidr> = match split {
+        None => return,
+        // only the blocks that are listed are made, a count like 4294967296 would take gigabytes
+        Some(Split::Count(n)) => match block.split(n) {
+            Some(subnets) => subnets.take(MAX_ROWS + 1).collect(),
+            None => {
+                eprintln!("{} can't be split into {} blocks", block, n);
+                process::exit(1);
+            }
+        },
+        Some(Split::Prefix(prefix)) => match block.subnets(prefix) {
+            Some(subnets) => subnets.take(MAX_ROWS + 1).collect(),
+            None => {
+                eprintln!("{} has no /{} blocks", block, prefix);
+                process::exit(1);
+            }
+        },
+    };
+    let rows: Vec<Vec<String>> = subnets
+        .iter()
+        .take(MAX_ROWS)
+        .map(|subnet| {
+            let (first_host, last_host) = subnet.host_range();
+            vec![
+                subnet.to_string(),
+                first_host.to_string(),
+                last_host.to_string(),
+                count(subnet.host_count()),
+            ]
+        })
+        .collect();
+    println!();
+    print_table(&["subnet", "first host", "last host", "hosts"], &rows);
+    if subnets.len() > MAX_ROWS {
+        println!("... only the first {} blocks are listed", MAX_ROWS);
+    }
+}
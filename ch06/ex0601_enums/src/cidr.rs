@@ -0,0 +1,342 @@
+// CIDR blocks like 10.0.0.0/8 or 2001:db8::/32
+// the prefix length says how many of the leading bits of the address name the network
+// the bits after them number the hosts in it
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ip::{AddrParseError, IpAddrKind};
+
+// the prefix bits of an address that is width bits wide
+pub(crate) fn mask(width: u32, prefix: u8) -> u128 {
+    let all = u128::MAX >> (128 - width);
+    all & !all.checked_shr(u32::from(prefix)).unwrap_or(0)
+}
+
+/// an address and a prefix length, the address may have host bits set like 192.168.1.10/24
+/// the fields are private so the prefix is never longer than the address, new and parse check it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cidr {
+    address: IpAddrKind,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// None if the prefix is longer than the address
+    pub fn new(address: IpAddrKind, prefix: u8) -> Option<Cidr> {
+        (u32::from(prefix) <= address.width()).then_some(Cidr { address, prefix })
+    }
+
+    pub fn address(&self) -> IpAddrKind {
+        self.address
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    pub fn mask(&self) -> u128 {
+        mask(self.address.width(), self.prefix)
+    }
+
+    /// the block with the host bits cleared, 192.168.1.0/24 for 192.168.1.10/24
+    pub fn network(&self) -> Cidr {
+        Cidr {
+            address: self.address.with_bits(self.address.to_bits() & self.mask()),
+            prefix: self.prefix,
+        }
+    }
+
+    /// the address is of the same version and in the block
+    pub fn contains(&self, address: &IpAddrKind) -> bool {
+        address.is_v4() == self.address.is_v4()
+            && address.to_bits() & self.mask() == self.address.to_bits() & self.mask()
+    }
+
+    /// the other block is of the same version and completely inside this one
+    pub fn contains_block(&self, other: &Cidr) -> bool {
+        other.prefix >= self.prefix && self.contains(&other.address)
+    }
+
+    /// the mask as an address, 255.255.255.0 for a /24
+    pub fn netmask(&self) -> IpAddrKind {
+        self.address.with_bits(self.mask())
+    }
+
+    /// the host bits as an address, 0.0.0.255 for a /24
+    pub fn hostmask(&self) -> IpAddrKind {
+        let width = self.address.width();
+        self.address
+            .with_bits(mask(width, width as u8) & !self.mask())
+    }
+
+    /// the first address of the block, the network address
+    pub fn first(&self) -> IpAddrKind {
+        self.network().address
+    }
+
+    /// the last address of the block, all host bits set
+    pub fn last(&self) -> IpAddrKind {
+        self.address
+            .with_bits(self.first().to_bits() | self.hostmask().to_bits())
+    }
+
+    /// the last address of an IPv4 block, which isn't a host
+    /// IPv6 has no broadcast, and /31 and /32 blocks have no room for one (RFC 3021)
+    pub fn broadcast(&self) -> Option<IpAddrKind> {
+        (self.address.is_v4() && self.prefix <= 30).then(|| self.last())
+    }
+
+    /// the number of addresses in the block, None for ::/0 whose 2^128 addresses don't fit in a u128
+    pub fn address_count(&self) -> Option<u128> {
+        1u128.checked_shl(self.address.width() - u32::from(self.prefix))
+    }
+
+    /// the first and the last address a host can have
+    /// an IPv4 block leaves out its network and broadcast address unless it is a /31 or /32
+    pub fn host_range(&self) -> (IpAddrKind, IpAddrKind) {
+        let (first, last) = (self.first(), self.last());
+        if self.broadcast().is_some() {
+            (
+                first.with_bits(first.to_bits() + 1),
+                last.with_bits(last.to_bits() - 1),
+            )
+        } else {
+            (first, last)
+        }
+    }
+
+    /// the number of hosts in the host range, None for ::/0
+    pub fn host_count(&self) -> Option<u128> {
+        let count = self.address_count()?;
+        Some(if self.broadcast().is_some() {
+            count - 2
+        } else {
+            count
+        })
+    }
+
+    /// the block one bit shorter that this block is half of, None for a /0
+    pub fn supernet(&self) -> Option<Cidr> {
+        let prefix = self.prefix.checked_sub(1)?;
+        Some(
+            Cidr {
+                address: self.address,
+                prefix,
+            }
+            .network(),
+        )
+    }
+
+    /// the smallest block that contains all the blocks
+    /// None if there are none or they mix IPv4 and IPv6
+    pub fn supernet_of(blocks: &[Cidr]) -> Option<Cidr> {
+        let (first, rest) = blocks.split_first()?;
+        let width = first.address.width();
+        let mut prefix = first.prefix;
+        for block in rest {
+            if block.address.is_v4() != first.address.is_v4() {
+                return None;
+            }
+            // the leading bits both addresses share
+            let common =
+                (first.address.to_bits() ^ block.address.to_bits()).leading_zeros() - (128 - width);
+            prefix = prefix.min(block.prefix).min(common as u8);
+        }
+        Some(
+            Cidr {
+                address: first.address,
+                prefix,
+            }
+            .network(),
+        )
+    }
+
+    /// all blocks with the longer prefix inside this block, in order
+    /// None if the prefix is shorter than this block's or longer than the address
+    pub fn subnets(&self, prefix: u8) -> Option<impl Iterator<Item = Cidr>> {
+        let width = self.address.width();
+        if prefix < self.prefix || u32::from(prefix) > width {
+            return None;
+        }
+        let network = self.network().address;
+        let extra = u32::from(prefix - self.prefix);
+        let last_index = u128::MAX.checked_shr(128 - extra).unwrap_or(0);
+        let shift = width - u32::from(prefix);
+        Some((0..=last_index).map(move |index| Cidr {
+            address: network.with_bits(network.to_bits() | index.checked_shl(shift).unwrap_or(0)),
+            prefix,
+        }))
+    }
+
+    /// the block cut into count blocks of the same size, as large as they can be
+    /// they start at the beginning of the block, the rest is left over when count isn't a power of two
+    /// the blocks are made one by one like subnets, so a large count takes no memory
+    /// None if count is 0 or the blocks would need a prefix longer than the address
+    pub fn split(&self, count: usize) -> Option<impl Iterator<Item = Cidr>> {
+        if count == 0 {
+            return None;
+        }
+        // the bits it takes to number count blocks
+        let extra = usize::BITS - (count - 1).leading_zeros();
+        let prefix = u32::from(self.prefix) + extra;
+        if prefix > self.address.width() {
+            return None;
+        }
+        Some(self.subnets(prefix as u8)?.take(count))
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix)
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<Cidr, AddrParseError> {
+        let Some((address, prefix)) = s.split_once('/') else {
+            return Err(AddrParseError::new(
+                s,
+                s.len(),
+                0,
+                "a CIDR block needs a /prefix length",
+            ));
+        };
+        // the positions of errors in the address are already right, the input is the whole block
+        let address: IpAddrKind =
+            address
+                .parse()
+                .map_err(|error: AddrParseError| AddrParseError {
+                    input: String::from(s),
+                    ..error
+                })?;
+        let position = s.len() - prefix.len();
+        let invalid = |reason: String| AddrParseError::new(s, position, prefix.len(), reason);
+        if prefix.is_empty()
+            || !prefix.bytes().all(|b| b.is_ascii_digit())
+            || (prefix.len() > 1 && prefix.starts_with('0'))
+        {
+            return Err(invalid(String::from(
+                "the prefix length is a number without leading zeros",
+            )));
+        }
+        prefix
+            .parse::<u8>()
+            .ok()
+            .and_then(|prefix| Cidr::new(address, prefix))
+            .ok_or_else(|| invalid(format!("the prefix length goes up to {}", address.width())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_are_parsed_and_contain_their_addresses() {
+        let block: Cidr = "192.168.1.10/24".parse().unwrap();
+        assert_eq!(block.network().to_string(), "192.168.1.0/24");
+        assert!(block.contains(&"192.168.1.255".parse().unwrap()));
+        assert!(!block.contains(&"192.168.2.0".parse().unwrap()));
+        assert!(!block.contains(&"::ffff:192.168.1.1".parse().unwrap()));
+        let v6: Cidr = "2001:db8::1/32".parse().unwrap();
+        assert_eq!(v6.network().to_string(), "2001:db8::/32");
+        assert!("0.0.0.0/0"
+            .parse::<Cidr>()
+            .unwrap()
+            .contains(&"8.8.8.8".parse().unwrap()));
+
+        assert_eq!("10.0.0.0/33".parse::<Cidr>().unwrap_err().part(), "33");
+        assert_eq!("10.0.0.0/08".parse::<Cidr>().unwrap_err().part(), "08");
+        assert_eq!("10.0.0.0".parse::<Cidr>().unwrap_err().position, 8);
+        let error = "10.0.0.256/8".parse::<Cidr>().unwrap_err();
+        assert_eq!(
+            (error.input.as_str(), error.part()),
+            ("10.0.0.256/8", "256")
+        );
+    }
+
+    fn block(text: &str) -> Cidr {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn subnet_math() {
+        let lan = block("192.168.1.10/24");
+        assert_eq!(lan.netmask().to_string(), "255.255.255.0");
+        assert_eq!(lan.hostmask().to_string(), "0.0.0.255");
+        assert_eq!(lan.broadcast().unwrap().to_string(), "192.168.1.255");
+        let (first, last) = lan.host_range();
+        assert_eq!(first.to_string(), "192.168.1.1");
+        assert_eq!(last.to_string(), "192.168.1.254");
+        assert_eq!(
+            (lan.address_count(), lan.host_count()),
+            (Some(256), Some(254))
+        );
+
+        // point-to-point links and single hosts use every address
+        let link = block("10.0.0.0/31");
+        assert_eq!((link.broadcast(), link.host_count()), (None, Some(2)));
+        assert_eq!(block("10.0.0.7/32").host_range().0.to_string(), "10.0.0.7");
+
+        let v6 = block("2001:db8::/64");
+        assert_eq!(v6.broadcast(), None);
+        assert_eq!(v6.host_count(), Some(1 << 64));
+        assert_eq!(v6.last().to_string(), "2001:db8::ffff:ffff:ffff:ffff");
+        assert_eq!(block("::/0").address_count(), None);
+        assert_eq!(block("::/1").address_count(), Some(1 << 127));
+    }
+
+    #[test]
+    fn blocks_are_split_and_joined() {
+        let lan = block("192.168.0.0/22");
+        let quarters: Vec<String> = lan.split(4).unwrap().map(|block| block.to_string()).collect();
+        assert_eq!(
+            quarters,
+            [
+                "192.168.0.0/24",
+                "192.168.1.0/24",
+                "192.168.2.0/24",
+                "192.168.3.0/24"
+            ]
+        );
+        // three blocks need two more bits just like four
+        let thirds: Vec<Cidr> = lan.split(3).unwrap().collect();
+        assert_eq!(thirds.len(), 3);
+        assert_eq!(thirds[2].to_string(), "192.168.2.0/24");
+        assert!(lan.split(1).unwrap().eq([lan]));
+        assert!(lan.split(0).is_none());
+        assert!(block("10.0.0.0/31").split(4).is_none());
+        let v6 = block("2001:db8::/32").split(65536).unwrap().last().unwrap();
+        assert_eq!(v6.to_string(), "2001:db8:ffff::/48");
+        // the blocks are made as they are needed, the first of 2^64 comes right away
+        let mut many = block("2001:db8::/32").split(usize::MAX).unwrap();
+        assert_eq!(many.next().unwrap().to_string(), "2001:db8::/96");
+        assert_eq!(block("::/0").subnets(1).unwrap().count(), 2);
+        assert!(lan.subnets(21).is_none());
+
+        assert_eq!(
+            block("192.168.2.0/24").supernet().unwrap().to_string(),
+            "192.168.2.0/23"
+        );
+        assert_eq!(block("0.0.0.0/0").supernet(), None);
+        let blocks = [
+            block("192.168.1.0/24"),
+            block("192.168.2.0/24"),
+            block("192.168.3.128/25"),
+        ];
+        let joined = Cidr::supernet_of(&blocks).unwrap();
+        assert_eq!(joined.to_string(), "192.168.0.0/22");
+        assert!(blocks.iter().all(|block| joined.contains_block(block)));
+        assert!(!blocks[0].contains_block(&joined));
+        let single = Cidr::supernet_of(&[block("10.0.0.1/32")]).unwrap();
+        assert_eq!(single.to_string(), "10.0.0.1/32");
+        assert_eq!(
+            Cidr::supernet_of(&[block("10.0.0.0/8"), block("::/0")]),
+            None
+        );
+        assert_eq!(Cidr::supernet_of(&[]), None);
+    }
+}
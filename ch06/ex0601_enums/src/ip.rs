@@ -0,0 +1,524 @@
+// IP addresses as an enum, parsed from and printed as text
+// IPv4 is the dotted decimal form, four numbers from 0 to 255 without leading zeros,
+// leading zeros are rejected because some tools read 010 as octal 8
+// IPv6 is the text form of RFC 4291: eight groups of up to four hex digits,
+// where one :: stands for one or more groups of zeros and the last 32 bits
+// may be written as an IPv4 address, like ::ffff:192.0.2.1
+// addresses are printed in the canonical form of RFC 5952: lowercase, no leading zeros,
+// the longest run of two or more zero groups shortened to ::
+use std::fmt;
+use std::str::FromStr;
+
+/// enums give you a way of saying a value is one of a possible set of values
+/// example where an enum is more appropriate than a struct:
+/// enumerating all possible variants of an IP address
+/// data can be put directly into an enum
+/// we can put any kind of data inside an enum variant:
+/// strings, numeric types, structs or even another enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IpAddrKind {
+    // each variant can have different types and amounts of associated data
+    // four bytes for IPv4, eight 16 bit groups for IPv6
+    V4(u8, u8, u8, u8),
+    V6([u16; 8]),
+} // IpAddrKind is now a custom data type
+
+/// what kind of address it is, the ranges of RFC 6890 and RFC 4291
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    // 0.0.0.0 and ::
+    Unspecified,
+    Loopback,
+    // 10.0.0.0/8, 172.16.0.0/12, 192.168.0.0/16 and the unique local fc00::/7
+    Private,
+    // 169.254.0.0/16 and fe80::/10
+    LinkLocal,
+    Multicast,
+    // 255.255.255.255
+    Broadcast,
+    // everything else
+    Global,
+}
+
+/// which part of the text isn't an address and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddrParseError {
+    pub input: String,
+    // the byte position of the wrong part and how long it is
+    pub position: usize,
+    pub length: usize,
+    pub reason: String,
+}
+
+impl AddrParseError {
+    pub(crate) fn new(
+        input: &str,
+        position: usize,
+        length: usize,
+        reason: impl Into<String>,
+    ) -> AddrParseError {
+        AddrParseError {
+            input: String::from(input),
+            position,
+            length,
+            reason: reason.into(),
+        }
+    }
+
+    /// the wrong part of the input
+    pub fn part(&self) -> &str {
+        let end = (self.position + self.length).min(self.input.len());
+        self.input.get(self.position..end).unwrap_or("")
+    }
+}
+
+impl fmt::Display for AddrParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid IP address {:?}: {}", self.input, self.reason)?;
+        if self.length > 0 {
+            write!(f, ", {:?} at position {}", self.part(), self.position)
+        } else {
+            write!(f, " at position {}", self.position)
+        }
+    }
+}
+
+impl std::error::Error for AddrParseError {}
+
+impl IpAddrKind {
+    pub const V4_LOCALHOST: IpAddrKind = IpAddrKind::V4(127, 0, 0, 1);
+    pub const V6_LOCALHOST: IpAddrKind = IpAddrKind::V6([0, 0, 0, 0, 0, 0, 0, 1]);
+
+    pub fn is_v4(&self) -> bool {
+        matches!(self, IpAddrKind::V4(..))
+    }
+
+    /// the number of bits, 32 or 128
+    pub fn width(&self) -> u32 {
+        match self {
+            IpAddrKind::V4(..) => 32,
+            IpAddrKind::V6(_) => 128,
+        }
+    }
+
+    /// the address as a number, IPv4 addresses in the lowest 32 bits
+    pub fn to_bits(&self) -> u128 {
+        match *self {
+            IpAddrKind::V4(a, b, c, d) => u128::from(u32::from_be_bytes([a, b, c, d])),
+            IpAddrKind::V6(groups) => groups
+                .iter()
+                .fold(0, |bits, &group| bits << 16 | u128::from(group)),
+        }
+    }
+
+    pub fn v4_from_bits(bits: u32) -> IpAddrKind {
+        let [a, b, c, d] = bits.to_be_bytes();
+        IpAddrKind::V4(a, b, c, d)
+    }
+
+    pub fn v6_from_bits(bits: u128) -> IpAddrKind {
+        let mut groups = [0u16; 8];
+        for (index, group) in groups.iter_mut().enumerate() {
+            *group = (bits >> (112 - 16 * index)) as u16;
+        }
+        IpAddrKind::V6(groups)
+    }
+
+    /// an address of the same version as this one from its bits
+    pub fn with_bits(&self, bits: u128) -> IpAddrKind {
+        match self {
+            IpAddrKind::V4(..) => IpAddrKind::v4_from_bits(bits as u32),
+            IpAddrKind::V6(_) => IpAddrKind::v6_from_bits(bits),
+        }
+    }
+
+    /// the IPv4 address in an IPv4-mapped IPv6 address ::ffff:a.b.c.d
+    pub fn to_mapped_v4(&self) -> Option<IpAddrKind> {
+        match *self {
+            IpAddrKind::V6([0, 0, 0, 0, 0, 0xffff, high, low]) => Some(IpAddrKind::v4_from_bits(
+                u32::from(high) << 16 | u32::from(low),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn class(&self) -> Class {
+        match *self {
+            IpAddrKind::V4(a, b, c, d) => match (a, b) {
+                (0, _) if [b, c, d] == [0, 0, 0] => Class::Unspecified,
+                (127, _) => Class::Loopback,
+                (10, _) | (192, 168) => Class::Private,
+                (172, 16..=31) => Class::Private,
+                (169, 254) => Class::LinkLocal,
+                (224..=239, _) => Class::Multicast,
+                (255, 255) if [c, d] == [255, 255] => Class::Broadcast,
+                _ => Class::Global,
+            },
+            IpAddrKind::V6(groups) => match groups {
+                [0, 0, 0, 0, 0, 0, 0, 0] => Class::Unspecified,
+                [0, 0, 0, 0, 0, 0, 0, 1] => Class::Loopback,
+                // an IPv4-mapped address is what the IPv4 address is
+                [0, 0, 0, 0, 0, 0xffff, ..] => {
+                    self.to_mapped_v4().map_or(Class::Global, |v4| v4.class())
+                }
+                [first, ..] if first & 0xfe00 == 0xfc00 => Class::Private,
+                [first, ..] if first & 0xffc0 == 0xfe80 => Class::LinkLocal,
+                [first, ..] if first & 0xff00 == 0xff00 => Class::Multicast,
+                _ => Class::Global,
+            },
+        }
+    }
+
+    pub fn is_unspecified(&self) -> bool {
+        self.class() == Class::Unspecified
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.class() == Class::Loopback
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.class() == Class::Private
+    }
+
+    pub fn is_link_local(&self) -> bool {
+        self.class() == Class::LinkLocal
+    }
+
+    pub fn is_multicast(&self) -> bool {
+        self.class() == Class::Multicast
+    }
+}
+
+// parses the IPv4 address that starts at offset in the input, for error positions
+fn parse_v4(input: &str, text: &str, offset: usize) -> Result<[u8; 4], AddrParseError> {
+    let mut octets = [0u8; 4];
+    let mut start = 0;
+    for (index, part) in text.split('.').enumerate() {
+        let position = offset + start;
+        start += part.len() + 1;
+        if index == 4 {
+            return Err(AddrParseError::new(
+                input,
+                position - 1,
+                text.len() + offset + 1 - position,
+                "an IPv4 address has only four parts",
+            ));
+        }
+        if part.is_empty() {
+            return Err(AddrParseError::new(
+                input,
+                position,
+                0,
+                "a part of the IPv4 address is empty",
+            ));
+        }
+        if let Some((at, c)) = part.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+            return Err(AddrParseError::new(
+                input,
+                position + at,
+                c.len_utf8(),
+                "an IPv4 address has only decimal digits",
+            ));
+        }
+        if part.len() > 1 && part.starts_with('0') {
+            return Err(AddrParseError::new(
+                input,
+                position,
+                part.len(),
+                "leading zeros aren't allowed",
+            ));
+        }
+        octets[index] = part.parse().map_err(|_| {
+            AddrParseError::new(
+                input,
+                position,
+                part.len(),
+                "the parts of an IPv4 address go up to 255",
+            )
+        })?;
+        if index < 3 && start > text.len() {
+            return Err(AddrParseError::new(
+                input,
+                offset + text.len(),
+                0,
+                "an IPv4 address has four parts",
+            ));
+        }
+    }
+    Ok(octets)
+}
+
+// the groups of one side of the ::, an IPv4 address at the end counts as two groups
+// last says whether this side ends the address, only there an IPv4 address may stand
+fn parse_groups(
+    input: &str,
+    text: &str,
+    offset: usize,
+    last: bool,
+) -> Result<Vec<u16>, AddrParseError> {
+    let mut groups = Vec::new();
+    if text.is_empty() {
+        return Ok(groups);
+    }
+    let parts: Vec<&str> = text.split(':').collect();
+    let mut position = offset;
+    for (index, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            return Err(AddrParseError::new(
+                input,
+                position,
+                0,
+                "a group is empty, only :: may leave groups out",
+            ));
+        }
+        if part.contains('.') {
+            if !(last && index == parts.len() - 1) {
+                return Err(AddrParseError::new(
+                    input,
+                    position,
+                    part.len(),
+                    "an embedded IPv4 address has to be at the end",
+                ));
+            }
+            let [a, b, c, d] = parse_v4(input, part, position)?;
+            groups.push(u16::from_be_bytes([a, b]));
+            groups.push(u16::from_be_bytes([c, d]));
+        } else {
+            if let Some((at, c)) = part.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+                return Err(AddrParseError::new(
+                    input,
+                    position + at,
+                    c.len_utf8(),
+                    "a group has only hex digits",
+                ));
+            }
+            if part.len() > 4 {
+                return Err(AddrParseError::new(
+                    input,
+                    position,
+                    part.len(),
+                    "a group has at most four hex digits",
+                ));
+            }
+            groups.push(u16::from_str_radix(part, 16).expect("checked to be hex digits"));
+        }
+        position += part.len() + 1;
+    }
+    Ok(groups)
+}
+
+fn parse_v6(input: &str) -> Result<[u16; 8], AddrParseError> {
+    if let Some(zone) = input.find('%') {
+        return Err(AddrParseError::new(
+            input,
+            zone,
+            input.len() - zone,
+            "zone identifiers aren't supported",
+        ));
+    }
+    let mut halves = input.match_indices("::");
+    let compressed = halves.next().map(|(at, _)| at);
+    if let Some((second, _)) = halves.next() {
+        return Err(AddrParseError::new(
+            input,
+            second,
+            2,
+            "only one :: is allowed",
+        ));
+    }
+    match compressed {
+        None => {
+            let groups = parse_groups(input, input, 0, true)?;
+            <[u16; 8]>::try_from(groups.as_slice()).map_err(|_| {
+                AddrParseError::new(
+                    input,
+                    0,
+                    input.len(),
+                    format!("{} groups instead of 8", groups.len()),
+                )
+            })
+        }
+        Some(at) => {
+            // a ::: is an empty group right after the ::
+            let head = parse_groups(input, &input[..at], 0, false)?;
+            let tail = parse_groups(input, &input[at + 2..], at + 2, true)?;
+            if head.len() + tail.len() > 7 {
+                return Err(AddrParseError::new(
+                    input,
+                    at,
+                    2,
+                    "there are no groups left for ::",
+                ));
+            }
+            let mut groups = [0u16; 8];
+            groups[..head.len()].copy_from_slice(&head);
+            groups[8 - tail.len()..].copy_from_slice(&tail);
+            Ok(groups)
+        }
+    }
+}
+
+impl FromStr for IpAddrKind {
+    type Err = AddrParseError;
+
+    fn from_str(s: &str) -> Result<IpAddrKind, AddrParseError> {
+        if s.is_empty() {
+            return Err(AddrParseError::new(s, 0, 0, "the address is empty"));
+        }
+        if s.contains(':') {
+            parse_v6(s).map(IpAddrKind::V6)
+        } else {
+            let [a, b, c, d] = parse_v4(s, s, 0)?;
+            Ok(IpAddrKind::V4(a, b, c, d))
+        }
+    }
+}
+
+impl fmt::Display for IpAddrKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let groups = match *self {
+            IpAddrKind::V4(a, b, c, d) => return write!(f, "{}.{}.{}.{}", a, b, c, d),
+            IpAddrKind::V6(groups) => groups,
+        };
+        // RFC 5952 5: IPv4-mapped addresses end in the dotted IPv4 form
+        if let Some(v4) = self.to_mapped_v4() {
+            return write!(f, "::ffff:{}", v4);
+        }
+        // the longest run of zero groups, the first one if two are as long, only if it's 2 or more
+        let mut longest = (0, 0);
+        let mut index = 0;
+        while index < 8 {
+            let length = groups[index..]
+                .iter()
+                .take_while(|&&group| group == 0)
+                .count();
+            if length > longest.1 {
+                longest = (index, length);
+            }
+            index += length.max(1);
+        }
+        let write_groups = |f: &mut fmt::Formatter, groups: &[u16]| -> fmt::Result {
+            for (index, group) in groups.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(":")?;
+                }
+                write!(f, "{:x}", group)?;
+            }
+            Ok(())
+        };
+        if longest.1 < 2 {
+            return write_groups(f, &groups);
+        }
+        write_groups(f, &groups[..longest.0])?;
+        f.write_str("::")?;
+        write_groups(f, &groups[longest.0 + longest.1..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> IpAddrKind {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn ipv4_addresses() {
+        assert_eq!(parse("192.168.0.1"), IpAddrKind::V4(192, 168, 0, 1));
+        assert_eq!(parse("0.0.0.0").to_string(), "0.0.0.0");
+        let error = "192.168.300.1".parse::<IpAddrKind>().unwrap_err();
+        assert_eq!((error.part(), error.position), ("300", 8));
+        assert_eq!("10.01.0.1".parse::<IpAddrKind>().unwrap_err().part(), "01");
+        assert_eq!("10.0.0".parse::<IpAddrKind>().unwrap_err().position, 6);
+        assert_eq!("10.0.0.1.2".parse::<IpAddrKind>().unwrap_err().part(), ".2");
+        assert_eq!("10.0.x.1".parse::<IpAddrKind>().unwrap_err().part(), "x");
+        assert_eq!("10..0.1".parse::<IpAddrKind>().unwrap_err().position, 3);
+    }
+
+    #[test]
+    fn ipv6_addresses_are_parsed_and_printed_canonically() {
+        let cases = [
+            ("2001:DB8:0:0:0:0:2:1", "2001:db8::2:1"),
+            ("2001:db8:0000:1:1:1:1:1", "2001:db8:0:1:1:1:1:1"),
+            ("2001:0:0:1:0:0:0:1", "2001:0:0:1::1"),
+            ("2001:db8:0:0:1:0:0:1", "2001:db8::1:0:0:1"),
+            ("::", "::"),
+            ("::1", "::1"),
+            ("1::", "1::"),
+            ("fe80::1:2:3:4:5:6", "fe80:0:1:2:3:4:5:6"),
+            ("::ffff:192.0.2.1", "::ffff:192.0.2.1"),
+            ("64:ff9b::192.0.2.33", "64:ff9b::c000:221"),
+        ];
+        for (text, canonical) in cases {
+            let address = parse(text);
+            assert_eq!(address.to_string(), canonical, "{}", text);
+            assert_eq!(parse(canonical), address);
+        }
+        // the formatting of the standard library follows RFC 5952 too
+        let mut state: u64 = 7;
+        for _ in 0..500 {
+            let mut groups = [0u16; 8];
+            for group in groups.iter_mut() {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1);
+                // mostly zeros, so there are runs to shorten
+                *group = if (state >> 60) < 9 {
+                    0
+                } else {
+                    (state >> 33) as u16
+                };
+            }
+            let ours = IpAddrKind::V6(groups).to_string();
+            assert_eq!(ours, std::net::Ipv6Addr::from(groups).to_string());
+            assert_eq!(parse(&ours), IpAddrKind::V6(groups));
+        }
+    }
+
+    #[test]
+    fn ipv6_errors_point_to_the_wrong_part() {
+        let error = |text: &str| text.parse::<IpAddrKind>().unwrap_err();
+        assert_eq!(
+            (error("1::2::3").part(), error("1::2::3").position),
+            ("::", 4)
+        );
+        assert_eq!(error("1:::2").position, 3);
+        assert_eq!(error("2001:db8:12345::1").part(), "12345");
+        assert_eq!(error("2001:db8:g::1").part(), "g");
+        assert_eq!(error(":1:2:3:4:5:6:7").position, 0);
+        assert_eq!(error("1:2:3:4:5:6:7").reason, "7 groups instead of 8");
+        assert_eq!(error("1:2:3:4:5:6:7:8::").part(), "::");
+        assert_eq!(error("::1.2.3.4:5").part(), "1.2.3.4");
+        assert_eq!(error("::ffff:1.2.3.256").part(), "256");
+        assert_eq!(error("fe80::1%eth0").part(), "%eth0");
+        assert_eq!(
+            error("1::2::3").to_string(),
+            "invalid IP address \"1::2::3\": only one :: is allowed, \"::\" at position 4"
+        );
+    }
+
+    #[test]
+    fn addresses_are_classified() {
+        let class = |text: &str| parse(text).class();
+        assert_eq!(class("127.0.0.1"), Class::Loopback);
+        assert_eq!(class("::1"), Class::Loopback);
+        assert_eq!(class("172.31.255.1"), Class::Private);
+        assert_eq!(class("172.32.0.1"), Class::Global);
+        assert_eq!(class("fd12:3456::1"), Class::Private);
+        assert_eq!(class("169.254.10.1"), Class::LinkLocal);
+        assert_eq!(class("fe80::1"), Class::LinkLocal);
+        assert_eq!(class("239.255.255.250"), Class::Multicast);
+        assert_eq!(class("ff02::1"), Class::Multicast);
+        assert_eq!(class("::ffff:10.0.0.1"), Class::Private);
+        assert_eq!(class("255.255.255.255"), Class::Broadcast);
+        assert_eq!(class("::"), Class::Unspecified);
+        assert_eq!(class("2001:db8::1"), Class::Global);
+        assert_eq!(parse("2001:db8::1").to_bits() >> 112, 0x2001);
+        assert_eq!(
+            IpAddrKind::v4_from_bits(0x7f000001),
+            IpAddrKind::V4_LOCALHOST
+        );
+    }
+}
@@ -0,0 +1,11 @@
+//! the library part of the enums example
+//! IpAddrKind lives here so it can be parsed, printed and routed
+
+/// IP addresses, parsed and printed as text and classified
+pub mod ip;
+
+/// CIDR blocks like 10.0.0.0/8 and the subnet math on them
+pub mod cidr;
+
+/// a routing table of CIDR blocks with longest prefix matching
+pub mod routing;
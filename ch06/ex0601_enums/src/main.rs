@@ -1,20 +1,11 @@
-/// enumerations are also referred to as enums
-/// enums allow you to define a type by enumerating its possible variants
+//! enumerations are also referred to as enums
+//! enums allow you to define a type by enumerating its possible variants
+use ex0501_structs::color::Color;
 
-/// enums give you a way of saying a value is one of a possible set of values
-/// example where an enum is more appropriate than a struct:
-/// enumerating all possible variants of an IP address
-/// data can be put directly into an enum
-/// we can put any kind of data inside an enum variant: 
-/// strings, numeric types, structs or even another enum
-#[derive(Debug)]
-#[allow(dead_code)]
-enum IpAddrKind {
-    // definition says that both variants will have associated String values
-    // each variant can have different types and amounts of associated data
-    V4(u8, u8, u8, u8), 
-    V6(String),
-} // IpAddrKind is now a custom data type
+// the IpAddrKind enum moved to the library (src/ip.rs)
+// so addresses can be parsed from text, printed and classified
+use ex0601_enums::ip::IpAddrKind;
+use ex0601_enums::routing::RoutingTable;
 
 // example of an enum with a wide variety of types
 // to achieve the same with structs we would have to define four different structs
@@ -24,7 +15,8 @@ enum Message {
     Quit,
     Move { x: i32, y: i32 },
     Write(String),
-    ChangeColor(i32, i32, i32),
+    // the Color of the structs chapter, three i32 values allowed colors like (-5, 900, 0)
+    ChangeColor(Color),
 }
 // we’re also able to define methods on enums using impl
 impl Message {
@@ -49,10 +41,20 @@ fn main() {
     // creating instances of each of the two variants of IpAddrKind
     // the variants of the enum are namespaced under its identifier
     let four = IpAddrKind::V4(127, 0, 0, 1);
-    let six = IpAddrKind::V6(String::from("::1"));
+    let six = IpAddrKind::V6([0, 0, 0, 0, 0, 0, 0, 1]);
     // instances can be used in a function that takes any kind of IP address
-    let _route_v4 = route(four);
-    let _route_v6 = route(six);
+    // the routing table knows a next hop for IPv4 and IPv6 blocks, the most specific one wins
+    let table = RoutingTable::parse(
+        "127.0.0.0/8 via lo
+::1/128 via lo
+0.0.0.0/0 via eth0
+10.0.0.0/8 via eth1",
+    )
+    .expect("the routes are valid");
+    route(&table, four);
+    route(&table, six);
+    route(&table, IpAddrKind::V4(10, 1, 2, 3));
+    route(&table, IpAddrKind::V6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]));
 
    // a first approach to store the actual IP address data using a struct
     /* 
@@ -69,13 +71,28 @@ fn main() {
         addr: String::from("::1"),
     };
     */
-    let loopback = IpAddrKind::V6(String::from("::1"));
+    let loopback = IpAddrKind::V6([0, 0, 0, 0, 0, 0, 0, 1]);
     println!("{:?}", loopback);
+
+    // addresses can be parsed from text and are printed in their shortest form
+    for text in ["192.168.0.1", "2001:DB8:0:0:0:0:2:1", "::ffff:192.0.2.1", "1::2::3", "10.0.300.1"] {
+        match text.parse::<IpAddrKind>() {
+            Ok(address) => println!("{} is {}, {:?}", text, address, address.class()),
+            Err(error) => println!("{}", error),
+        }
+    }
     
     // example for using the Message enum
     let m = Message::Write(String::from("time waits for no one"));
     m.call();
+    let m = Message::ChangeColor(Color::rgb(255, 99, 71));
+    m.call();
 
 }
 
-fn route(_ip_address: IpAddrKind) {}
\ No newline at end of file
+fn route(table: &RoutingTable, ip_address: IpAddrKind) {
+    match table.lookup(&ip_address) {
+        Some((block, next_hop)) => println!("{} goes via {} ({})", ip_address, next_hop, block),
+        None => println!("{} has no route", ip_address),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,441 @@
+// a routing table that finds the next hop for an address
+// the routes are CIDR blocks in a binary trie, one for IPv4 and one for IPv6,
+// every node stands for a prefix and its two children for the prefix followed by a 0 or a 1
+// a lookup walks down the bits of the address and keeps the last route it passes,
+// which is the longest prefix that matches, like 10.1.0.0/16 before 10.0.0.0/8
+//
+// routes can be loaded from text, one route per line like
+//     10.0.0.0/8 via eth0
+// empty lines and lines starting with # are skipped
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::cidr::Cidr;
+use crate::ip::IpAddrKind;
+
+#[derive(Debug, Clone)]
+struct Node<H> {
+    next_hop: Option<H>,
+    children: [Option<Box<Node<H>>>; 2],
+}
+
+impl<H> Default for Node<H> {
+    fn default() -> Node<H> {
+        Node {
+            next_hop: None,
+            children: [None, None],
+        }
+    }
+}
+
+impl<H> Node<H> {
+    fn is_empty(&self) -> bool {
+        self.next_hop.is_none() && self.children.iter().all(Option::is_none)
+    }
+
+    // the routes below this node, depth bits of the prefix in bits
+    fn collect<'a>(
+        &'a self,
+        bits: u128,
+        depth: u8,
+        width: u32,
+        routes: &mut Vec<(u128, u8, &'a H)>,
+    ) {
+        if let Some(hop) = &self.next_hop {
+            routes.push((bits, depth, hop));
+        }
+        for (bit, child) in self.children.iter().enumerate() {
+            if let Some(child) = child {
+                let bits = bits | (bit as u128) << (width - 1 - u32::from(depth));
+                child.collect(bits, depth + 1, width, routes);
+            }
+        }
+    }
+
+    // removes the route of the prefix, and the nodes that are left empty on the way back up
+    fn remove(&mut self, bits: u128, depth: u8, prefix: u8, width: u32) -> Option<H> {
+        if depth == prefix {
+            return self.next_hop.take();
+        }
+        let bit = (bits >> (width - 1 - u32::from(depth)) & 1) as usize;
+        let child = self.children[bit].as_mut()?;
+        let removed = child.remove(bits, depth + 1, prefix, width);
+        if child.is_empty() {
+            self.children[bit] = None;
+        }
+        removed
+    }
+}
+
+impl<H: PartialEq> Node<H> {
+    // two sibling routes to the same next hop cover their parent completely,
+    // so they become one route of the parent, the routes above merge once the ones below did
+    fn merge_siblings(&mut self) -> usize {
+        let mut merged = 0;
+        for child in self.children.iter_mut().flatten() {
+            merged += child.merge_siblings();
+        }
+        if let [Some(zero), Some(one)] = &mut self.children {
+            if zero.next_hop.is_some() && zero.next_hop == one.next_hop {
+                // the route the parent had before is hidden by the two children anyway
+                merged += if self.next_hop.is_some() { 2 } else { 1 };
+                self.next_hop = zero.next_hop.take();
+                one.next_hop = None;
+            }
+        }
+        merged
+    }
+
+    // a route to the same next hop as the route above it changes no lookup
+    fn remove_redundant(&mut self, inherited: Option<&H>) -> usize {
+        let mut removed = 0;
+        if self.next_hop.is_some() && self.next_hop.as_ref() == inherited {
+            self.next_hop = None;
+            removed += 1;
+        }
+        let inherited = self.next_hop.as_ref().or(inherited);
+        for slot in self.children.iter_mut() {
+            if let Some(child) = slot {
+                removed += child.remove_redundant(inherited);
+                if child.is_empty() {
+                    *slot = None;
+                }
+            }
+        }
+        removed
+    }
+}
+
+/// a line of a route file that isn't a route
+#[derive(Debug)]
+pub enum RouteFileError {
+    Io(io::Error),
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for RouteFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RouteFileError::Io(error) => write!(f, "{}", error),
+            RouteFileError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for RouteFileError {}
+
+impl From<io::Error> for RouteFileError {
+    fn from(error: io::Error) -> RouteFileError {
+        RouteFileError::Io(error)
+    }
+}
+
+/// the routes of IPv4 and IPv6 blocks to next hops like interface names
+#[derive(Debug, Clone)]
+pub struct RoutingTable<H = String> {
+    v4: Node<H>,
+    v6: Node<H>,
+    len: usize,
+}
+
+impl<H> Default for RoutingTable<H> {
+    fn default() -> RoutingTable<H> {
+        RoutingTable {
+            v4: Node::default(),
+            v6: Node::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<H> RoutingTable<H> {
+    pub fn new() -> RoutingTable<H> {
+        RoutingTable::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn root(&self, address: &IpAddrKind) -> &Node<H> {
+        if address.is_v4() {
+            &self.v4
+        } else {
+            &self.v6
+        }
+    }
+
+    fn root_mut(&mut self, address: &IpAddrKind) -> &mut Node<H> {
+        if address.is_v4() {
+            &mut self.v4
+        } else {
+            &mut self.v6
+        }
+    }
+
+    /// adds the route for the network of the block, returns the next hop it replaced
+    /// host bits are ignored, 10.1.2.3/8 is the route for 10.0.0.0/8
+    pub fn insert(&mut self, block: Cidr, next_hop: H) -> Option<H> {
+        let width = block.address().width();
+        let bits = block.address().to_bits();
+        let mut node = self.root_mut(&block.address());
+        for depth in 0..u32::from(block.prefix()) {
+            let bit = (bits >> (width - 1 - depth) & 1) as usize;
+            node = node.children[bit].get_or_insert_with(Box::default);
+        }
+        let replaced = node.next_hop.replace(next_hop);
+        if replaced.is_none() {
+            self.len += 1;
+        }
+        replaced
+    }
+
+    /// removes the route for the network of the block
+    pub fn remove(&mut self, block: &Cidr) -> Option<H> {
+        let width = block.address().width();
+        let bits = block.address().to_bits();
+        let removed = self
+            .root_mut(&block.address())
+            .remove(bits, 0, block.prefix(), width);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// the next hop of exactly this block
+    pub fn get(&self, block: &Cidr) -> Option<&H> {
+        let width = block.address().width();
+        let bits = block.address().to_bits();
+        let mut node = self.root(&block.address());
+        for depth in 0..u32::from(block.prefix()) {
+            let bit = (bits >> (width - 1 - depth) & 1) as usize;
+            node = node.children[bit].as_deref()?;
+        }
+        node.next_hop.as_ref()
+    }
+
+    /// the longest prefix that contains the address and its next hop
+    pub fn lookup(&self, address: &IpAddrKind) -> Option<(Cidr, &H)> {
+        let width = address.width();
+        let bits = address.to_bits();
+        let mut node = self.root(address);
+        let mut best = node.next_hop.as_ref().map(|hop| (0, hop));
+        for depth in 0..width {
+            let bit = (bits >> (width - 1 - depth) & 1) as usize;
+            match node.children[bit].as_deref() {
+                Some(child) => node = child,
+                None => break,
+            }
+            if let Some(hop) = &node.next_hop {
+                best = Some((depth + 1, hop));
+            }
+        }
+        best.map(|(prefix, hop)| {
+            let block =
+                Cidr::new(*address, prefix as u8).expect("the trie is no deeper than the address");
+            (block.network(), hop)
+        })
+    }
+
+    /// the next hop for the address, the longest matching prefix decides
+    pub fn route(&self, address: &IpAddrKind) -> Option<&H> {
+        self.lookup(address).map(|(_, hop)| hop)
+    }
+
+    /// all routes, IPv4 first, every block before the blocks inside it
+    pub fn routes(&self) -> Vec<(Cidr, &H)> {
+        let mut routes = Vec::with_capacity(self.len);
+        for (root, example) in [
+            (&self.v4, IpAddrKind::V4(0, 0, 0, 0)),
+            (&self.v6, IpAddrKind::V6([0; 8])),
+        ] {
+            let mut found = Vec::new();
+            root.collect(0, 0, example.width(), &mut found);
+            routes.extend(found.into_iter().map(|(bits, prefix, hop)| {
+                let block = Cidr::new(example.with_bits(bits), prefix)
+                    .expect("the trie is no deeper than the address");
+                (block, hop)
+            }));
+        }
+        routes
+    }
+}
+
+impl<H: PartialEq> RoutingTable<H> {
+    /// makes the table smaller without changing where any address is routed:
+    /// two neighbouring blocks with the same next hop become one block,
+    /// 10.0.0.0/9 and 10.128.0.0/9 via eth0 become 10.0.0.0/8 via eth0,
+    /// and a block inside a block with the same next hop is dropped
+    /// returns how many routes are gone
+    pub fn aggregate(&mut self) -> usize {
+        let before = self.len;
+        for root in [&mut self.v4, &mut self.v6] {
+            let merged = root.merge_siblings();
+            let removed = root.remove_redundant(None);
+            self.len -= merged + removed;
+        }
+        before - self.len
+    }
+}
+
+impl RoutingTable<String> {
+    /// the routes of a text with lines like 10.0.0.0/8 via eth0
+    pub fn parse(text: &str) -> Result<RoutingTable<String>, RouteFileError> {
+        let mut table = RoutingTable::new();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let syntax = |message: String| RouteFileError::Syntax {
+                line: line_number,
+                message,
+            };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let [block, "via", next_hop] = words[..] else {
+                return Err(syntax(format!("{:?} isn't like 10.0.0.0/8 via eth0", line)));
+            };
+            let block: Cidr = block
+                .parse()
+                .map_err(|error| syntax(format!("{}", error)))?;
+            if table.insert(block, String::from(next_hop)).is_some() {
+                return Err(syntax(format!("{} has a route already", block.network())));
+            }
+        }
+        Ok(table)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<RoutingTable<String>, RouteFileError> {
+        RoutingTable::parse(&fs::read_to_string(path)?)
+    }
+}
+
+/// the routes in the format of the route files
+impl<H: fmt::Display> fmt::Display for RoutingTable<H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (block, hop) in self.routes() {
+            writeln!(f, "{} via {}", block, hop)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTES: &str = "# a small network
+0.0.0.0/0 via wan0
+10.0.0.0/8 via eth0
+10.1.0.0/16 via eth1
+
+10.1.2.0/24 via eth2
+2001:db8::/32 via eth6
+2001:db8:1::/48 via eth7
+";
+
+    fn address(text: &str) -> IpAddrKind {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn the_longest_prefix_wins() {
+        let table = RoutingTable::parse(ROUTES).unwrap();
+        assert_eq!(table.len(), 6);
+        let hop = |text: &str| table.route(&address(text)).map(String::as_str);
+        assert_eq!(hop("10.1.2.3"), Some("eth2"));
+        assert_eq!(hop("10.1.3.3"), Some("eth1"));
+        assert_eq!(hop("10.200.0.1"), Some("eth0"));
+        assert_eq!(hop("8.8.8.8"), Some("wan0"));
+        assert_eq!(hop("2001:db8:1::1"), Some("eth7"));
+        assert_eq!(hop("2001:db8:2::1"), Some("eth6"));
+        // there is no default route for IPv6
+        assert_eq!(hop("2001:db9::1"), None);
+        let (block, _) = table.lookup(&address("10.1.3.3")).unwrap();
+        assert_eq!(block.to_string(), "10.1.0.0/16");
+        assert_eq!(
+            table.to_string(),
+            ROUTES
+                .replace("# a small network\n", "")
+                .replace("\n\n", "\n")
+        );
+    }
+
+    #[test]
+    fn routes_are_inserted_and_removed() {
+        let mut table = RoutingTable::parse(ROUTES).unwrap();
+        let block: Cidr = "10.1.2.99/24".parse().unwrap();
+        assert_eq!(table.get(&block).map(String::as_str), Some("eth2"));
+        assert_eq!(
+            table.insert(block, String::from("eth9")),
+            Some(String::from("eth2"))
+        );
+        assert_eq!(table.remove(&block), Some(String::from("eth9")));
+        assert_eq!(table.remove(&block), None);
+        assert_eq!(
+            table.route(&address("10.1.2.3")).map(String::as_str),
+            Some("eth1")
+        );
+        assert_eq!(table.len(), 5);
+        // the emptied branch is gone
+        assert!(table.v4.children[0].as_ref().unwrap().children[0].is_some());
+
+        let error = RoutingTable::parse("10.0.0.0/8 via eth0\n10.0.0.0/33 via eth1").unwrap_err();
+        assert!(error.to_string().starts_with("line 2: invalid IP address"));
+        let error = RoutingTable::parse("10.0.0.0/8 eth0").unwrap_err();
+        assert!(error.to_string().starts_with("line 1:"));
+        assert!(RoutingTable::parse("10.0.0.0/8 via a\n10.1.2.3/8 via b").is_err());
+    }
+
+    #[test]
+    fn aggregation_keeps_every_route() {
+        let mut table = RoutingTable::parse(
+            "10.0.0.0/9 via eth0
+10.128.0.0/9 via eth0
+10.0.0.0/24 via eth0
+10.0.1.0/24 via eth1
+192.168.0.0/24 via eth2
+192.168.1.0/24 via eth2
+192.168.2.0/24 via eth2
+192.168.3.0/24 via eth2
+192.168.0.0/22 via eth3
+2001:db8::/33 via eth6
+2001:db8:8000::/33 via eth6",
+        )
+        .unwrap();
+        let probes: Vec<IpAddrKind> = (0..2000u32)
+            .map(|i| {
+                let bits = i.wrapping_mul(2_654_435_761);
+                match i % 3 {
+                    0 => IpAddrKind::V4(10, (bits >> 24) as u8, (bits >> 16) as u8 & 3, bits as u8),
+                    1 => IpAddrKind::V4(192, 168, (bits >> 8) as u8 & 7, bits as u8),
+                    _ => IpAddrKind::v6_from_bits(0x2001_0db8 << 96 | u128::from(bits) << 64),
+                }
+            })
+            .collect();
+        let before: Vec<Option<String>> = probes
+            .iter()
+            .map(|probe| table.route(probe).cloned())
+            .collect();
+
+        assert_eq!(table.aggregate(), 7);
+        let after: Vec<Option<String>> = probes
+            .iter()
+            .map(|probe| table.route(probe).cloned())
+            .collect();
+        assert_eq!(before, after);
+        assert_eq!(
+            table.to_string(),
+            "10.0.0.0/8 via eth0\n10.0.1.0/24 via eth1\n192.168.0.0/22 via eth2\n2001:db8::/32 via eth6\n"
+        );
+        assert_eq!(table.len(), table.routes().len());
+    }
+}
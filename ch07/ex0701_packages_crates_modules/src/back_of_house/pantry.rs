@@ -0,0 +1,372 @@
+// the pantry is where the back of house keeps its ingredients
+// every menu item has a recipe that says how much of each ingredient one portion uses
+// cooking takes the ingredients out of the stock
+// and an item that can't be cooked anymore is 86'd (taken off the menu for the rest of the service)
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use crate::menu::{Appetizer, MenuItem};
+use crate::serving::Order;
+
+/// the unit an ingredient is counted in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Grams,
+    Milliliters,
+    Pieces,
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unit::Grams => write!(f, "g"),
+            Unit::Milliliters => write!(f, "ml"),
+            Unit::Pieces => write!(f, "pcs"),
+        }
+    }
+}
+
+/// the ingredients of one portion of a menu item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipe {
+    pub ingredients: Vec<(String, u32)>,
+}
+
+impl Recipe {
+    pub fn new(ingredients: &[(&str, u32)]) -> Recipe {
+        Recipe {
+            ingredients: ingredients
+                .iter()
+                .map(|&(name, quantity)| (String::from(name), quantity))
+                .collect(),
+        }
+    }
+}
+
+/// the recipes of the kitchen, one per menu item
+/// breakfasts need one recipe per toast because the bread differs
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RecipeBook {
+    recipes: HashMap<MenuItem, Recipe>,
+}
+
+impl RecipeBook {
+    pub fn new() -> RecipeBook {
+        RecipeBook::default()
+    }
+
+    pub fn add(&mut self, item: MenuItem, recipe: Recipe) {
+        self.recipes.insert(item, recipe);
+    }
+
+    pub fn recipe_for(&self, item: &MenuItem) -> Option<&Recipe> {
+        self.recipes.get(item)
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &MenuItem> {
+        self.recipes.keys()
+    }
+
+    /// the recipes of the summer menu
+    /// every breakfast comes with peaches as its seasonal fruit
+    pub fn summer() -> RecipeBook {
+        let mut book = RecipeBook::new();
+        for (toast, bread) in [("Rye", "rye bread"), ("Wheat", "wheat bread"), ("White", "white bread")] {
+            book.add(
+                MenuItem::breakfast(toast),
+                Recipe::new(&[(bread, 2), ("butter", 10), ("eggs", 2), ("peaches", 1)]),
+            );
+        }
+        book.add(
+            MenuItem::Appetizer(Appetizer::Soup),
+            Recipe::new(&[("vegetable stock", 300), ("carrots", 80), ("onions", 40)]),
+        );
+        book.add(
+            MenuItem::Appetizer(Appetizer::Salad),
+            Recipe::new(&[("lettuce", 120), ("tomatoes", 1), ("olive oil", 15)]),
+        );
+        book
+    }
+}
+
+/// how much of an ingredient is on hand and when to order more
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StockLevel {
+    pub on_hand: u32,
+    pub unit: Unit,
+    // at or below this quantity the ingredient shows up in the reorder report
+    pub reorder_point: u32,
+    // a reorder brings the stock back up to this quantity
+    pub par_level: u32,
+}
+
+/// one line of the reorder report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reorder {
+    pub ingredient: String,
+    pub on_hand: u32,
+    pub reorder_point: u32,
+    pub order_quantity: u32,
+    pub unit: Unit,
+}
+
+impl fmt::Display for Reorder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<16} on hand {:>6} {:<3} reorder point {:>6}  order {:>6} {}",
+            self.ingredient, self.on_hand, self.unit, self.reorder_point, self.order_quantity, self.unit
+        )
+    }
+}
+
+/// why the kitchen can't cook something
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KitchenError {
+    NoRecipe(MenuItem),
+    EightySixed(MenuItem),
+    OutOfStock {
+        ingredient: String,
+        needed: u32,
+        on_hand: u32,
+    },
+}
+
+impl fmt::Display for KitchenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KitchenError::NoRecipe(item) => write!(f, "there is no recipe for {}", item),
+            KitchenError::EightySixed(item) => write!(f, "{} is 86'd", item),
+            KitchenError::OutOfStock {
+                ingredient,
+                needed,
+                on_hand,
+            } => write!(f, "{} {} needed but only {} on hand", needed, ingredient, on_hand),
+        }
+    }
+}
+
+impl std::error::Error for KitchenError {}
+
+/// the stock of every ingredient and the recipes that use them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pantry {
+    recipes: RecipeBook,
+    stock: HashMap<String, StockLevel>,
+    eighty_sixed: HashSet<MenuItem>,
+}
+
+impl Pantry {
+    /// a pantry starts empty so every item is 86'd until ingredients arrive
+    pub fn new(recipes: RecipeBook) -> Pantry {
+        let mut pantry = Pantry {
+            recipes,
+            stock: HashMap::new(),
+            eighty_sixed: HashSet::new(),
+        };
+        pantry.update_eighty_sixed();
+        pantry
+    }
+
+    /// tells the pantry about an ingredient and when it should be reordered
+    /// the quantity on hand of a known ingredient is kept
+    pub fn stock_ingredient(&mut self, ingredient: &str, unit: Unit, reorder_point: u32, par_level: u32) {
+        let level = self.stock.entry(String::from(ingredient)).or_insert(StockLevel {
+            on_hand: 0,
+            unit,
+            reorder_point,
+            par_level,
+        });
+        level.unit = unit;
+        level.reorder_point = reorder_point;
+        level.par_level = par_level;
+    }
+
+    /// a delivery arrived, items that can be cooked again come back on the menu
+    /// an ingredient that wasn't stocked before is counted in pieces
+    pub fn receive(&mut self, ingredient: &str, quantity: u32) {
+        let level = self.stock.entry(String::from(ingredient)).or_insert(StockLevel {
+            on_hand: 0,
+            unit: Unit::Pieces,
+            reorder_point: 0,
+            par_level: 0,
+        });
+        level.on_hand = level.on_hand.saturating_add(quantity);
+        self.update_eighty_sixed();
+    }
+
+    pub fn on_hand(&self, ingredient: &str) -> u32 {
+        self.stock.get(ingredient).map_or(0, |level| level.on_hand)
+    }
+
+    pub fn stock_level(&self, ingredient: &str) -> Option<&StockLevel> {
+        self.stock.get(ingredient)
+    }
+
+    pub fn recipes(&self) -> &RecipeBook {
+        &self.recipes
+    }
+
+    /// false for 86'd items and items the kitchen has no recipe for
+    pub fn is_available(&self, item: &MenuItem) -> bool {
+        self.recipes.recipe_for(item).is_some() && !self.eighty_sixed.contains(item)
+    }
+
+    /// every item that is off the menu, sorted by name
+    pub fn eighty_sixed(&self) -> Vec<MenuItem> {
+        let mut items: Vec<MenuItem> = self.eighty_sixed.iter().cloned().collect();
+        items.sort_by_key(|item| item.to_string());
+        items
+    }
+
+    /// cooks every line of the order or nothing at all
+    /// the whole order is checked before any ingredient leaves the pantry
+    /// so a failed order never leaves the stock half depleted
+    pub fn cook(&mut self, order: &Order) -> Result<(), KitchenError> {
+        let needed = self.ingredients_for(order.lines.iter().map(|line| (&line.item, line.quantity)))?;
+        for (ingredient, quantity) in &needed {
+            let on_hand = self.on_hand(ingredient);
+            if on_hand < *quantity {
+                return Err(KitchenError::OutOfStock {
+                    ingredient: ingredient.clone(),
+                    needed: *quantity,
+                    on_hand,
+                });
+            }
+        }
+
+        for (ingredient, quantity) in needed {
+            if let Some(level) = self.stock.get_mut(&ingredient) {
+                level.on_hand -= quantity;
+            }
+        }
+        self.update_eighty_sixed();
+        Ok(())
+    }
+
+    /// the ingredients at or below their reorder point, sorted by name
+    /// with the quantity that brings each of them back up to par
+    pub fn reorder_report(&self) -> Vec<Reorder> {
+        let mut report: Vec<Reorder> = self
+            .stock
+            .iter()
+            .filter(|(_, level)| level.on_hand <= level.reorder_point)
+            .map(|(ingredient, level)| Reorder {
+                ingredient: ingredient.clone(),
+                on_hand: level.on_hand,
+                reorder_point: level.reorder_point,
+                order_quantity: level.par_level.saturating_sub(level.on_hand),
+                unit: level.unit,
+            })
+            .collect();
+        report.sort_by(|a, b| a.ingredient.cmp(&b.ingredient));
+        report
+    }
+
+    // adds up the ingredients of several items
+    // a BTreeMap keeps the order of the checks and the errors deterministic
+    fn ingredients_for<'a>(
+        &self,
+        items: impl Iterator<Item = (&'a MenuItem, u32)>,
+    ) -> Result<BTreeMap<String, u32>, KitchenError> {
+        let mut needed = BTreeMap::new();
+        for (item, quantity) in items {
+            let recipe = self
+                .recipes
+                .recipe_for(item)
+                .ok_or_else(|| KitchenError::NoRecipe(item.clone()))?;
+            if self.eighty_sixed.contains(item) {
+                return Err(KitchenError::EightySixed(item.clone()));
+            }
+            for (ingredient, amount) in &recipe.ingredients {
+                let total: &mut u32 = needed.entry(ingredient.clone()).or_default();
+                *total = total.saturating_add(amount.saturating_mul(quantity));
+            }
+        }
+        Ok(needed)
+    }
+
+    // an item is 86'd as soon as a single portion can't be cooked anymore
+    fn update_eighty_sixed(&mut self) {
+        let eighty_sixed: HashSet<MenuItem> = self
+            .recipes
+            .recipes
+            .iter()
+            .filter(|(_, recipe)| {
+                recipe
+                    .ingredients
+                    .iter()
+                    .any(|(ingredient, amount)| self.on_hand(ingredient) < *amount)
+            })
+            .map(|(item, _)| item.clone())
+            .collect();
+        self.eighty_sixed = eighty_sixed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pantry() -> Pantry {
+        let mut pantry = Pantry::new(RecipeBook::summer());
+        pantry.stock_ingredient("vegetable stock", Unit::Milliliters, 600, 3000);
+        pantry.receive("vegetable stock", 900);
+        pantry.receive("carrots", 1000);
+        pantry.receive("onions", 1000);
+        pantry
+    }
+
+    fn soups(count: u32) -> Order {
+        let mut order = Order::new(1);
+        order.add(1, MenuItem::Appetizer(Appetizer::Soup), count);
+        order
+    }
+
+    #[test]
+    fn cooking_depletes_the_stock() {
+        let mut pantry = pantry();
+        pantry.cook(&soups(2)).unwrap();
+        assert_eq!(pantry.on_hand("vegetable stock"), 300);
+        assert_eq!(pantry.on_hand("carrots"), 840);
+        assert!(pantry.is_available(&MenuItem::Appetizer(Appetizer::Soup)));
+    }
+
+    #[test]
+    fn running_out_86s_the_item_until_the_next_delivery() {
+        let mut pantry = pantry();
+        let soup = MenuItem::Appetizer(Appetizer::Soup);
+        pantry.cook(&soups(3)).unwrap();
+        assert!(!pantry.is_available(&soup));
+        assert_eq!(pantry.cook(&soups(1)), Err(KitchenError::EightySixed(soup.clone())));
+
+        pantry.receive("vegetable stock", 300);
+        assert!(pantry.is_available(&soup));
+    }
+
+    #[test]
+    fn a_failed_order_leaves_the_stock_untouched() {
+        let mut pantry = pantry();
+        let result = pantry.cook(&soups(4));
+        assert_eq!(
+            result,
+            Err(KitchenError::OutOfStock {
+                ingredient: String::from("vegetable stock"),
+                needed: 1200,
+                on_hand: 900,
+            })
+        );
+        assert_eq!(pantry.on_hand("carrots"), 1000);
+    }
+
+    #[test]
+    fn low_stock_shows_up_in_the_reorder_report() {
+        let mut pantry = pantry();
+        assert!(pantry.reorder_report().is_empty());
+        pantry.cook(&soups(1)).unwrap();
+        let report = pantry.reorder_report();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].ingredient, "vegetable stock");
+        assert_eq!(report[0].order_quantity, 2400);
+    }
+}
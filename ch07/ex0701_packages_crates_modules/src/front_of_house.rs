@@ -9,10 +9,6 @@ pub mod hosting;
 // front of house is (2) where  servers take orders
 // orders are served and payment are taken
 // hosting and serving are siblings to each other
-mod serving {
-    fn _take_order() {}
-
-    fn _serve_order() {}
-
-    fn _take_payment() {}
-}
\ No newline at end of file
+// serving lives in its own file just like hosting
+// and is made public so that bills can be settled from outside the crate
+pub mod serving;
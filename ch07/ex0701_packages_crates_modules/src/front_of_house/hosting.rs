@@ -1,6 +1,194 @@
+use std::collections::VecDeque;
+use std::fmt;
+
 // making the module public doesn’t make its contents public
 // the items within the module need to be made public as well
 // to expose them to the outside world
 pub fn add_to_waitlist() {}
 
-fn _seat_at_table() {}
+// parties that booked ahead are kept in the reservation book
+pub mod reservations;
+
+/// a group of guests that arrives and is seated together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Party {
+    pub name: String,
+    pub size: u32,
+}
+
+impl Party {
+    pub fn new(name: &str, size: u32) -> Party {
+        Party {
+            name: String::from(name),
+            size,
+        }
+    }
+}
+
+/// the parties waiting for a table in the order they arrived
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Waitlist {
+    parties: VecDeque<Party>,
+}
+
+impl Waitlist {
+    pub fn new() -> Waitlist {
+        Waitlist::default()
+    }
+
+    pub fn add(&mut self, party: Party) {
+        self.parties.push_back(party);
+    }
+
+    /// takes a party off the waitlist, for example because it left
+    pub fn remove(&mut self, name: &str) -> Option<Party> {
+        let index = self.parties.iter().position(|party| party.name == name)?;
+        self.parties.remove(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.parties.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parties.is_empty()
+    }
+
+    pub fn parties(&self) -> impl Iterator<Item = &Party> {
+        self.parties.iter()
+    }
+}
+
+/// a table in the dining room and the party sitting at it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub number: u32,
+    pub seats: u32,
+    pub party: Option<Party>,
+}
+
+impl Table {
+    pub fn is_free(&self) -> bool {
+        self.party.is_none()
+    }
+}
+
+/// all the tables of the dining room
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Floor {
+    tables: Vec<Table>,
+}
+
+impl Floor {
+    pub fn new() -> Floor {
+        Floor::default()
+    }
+
+    /// a floor with one table per entry, numbered from 1
+    pub fn with_tables(seats: &[u32]) -> Floor {
+        let mut floor = Floor::new();
+        for (index, &seats) in seats.iter().enumerate() {
+            floor.add_table(index as u32 + 1, seats);
+        }
+        floor
+    }
+
+    pub fn add_table(&mut self, number: u32, seats: u32) {
+        self.tables.push(Table {
+            number,
+            seats,
+            party: None,
+        });
+    }
+
+    pub fn tables(&self) -> &[Table] {
+        &self.tables
+    }
+
+    pub fn table(&self, number: u32) -> Option<&Table> {
+        self.tables.iter().find(|table| table.number == number)
+    }
+
+    /// puts a party at a specific table
+    pub fn seat(&mut self, number: u32, party: Party) -> Result<(), SeatingError> {
+        let table = self
+            .tables
+            .iter_mut()
+            .find(|table| table.number == number)
+            .ok_or(SeatingError::NoSuchTable(number))?;
+        if let Some(seated) = &table.party {
+            return Err(SeatingError::Occupied {
+                table: number,
+                by: seated.name.clone(),
+            });
+        }
+        if party.size > table.seats {
+            return Err(SeatingError::TooSmall {
+                table: number,
+                seats: table.seats,
+                size: party.size,
+            });
+        }
+        table.party = Some(party);
+        Ok(())
+    }
+
+    /// the party leaves and the table is free again
+    pub fn clear(&mut self, number: u32) -> Option<Party> {
+        self.tables
+            .iter_mut()
+            .find(|table| table.number == number)
+            .and_then(|table| table.party.take())
+    }
+
+    // the smallest free table the party fits at so big tables stay free for big parties
+    fn best_table_for(&self, size: u32, held: &[u32]) -> Option<u32> {
+        self.tables
+            .iter()
+            .filter(|table| table.is_free() && table.seats >= size && !held.contains(&table.number))
+            .min_by_key(|table| (table.seats, table.number))
+            .map(|table| table.number)
+    }
+}
+
+/// seats the first party on the waitlist that fits a free table
+/// a large party doesn't hold up smaller parties behind it when no large table is free
+/// returns the table number and the party that was seated
+pub fn seat_at_table(waitlist: &mut Waitlist, floor: &mut Floor) -> Option<(u32, Party)> {
+    seat_at_unheld_table(waitlist, floor, &[])
+}
+
+/// like seat_at_table but keeps the held tables free
+/// for example the tables that are held for upcoming reservations
+pub fn seat_at_unheld_table(waitlist: &mut Waitlist, floor: &mut Floor, held: &[u32]) -> Option<(u32, Party)> {
+    let (index, number) = waitlist.parties.iter().enumerate().find_map(|(index, party)| {
+        floor
+            .best_table_for(party.size, held)
+            .map(|number| (index, number))
+    })?;
+    let party = waitlist.parties.remove(index)?;
+    floor.seat(number, party.clone()).ok()?;
+    Some((number, party))
+}
+
+/// why a party couldn't be seated at a table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeatingError {
+    NoSuchTable(u32),
+    Occupied { table: u32, by: String },
+    TooSmall { table: u32, seats: u32, size: u32 },
+}
+
+impl fmt::Display for SeatingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SeatingError::NoSuchTable(table) => write!(f, "there is no table {}", table),
+            SeatingError::Occupied { table, by } => write!(f, "table {} is taken by {}", table, by),
+            SeatingError::TooSmall { table, seats, size } => {
+                write!(f, "table {} has {} seats but the party is {}", table, seats, size)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SeatingError {}
@@ -0,0 +1,475 @@
+// a reservation holds a table for a party for a slot of the day
+// two reservations for the same table must never overlap
+// parties that don't show up in time lose their table to the waitlist
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{Floor, Party, Waitlist};
+
+/// the longest reservation, a whole day
+pub const MAX_SLOT_MINUTES: u32 = 24 * 60;
+
+/// a time of the day in minutes since midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay(u32);
+
+impl TimeOfDay {
+    pub fn new(hours: u32, minutes: u32) -> Option<TimeOfDay> {
+        if hours < 24 && minutes < 60 {
+            Some(TimeOfDay(hours * 60 + minutes))
+        } else {
+            None
+        }
+    }
+
+    pub fn minutes(self) -> u32 {
+        self.0
+    }
+
+    /// the time a number of minutes later, a slot may run past midnight
+    /// a time past u32::MAX minutes stays there, no slot or grace period is that long
+    pub fn plus(self, minutes: u32) -> TimeOfDay {
+        TimeOfDay(self.0.saturating_add(minutes))
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+/// parses times like "18:30"
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TimeOfDay, String> {
+        let invalid = || format!("invalid time `{}`, expected HH:MM", s);
+        let (hours, minutes) = s.trim().split_once(':').ok_or_else(invalid)?;
+        let hours = hours.parse().map_err(|_| invalid())?;
+        let minutes = minutes.parse().map_err(|_| invalid())?;
+        TimeOfDay::new(hours, minutes).ok_or_else(invalid)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Booked,
+    Seated { at: TimeOfDay },
+    NoShow,
+    // a no-show that came after all and was put on the waitlist
+    Waitlisted,
+    Cancelled,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Booked => write!(f, "booked"),
+            Status::Seated { .. } => write!(f, "seated"),
+            Status::NoShow => write!(f, "no-show"),
+            Status::Waitlisted => write!(f, "waitlisted"),
+            Status::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reservation {
+    pub id: u32,
+    pub party: Party,
+    pub table: u32,
+    pub start: TimeOfDay,
+    pub minutes: u32,
+    pub status: Status,
+}
+
+impl Reservation {
+    pub fn end(&self) -> TimeOfDay {
+        self.start.plus(self.minutes)
+    }
+
+    // cancelled reservations and no-shows don't hold their table anymore
+    fn holds_table(&self) -> bool {
+        matches!(self.status, Status::Booked | Status::Seated { .. })
+    }
+
+    fn overlaps(&self, start: TimeOfDay, minutes: u32) -> bool {
+        self.start < start.plus(minutes) && start < self.end()
+    }
+}
+
+/// what happened when a party with a reservation arrived
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckIn {
+    Seated { table: u32, late: bool },
+    // the table was released, the party waits like a walk-in
+    Waitlisted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReservationError {
+    EmptyParty,
+    EmptySlot,
+    // longer than MAX_SLOT_MINUTES
+    SlotTooLong(u32),
+    NoSuchReservation(u32),
+    NoSuchTable(u32),
+    TooLarge { table: u32, seats: u32, size: u32 },
+    Conflict { table: u32, with: u32 },
+    NoTableFree,
+    NotBooked { id: u32, status: Status },
+    TableOccupied(u32),
+}
+
+impl fmt::Display for ReservationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReservationError::EmptyParty => write!(f, "a party needs at least one guest"),
+            ReservationError::EmptySlot => write!(f, "a reservation needs to last at least one minute"),
+            ReservationError::SlotTooLong(minutes) => {
+                write!(f, "a reservation of {} minutes is longer than a day", minutes)
+            }
+            ReservationError::NoSuchReservation(id) => write!(f, "there is no reservation {}", id),
+            ReservationError::NoSuchTable(table) => write!(f, "there is no table {}", table),
+            ReservationError::TooLarge { table, seats, size } => {
+                write!(f, "table {} has {} seats but the party is {}", table, seats, size)
+            }
+            ReservationError::Conflict { table, with } => {
+                write!(f, "table {} is already reserved by reservation {}", table, with)
+            }
+            ReservationError::NoTableFree => write!(f, "no table large enough is free at that time"),
+            ReservationError::NotBooked { id, status } => write!(f, "reservation {} is {}", id, status),
+            ReservationError::TableOccupied(table) => write!(f, "table {} is still occupied", table),
+        }
+    }
+}
+
+impl std::error::Error for ReservationError {}
+
+/// the reservations of one day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReservationBook {
+    // the number and the seats of every table that can be reserved
+    tables: Vec<(u32, u32)>,
+    reservations: Vec<Reservation>,
+    next_id: u32,
+    // how late a party may be before its table is given away
+    grace_minutes: u32,
+    // how long before a reservation its table is kept free of walk-ins
+    hold_minutes: u32,
+}
+
+impl ReservationBook {
+    pub fn new(floor: &Floor, grace_minutes: u32, hold_minutes: u32) -> ReservationBook {
+        ReservationBook {
+            tables: floor.tables().iter().map(|table| (table.number, table.seats)).collect(),
+            reservations: Vec::new(),
+            next_id: 1,
+            grace_minutes,
+            hold_minutes,
+        }
+    }
+
+    pub fn reservations(&self) -> &[Reservation] {
+        &self.reservations
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Reservation> {
+        self.reservations.iter().find(|reservation| reservation.id == id)
+    }
+
+    /// books the smallest table that fits the party and is free for the whole slot
+    pub fn book(&mut self, party: Party, start: TimeOfDay, minutes: u32) -> Result<u32, ReservationError> {
+        Self::check_request(&party, minutes)?;
+        let mut candidates: Vec<(u32, u32)> = self
+            .tables
+            .iter()
+            .copied()
+            .filter(|&(_, seats)| seats >= party.size)
+            .collect();
+        candidates.sort_by_key(|&(number, seats)| (seats, number));
+        let table = candidates
+            .into_iter()
+            .map(|(number, _)| number)
+            .find(|&number| self.conflict(number, start, minutes).is_none())
+            .ok_or(ReservationError::NoTableFree)?;
+        Ok(self.insert(party, table, start, minutes))
+    }
+
+    /// books a specific table, for example because the guests asked for the window
+    pub fn book_table(
+        &mut self,
+        table: u32,
+        party: Party,
+        start: TimeOfDay,
+        minutes: u32,
+    ) -> Result<u32, ReservationError> {
+        Self::check_request(&party, minutes)?;
+        let seats = self
+            .tables
+            .iter()
+            .find(|&&(number, _)| number == table)
+            .map(|&(_, seats)| seats)
+            .ok_or(ReservationError::NoSuchTable(table))?;
+        if party.size > seats {
+            return Err(ReservationError::TooLarge {
+                table,
+                seats,
+                size: party.size,
+            });
+        }
+        if let Some(with) = self.conflict(table, start, minutes) {
+            return Err(ReservationError::Conflict { table, with });
+        }
+        Ok(self.insert(party, table, start, minutes))
+    }
+
+    pub fn cancel(&mut self, id: u32) -> Result<(), ReservationError> {
+        let reservation = self.booked_mut(id)?;
+        reservation.status = Status::Cancelled;
+        Ok(())
+    }
+
+    /// the tables walk-ins shouldn't be seated at right now
+    /// because a booked party is about to arrive or is still within its grace period
+    pub fn held_tables(&self, now: TimeOfDay) -> Vec<u32> {
+        let mut held: Vec<u32> = self
+            .reservations
+            .iter()
+            .filter(|reservation| reservation.status == Status::Booked)
+            .filter(|reservation| {
+                let from = reservation.start.minutes().saturating_sub(self.hold_minutes);
+                let until = reservation.start.plus(self.grace_minutes).minutes();
+                (from..=until).contains(&now.minutes())
+            })
+            .map(|reservation| reservation.table)
+            .collect();
+        held.sort_unstable();
+        held.dedup();
+        held
+    }
+
+    /// seats a party that arrived for its reservation
+    /// a party that is late but within its grace period still gets its table
+    /// a party whose table was already released joins the waitlist, once
+    pub fn check_in(
+        &mut self,
+        id: u32,
+        now: TimeOfDay,
+        floor: &mut Floor,
+        waitlist: &mut Waitlist,
+    ) -> Result<CheckIn, ReservationError> {
+        let reservation = self
+            .reservations
+            .iter_mut()
+            .find(|reservation| reservation.id == id)
+            .ok_or(ReservationError::NoSuchReservation(id))?;
+        match reservation.status {
+            Status::Booked => {}
+            Status::NoShow => {
+                waitlist.add(reservation.party.clone());
+                reservation.status = Status::Waitlisted;
+                return Ok(CheckIn::Waitlisted);
+            }
+            // checking in again doesn't put the party on the waitlist twice
+            Status::Waitlisted => return Ok(CheckIn::Waitlisted),
+            status => return Err(ReservationError::NotBooked { id, status }),
+        }
+        floor
+            .seat(reservation.table, reservation.party.clone())
+            .map_err(|_| ReservationError::TableOccupied(reservation.table))?;
+        reservation.status = Status::Seated { at: now };
+        Ok(CheckIn::Seated {
+            table: reservation.table,
+            late: now > reservation.start,
+        })
+    }
+
+    /// marks every booked party that is past its grace period as a no-show
+    /// and seats waiting walk-ins at the tables that became free
+    /// returns the ids of the no-shows
+    pub fn release_no_shows(&mut self, now: TimeOfDay, floor: &mut Floor, waitlist: &mut Waitlist) -> Vec<u32> {
+        let grace = self.grace_minutes;
+        let mut released = Vec::new();
+        for reservation in &mut self.reservations {
+            if reservation.status == Status::Booked && now > reservation.start.plus(grace) {
+                reservation.status = Status::NoShow;
+                released.push(reservation.id);
+            }
+        }
+        let held = self.held_tables(now);
+        while super::seat_at_unheld_table(waitlist, floor, &held).is_some() {}
+        released
+    }
+
+    /// the reservation sheet of the day as CSV, sorted by time and table
+    pub fn to_csv(&self) -> String {
+        let mut sorted: Vec<&Reservation> = self.reservations.iter().collect();
+        sorted.sort_by_key(|reservation| (reservation.start, reservation.table, reservation.id));
+
+        let mut csv = String::from("id,name,size,table,start,end,status,arrived\n");
+        for reservation in sorted {
+            let arrived = match reservation.status {
+                Status::Seated { at } => at.to_string(),
+                _ => String::new(),
+            };
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{}\n",
+                reservation.id,
+                csv_field(&reservation.party.name),
+                reservation.party.size,
+                reservation.table,
+                reservation.start,
+                reservation.end(),
+                reservation.status,
+                arrived
+            ));
+        }
+        csv
+    }
+
+    pub fn export_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_csv())
+    }
+
+    fn check_request(party: &Party, minutes: u32) -> Result<(), ReservationError> {
+        if party.size == 0 {
+            return Err(ReservationError::EmptyParty);
+        }
+        if minutes == 0 {
+            return Err(ReservationError::EmptySlot);
+        }
+        if minutes > MAX_SLOT_MINUTES {
+            return Err(ReservationError::SlotTooLong(minutes));
+        }
+        Ok(())
+    }
+
+    // the id of a reservation that holds the table during the slot
+    fn conflict(&self, table: u32, start: TimeOfDay, minutes: u32) -> Option<u32> {
+        self.reservations
+            .iter()
+            .find(|r| r.table == table && r.holds_table() && r.overlaps(start, minutes))
+            .map(|r| r.id)
+    }
+
+    fn insert(&mut self, party: Party, table: u32, start: TimeOfDay, minutes: u32) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.reservations.push(Reservation {
+            id,
+            party,
+            table,
+            start,
+            minutes,
+            status: Status::Booked,
+        });
+        id
+    }
+
+    fn booked_mut(&mut self, id: u32) -> Result<&mut Reservation, ReservationError> {
+        let reservation = self
+            .reservations
+            .iter_mut()
+            .find(|reservation| reservation.id == id)
+            .ok_or(ReservationError::NoSuchReservation(id))?;
+        if reservation.status != Status::Booked {
+            return Err(ReservationError::NotBooked {
+                id,
+                status: reservation.status,
+            });
+        }
+        Ok(reservation)
+    }
+}
+
+// fields with commas, quotes or line breaks are quoted
+// and quotes inside them are doubled
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> TimeOfDay {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn overlapping_reservations_get_different_tables() {
+        let floor = Floor::with_tables(&[2, 4]);
+        let mut book = ReservationBook::new(&floor, 15, 30);
+        let first = book.book(Party::new("Ada", 2), time("18:00"), 90).unwrap();
+        let second = book.book(Party::new("Grace", 2), time("19:00"), 90).unwrap();
+        assert_eq!(book.get(first).unwrap().table, 1);
+        assert_eq!(book.get(second).unwrap().table, 2);
+
+        // both tables are taken at 19:15, but the first one is free again at 19:30
+        assert_eq!(
+            book.book(Party::new("Linus", 2), time("19:15"), 60),
+            Err(ReservationError::NoTableFree)
+        );
+        assert_eq!(
+            book.book_table(1, Party::new("Linus", 2), time("19:15"), 60),
+            Err(ReservationError::Conflict { table: 1, with: first })
+        );
+        assert!(book.book_table(1, Party::new("Linus", 2), time("19:30"), 60).is_ok());
+
+        assert_eq!(
+            book.book(Party::new("Linus", 2), time("18:00"), u32::MAX),
+            Err(ReservationError::SlotTooLong(u32::MAX))
+        );
+        assert_eq!(time("18:00").plus(u32::MAX).minutes(), u32::MAX);
+    }
+
+    #[test]
+    fn no_shows_release_their_table_to_the_waitlist() {
+        let mut floor = Floor::with_tables(&[4]);
+        let mut waitlist = Waitlist::new();
+        let mut book = ReservationBook::new(&floor, 15, 30);
+        let id = book.book(Party::new("Ada", 4), time("18:00"), 90).unwrap();
+
+        waitlist.add(Party::new("walk-in", 3));
+        assert_eq!(book.held_tables(time("17:45")), vec![1]);
+        assert!(book.release_no_shows(time("18:10"), &mut floor, &mut waitlist).is_empty());
+        assert!(floor.table(1).unwrap().is_free());
+
+        assert_eq!(book.release_no_shows(time("18:16"), &mut floor, &mut waitlist), vec![id]);
+        assert_eq!(floor.table(1).unwrap().party, Some(Party::new("walk-in", 3)));
+
+        // the party shows up after all and has to wait like everyone else
+        assert_eq!(
+            book.check_in(id, time("18:20"), &mut floor, &mut waitlist),
+            Ok(CheckIn::Waitlisted)
+        );
+        assert_eq!(waitlist.len(), 1);
+        assert_eq!(
+            book.check_in(id, time("18:25"), &mut floor, &mut waitlist),
+            Ok(CheckIn::Waitlisted)
+        );
+        assert_eq!(waitlist.len(), 1);
+        assert_eq!(book.get(id).unwrap().status, Status::Waitlisted);
+    }
+
+    #[test]
+    fn the_sheet_quotes_names_with_commas() {
+        let floor = Floor::with_tables(&[2]);
+        let mut book = ReservationBook::new(&floor, 15, 30);
+        book.book(Party::new("Lovelace, \"Ada\"", 2), time("12:00"), 60).unwrap();
+        assert_eq!(
+            book.to_csv(),
+            "id,name,size,table,start,end,status,arrived\n\
+             1,\"Lovelace, \"\"Ada\"\"\",2,1,12:00,13:00,booked,\n"
+        );
+    }
+}
@@ -1,8 +1,89 @@
 // front of house is (2) where  servers take orders
 // orders are served and payment are taken
 // hosting and serving are siblings to each other
-fn _take_order() {}
+use crate::menu::MenuItem;
+
+// billing nests inside serving the same way serving nests inside front_of_house
+// its file lives in the directory named after the parent module
+pub mod billing;
+
+use billing::{Bill, Money, Payment, PaymentError, PaymentMethod, Receipt};
+
+/// one line of an order, the seat number tells who at the table ordered it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderLine {
+    pub seat: u32,
+    pub item: MenuItem,
+    pub quantity: u32,
+}
+
+/// everything that was ordered at one table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Order {
+    pub table: u32,
+    pub lines: Vec<OrderLine>,
+}
+
+impl Order {
+    pub fn new(table: u32) -> Order {
+        Order {
+            table,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, seat: u32, item: MenuItem, quantity: u32) {
+        self.lines.push(OrderLine {
+            seat,
+            item,
+            quantity,
+        });
+    }
+}
+
+pub fn take_order(table: u32) -> Order {
+    Order::new(table)
+}
 
 fn _serve_order() {}
 
-fn _take_payment() {}
+/// settles a bill with one or more payments
+/// card and gift card payments are charged exactly so together they can't exceed the total
+/// cash may be overpaid and the difference is handed back as change
+pub fn take_payment(bill: &Bill, payments: Vec<Payment>) -> Result<Receipt, PaymentError> {
+    if payments.is_empty() {
+        return Err(PaymentError::NothingTendered);
+    }
+    if let Some(payment) = payments.iter().find(|p| p.amount <= Money::ZERO) {
+        return Err(PaymentError::InvalidAmount(payment.clone()));
+    }
+
+    let tendered = Money::checked_sum(payments.iter().map(|p| p.amount)).ok_or(PaymentError::TooLarge)?;
+    // the non-cash payments are part of what was tendered so they add up too
+    let charged: Money = payments
+        .iter()
+        .filter(|p| p.method != PaymentMethod::Cash)
+        .map(|p| p.amount)
+        .sum();
+
+    if charged > bill.total() {
+        return Err(PaymentError::Overcharged {
+            excess: charged - bill.total(),
+        });
+    }
+    if tendered < bill.total() {
+        return Err(PaymentError::Short {
+            remaining: bill.total() - tendered,
+        });
+    }
+
+    // because non-cash payments never exceed the total
+    // the change is always covered by the cash that was handed over
+    Ok(Receipt {
+        table: bill.table(),
+        total: bill.total(),
+        tendered,
+        change: tendered - bill.total(),
+        payments,
+    })
+}
@@ -0,0 +1,692 @@
+// billing turns an order into a bill and splits the bill between guests
+// all amounts are whole cents stored in integers
+// floating point numbers can't represent 0.10 exactly
+// and the end-of-day totals would drift apart from what was actually paid
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+use super::Order;
+use crate::menu::{Menu, MenuItem};
+
+/// the most shares a bill is split into evenly, more than any table seats
+pub const MAX_SHARES: usize = 100;
+
+/// an amount of money in cents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub fn from_cents(cents: i64) -> Money {
+        Money(cents)
+    }
+
+    pub fn cents(self) -> i64 {
+        self.0
+    }
+
+    // amounts that come from guests or the menu go through the checked operations
+    // so an absurd amount is an error instead of a panic or a wrapped total
+
+    /// None if the sum doesn't fit in the cents
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    /// None if the difference doesn't fit in the cents
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.0.checked_sub(other.0).map(Money)
+    }
+
+    /// the amount times a quantity, None if it doesn't fit in the cents
+    pub fn checked_mul(self, quantity: u32) -> Option<Money> {
+        self.0.checked_mul(i64::from(quantity)).map(Money)
+    }
+
+    /// the sum of all amounts, None if it doesn't fit in the cents
+    pub fn checked_sum<I: IntoIterator<Item = Money>>(amounts: I) -> Option<Money> {
+        amounts
+            .into_iter()
+            .try_fold(Money::ZERO, |total, amount| total.checked_add(amount))
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, cents / 100, cents % 100)
+    }
+}
+
+/// parses amounts like "12", "12.5" or "12.50" without going through a float
+impl FromStr for Money {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Money, String> {
+        let invalid = || format!("invalid amount `{}`", s);
+        let (negative, digits) = match s.trim().strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.trim()),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty() || fraction.len() > 2 {
+            return Err(invalid());
+        }
+        if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let whole: i64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+        let fraction: i64 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+        let cents = whole
+            .checked_mul(100)
+            .and_then(|c| c.checked_add(fraction))
+            .ok_or_else(invalid)?;
+        Ok(Money(if negative { -cents } else { cents }))
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |total, amount| total + amount)
+    }
+}
+
+/// how a fraction of a cent is rounded to a whole cent
+/// every computation in this module rounds exactly once
+/// so the same bill always produces the same cents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    // halves go to the nearest even cent (banker's rounding)
+    // over many bills the rounding errors cancel each other out
+    #[default]
+    HalfEven,
+    // halves go away from zero, as taught in school
+    HalfUp,
+}
+
+impl Rounding {
+    // divides and rounds the quotient to a whole number
+    fn divide(self, numerator: i128, denominator: i128) -> i128 {
+        let quotient = numerator.div_euclid(denominator);
+        let remainder = numerator.rem_euclid(denominator);
+        // comparing twice the remainder with the denominator
+        // tells whether the fraction is below, at or above one half
+        match (2 * remainder).cmp(&denominator) {
+            std::cmp::Ordering::Less => quotient,
+            std::cmp::Ordering::Greater => quotient + 1,
+            std::cmp::Ordering::Equal => match self {
+                Rounding::HalfEven if quotient % 2 == 0 => quotient,
+                Rounding::HalfEven => quotient + 1,
+                Rounding::HalfUp if numerator < 0 => quotient,
+                Rounding::HalfUp => quotient + 1,
+            },
+        }
+    }
+}
+
+/// a percentage like a tax rate, stored in millionths so 8.875% is exact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Rate {
+    millionths: u32,
+}
+
+impl Rate {
+    pub const ZERO: Rate = Rate { millionths: 0 };
+
+    /// None if the rate is too large to store
+    pub fn percent(percent: u32) -> Option<Rate> {
+        percent.checked_mul(10_000).map(|millionths| Rate { millionths })
+    }
+
+    /// one basis point is a hundredth of a percent, None if the rate is too large to store
+    pub fn basis_points(basis_points: u32) -> Option<Rate> {
+        basis_points.checked_mul(100).map(|millionths| Rate { millionths })
+    }
+
+    /// the rate applied to an amount, rounded to a whole cent
+    /// None if the result doesn't fit in the cents
+    pub fn of(self, amount: Money, rounding: Rounding) -> Option<Money> {
+        let cents = rounding.divide(i128::from(amount.0) * i128::from(self.millionths), 1_000_000);
+        i64::try_from(cents).ok().map(Money)
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fraction = format!("{:04}", self.millionths % 10_000);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{}%", self.millionths / 10_000)
+        } else {
+            write!(f, "{}.{}%", self.millionths / 10_000, fraction)
+        }
+    }
+}
+
+/// parses rates like "8", "8.875" or "8.875%"
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Rate, String> {
+        let invalid = || format!("invalid rate `{}`", s);
+        let digits = s.trim().trim_end_matches('%');
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() || fraction.len() > 4 {
+            return Err(invalid());
+        }
+        if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let whole: u32 = whole.parse().map_err(|_| invalid())?;
+        let fraction: u32 = format!("{:0<4}", fraction).parse().map_err(|_| invalid())?;
+        whole
+            .checked_mul(10_000)
+            .and_then(|m| m.checked_add(fraction))
+            .map(|millionths| Rate { millionths })
+            .ok_or_else(invalid)
+    }
+}
+
+/// the rates the restaurant charges on top of the menu prices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BillingPolicy {
+    pub tax: Rate,
+    pub service_charge: Rate,
+    // in some places the service charge is taxed like the food
+    pub tax_service_charge: bool,
+    pub rounding: Rounding,
+}
+
+/// a tip is either a fixed amount or a percentage of the subtotal
+/// tips are never taxed and never earn a service charge, and never negative
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tip {
+    Amount(Money),
+    Percent(Rate),
+}
+
+/// one priced line of a bill
+#[derive(Debug, Clone, PartialEq)]
+pub struct BillLine {
+    pub seat: u32,
+    pub item: MenuItem,
+    pub quantity: u32,
+    pub unit_price: Money,
+    pub amount: Money,
+}
+
+/// a priced order
+/// the total is always subtotal + service charge + tax + tip
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bill {
+    table: u32,
+    lines: Vec<BillLine>,
+    policy: BillingPolicy,
+    subtotal: Money,
+    service_charge: Money,
+    tax: Money,
+    tip: Money,
+    total: Money,
+}
+
+impl Bill {
+    /// prices every line of the order with the menu and applies the policy
+    pub fn from_order(order: &Order, menu: &Menu, policy: BillingPolicy) -> Result<Bill, BillingError> {
+        let mut lines = Vec::new();
+        for line in &order.lines {
+            let unit_price = menu
+                .price_of(&line.item)
+                .ok_or_else(|| BillingError::NotOnMenu(line.item.clone()))?;
+            lines.push(BillLine {
+                seat: line.seat,
+                item: line.item.clone(),
+                quantity: line.quantity,
+                unit_price,
+                amount: unit_price.checked_mul(line.quantity).ok_or(BillingError::TooLarge)?,
+            });
+        }
+
+        let subtotal = Money::checked_sum(lines.iter().map(|line| line.amount)).ok_or(BillingError::TooLarge)?;
+        let service_charge = policy
+            .service_charge
+            .of(subtotal, policy.rounding)
+            .ok_or(BillingError::TooLarge)?;
+        // tax is computed once on the whole bill and not per line
+        // otherwise rounding every line would add up to a different amount
+        let taxable = if policy.tax_service_charge {
+            subtotal.checked_add(service_charge).ok_or(BillingError::TooLarge)?
+        } else {
+            subtotal
+        };
+        let tax = policy.tax.of(taxable, policy.rounding).ok_or(BillingError::TooLarge)?;
+
+        let mut bill = Bill {
+            table: order.table,
+            lines,
+            policy,
+            subtotal,
+            service_charge,
+            tax,
+            tip: Money::ZERO,
+            total: Money::ZERO,
+        };
+        bill.total = bill.compute_total()?;
+        Ok(bill)
+    }
+
+    /// adds a tip to the bill, replacing any earlier tip
+    pub fn with_tip(mut self, tip: Tip) -> Result<Bill, BillingError> {
+        self.tip = match tip {
+            Tip::Amount(amount) if amount < Money::ZERO => return Err(BillingError::NegativeTip(amount)),
+            Tip::Amount(amount) => amount,
+            Tip::Percent(rate) => rate
+                .of(self.subtotal, self.policy.rounding)
+                .ok_or(BillingError::TooLarge)?,
+        };
+        self.total = self.compute_total()?;
+        Ok(self)
+    }
+
+    fn compute_total(&self) -> Result<Money, BillingError> {
+        Money::checked_sum([self.subtotal, self.service_charge, self.tax, self.tip]).ok_or(BillingError::TooLarge)
+    }
+
+    pub fn table(&self) -> u32 {
+        self.table
+    }
+
+    pub fn lines(&self) -> &[BillLine] {
+        &self.lines
+    }
+
+    pub fn subtotal(&self) -> Money {
+        self.subtotal
+    }
+
+    pub fn service_charge(&self) -> Money {
+        self.service_charge
+    }
+
+    pub fn tax(&self) -> Money {
+        self.tax
+    }
+
+    pub fn tip(&self) -> Money {
+        self.tip
+    }
+
+    pub fn total(&self) -> Money {
+        self.total
+    }
+
+    /// splits the total into equal shares, at most MAX_SHARES of them
+    /// leftover cents go one each to the first shares
+    pub fn split_evenly(&self, ways: usize) -> Result<Vec<Money>, BillingError> {
+        if ways == 0 {
+            return Err(BillingError::NoGuests);
+        }
+        if ways > MAX_SHARES {
+            return Err(BillingError::TooManyShares(ways));
+        }
+        let total = self.total().cents();
+        let ways = ways as i64;
+        let (share, leftover) = (total.div_euclid(ways), total.rem_euclid(ways));
+        Ok((0..ways)
+            .map(|index| Money(share + i64::from(index < leftover)))
+            .collect())
+    }
+
+    /// every seat pays for what was ordered from it
+    /// plus its share of the service charge, tax and tip
+    /// the shares are returned sorted by seat number
+    pub fn split_by_seat(&self) -> Vec<(u32, Money)> {
+        let mut seats: BTreeMap<u32, Money> = BTreeMap::new();
+        for line in &self.lines {
+            *seats.entry(line.seat).or_default() += line.amount;
+        }
+        let weights: Vec<i64> = seats.values().map(|amount| amount.cents()).collect();
+        let shares = allocate(self.total(), &weights);
+        seats.into_keys().zip(shares).collect()
+    }
+
+    /// guests pick the lines they had by index into lines()
+    /// a line picked by several guests is shared evenly between them
+    /// every line has to be picked by at least one guest
+    pub fn split_by_item(&self, guests: &[Vec<usize>]) -> Result<Vec<Money>, BillingError> {
+        if guests.is_empty() {
+            return Err(BillingError::NoGuests);
+        }
+        let mut sharers = vec![0_i64; self.lines.len()];
+        for &index in guests.iter().flatten() {
+            match sharers.get_mut(index) {
+                Some(count) => *count += 1,
+                None => return Err(BillingError::NoSuchLine(index)),
+            }
+        }
+        if let Some(index) = sharers.iter().position(|&count| count == 0) {
+            return Err(BillingError::UnclaimedLine(index));
+        }
+
+        // first every shared line is divided between the guests who had it
+        // then the whole total is allocated in proportion to those portions
+        let mut portions = vec![0_i64; guests.len()];
+        for (index, line) in self.lines.iter().enumerate() {
+            let mut line_shares = allocate(line.amount, &vec![1; sharers[index] as usize]).into_iter();
+            for (guest, picked) in guests.iter().enumerate() {
+                for _ in picked.iter().filter(|&&picked| picked == index) {
+                    portions[guest] += line_shares.next().unwrap_or_default().cents();
+                }
+            }
+        }
+        Ok(allocate(self.total(), &portions))
+    }
+}
+
+/// divides an amount in proportion to the weights using the largest remainder method
+/// every share is rounded down first, then the cents left over
+/// go one each to the shares with the largest fractions, ties to the lower index
+/// the shares always add up to exactly the amount
+/// if all weights are zero the amount is divided evenly
+pub fn allocate(amount: Money, weights: &[i64]) -> Vec<Money> {
+    if weights.is_empty() {
+        return Vec::new();
+    }
+    let even = vec![1_i128; weights.len()];
+    let weights: Vec<i128> = if weights.iter().all(|&w| w <= 0) {
+        even
+    } else {
+        weights.iter().map(|&w| i128::from(w.max(0))).collect()
+    };
+    let weight_sum: i128 = weights.iter().sum();
+    let total = i128::from(amount.cents());
+
+    let mut shares = Vec::with_capacity(weights.len());
+    let mut fractions = Vec::with_capacity(weights.len());
+    for (index, weight) in weights.iter().enumerate() {
+        let product = total * weight;
+        shares.push(product.div_euclid(weight_sum));
+        fractions.push((product.rem_euclid(weight_sum), index));
+    }
+
+    let mut leftover = total - shares.iter().sum::<i128>();
+    fractions.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for (_, index) in fractions {
+        if leftover == 0 {
+            break;
+        }
+        shares[index] += 1;
+        leftover -= 1;
+    }
+    shares.into_iter().map(|cents| Money(cents as i64)).collect()
+}
+
+/// why an order couldn't be billed or split
+#[derive(Debug, Clone, PartialEq)]
+pub enum BillingError {
+    NotOnMenu(MenuItem),
+    NoGuests,
+    NoSuchLine(usize),
+    UnclaimedLine(usize),
+    NegativeTip(Money),
+    // an amount on the bill doesn't fit in the cents
+    TooLarge,
+    // more shares than MAX_SHARES
+    TooManyShares(usize),
+}
+
+impl fmt::Display for BillingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BillingError::NotOnMenu(item) => write!(f, "{} is not on the menu", item),
+            BillingError::NoGuests => write!(f, "a bill can't be split between nobody"),
+            BillingError::NoSuchLine(index) => write!(f, "the bill has no line {}", index),
+            BillingError::UnclaimedLine(index) => write!(f, "nobody claimed line {} of the bill", index),
+            BillingError::NegativeTip(amount) => write!(f, "a tip of {} is less than nothing", amount),
+            BillingError::TooLarge => write!(f, "the bill is too large to add up"),
+            BillingError::TooManyShares(ways) => {
+                write!(f, "a bill can't be split {} ways, at most {}", ways, MAX_SHARES)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BillingError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentMethod {
+    Cash,
+    Card,
+    GiftCard,
+}
+
+impl FromStr for PaymentMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<PaymentMethod, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "cash" => Ok(PaymentMethod::Cash),
+            "card" => Ok(PaymentMethod::Card),
+            "gift" | "giftcard" | "gift-card" => Ok(PaymentMethod::GiftCard),
+            _ => Err(format!("unknown payment method `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payment {
+    pub method: PaymentMethod,
+    pub amount: Money,
+}
+
+/// what a guest gets back after paying
+#[derive(Debug, Clone, PartialEq)]
+pub struct Receipt {
+    pub table: u32,
+    pub total: Money,
+    pub tendered: Money,
+    pub change: Money,
+    pub payments: Vec<Payment>,
+}
+
+/// why a set of payments doesn't settle a bill
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaymentError {
+    NothingTendered,
+    InvalidAmount(Payment),
+    Short { remaining: Money },
+    // the payments don't fit in the cents when added up
+    TooLarge,
+    // cards can't be charged more than the bill, only cash gets change
+    Overcharged { excess: Money },
+}
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PaymentError::NothingTendered => write!(f, "no payment was tendered"),
+            PaymentError::InvalidAmount(payment) => {
+                write!(f, "a {:?} payment of {} is not a valid amount", payment.method, payment.amount)
+            }
+            PaymentError::Short { remaining } => write!(f, "{} is still owed", remaining),
+            PaymentError::TooLarge => write!(f, "the payments are too large to add up"),
+            PaymentError::Overcharged { excess } => {
+                write!(f, "cards would be charged {} more than the bill", excess)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PaymentError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::Appetizer;
+    use crate::serving::{take_payment, Order};
+
+    fn policy() -> BillingPolicy {
+        BillingPolicy {
+            tax: "8.875".parse().unwrap(),
+            service_charge: Rate::percent(18).unwrap(),
+            tax_service_charge: false,
+            rounding: Rounding::HalfEven,
+        }
+    }
+
+    fn order() -> Order {
+        let mut order = Order::new(4);
+        order.add(1, MenuItem::breakfast("Rye"), 1);
+        order.add(2, MenuItem::Appetizer(Appetizer::Soup), 2);
+        order.add(3, MenuItem::Appetizer(Appetizer::Salad), 1);
+        order
+    }
+
+    #[test]
+    fn money_parses_and_prints_exactly() {
+        assert_eq!("12.5".parse::<Money>().unwrap(), Money::from_cents(1250));
+        assert_eq!("-0.07".parse::<Money>().unwrap(), Money::from_cents(-7));
+        assert!("1.234".parse::<Money>().is_err());
+        assert_eq!(Money::from_cents(-105).to_string(), "-1.05");
+    }
+
+    #[test]
+    fn rounding_is_deterministic_at_half_a_cent() {
+        // 10% of 25 cents is exactly 2.5 cents
+        let amount = Money::from_cents(25);
+        let ten = Rate::percent(10).unwrap();
+        assert_eq!(ten.of(amount, Rounding::HalfEven), Some(Money::from_cents(2)));
+        assert_eq!(ten.of(amount, Rounding::HalfUp), Some(Money::from_cents(3)));
+        let amount = Money::from_cents(35);
+        assert_eq!(ten.of(amount, Rounding::HalfEven), Some(Money::from_cents(4)));
+    }
+
+    #[test]
+    fn bill_adds_service_tax_and_tip() {
+        let bill = Bill::from_order(&order(), &Menu::default(), policy())
+            .unwrap()
+            .with_tip(Tip::Percent(Rate::percent(10).unwrap()))
+            .unwrap();
+        // 12.50 + 2 * 6.75 + 7.25
+        assert_eq!(bill.subtotal(), Money::from_cents(3325));
+        // 18% of 33.25 = 5.985
+        assert_eq!(bill.service_charge(), Money::from_cents(598));
+        // 8.875% of 33.25 = 2.9509375
+        assert_eq!(bill.tax(), Money::from_cents(295));
+        // 10% of 33.25 = 3.325
+        assert_eq!(bill.tip(), Money::from_cents(332));
+        assert_eq!(bill.total(), Money::from_cents(4550));
+    }
+
+    #[test]
+    fn absurd_amounts_are_errors() {
+        assert_eq!(Rate::percent(u32::MAX), None);
+        assert_eq!(Rate::basis_points(u32::MAX), None);
+        assert_eq!(Rate::basis_points(875), Some("8.75".parse().unwrap()));
+        let bill = Bill::from_order(&order(), &Menu::default(), policy()).unwrap();
+        let tip = Tip::Amount(Money::from_cents(-1));
+        assert_eq!(bill.clone().with_tip(tip), Err(BillingError::NegativeTip(Money::from_cents(-1))));
+        let tip = Tip::Amount(Money::from_cents(i64::MAX));
+        assert_eq!(bill.clone().with_tip(tip), Err(BillingError::TooLarge));
+
+        let mut menu = Menu::default();
+        menu.set_price("soup", Money::from_cents(i64::MAX / 2));
+        assert_eq!(Bill::from_order(&order(), &menu, policy()), Err(BillingError::TooLarge));
+        let huge = Payment {
+            method: PaymentMethod::Cash,
+            amount: Money::from_cents(i64::MAX),
+        };
+        assert_eq!(take_payment(&bill, vec![huge.clone(), huge]), Err(PaymentError::TooLarge));
+    }
+
+    #[test]
+    fn splits_always_add_up_to_the_total() {
+        let bill = Bill::from_order(&order(), &Menu::default(), policy()).unwrap();
+        let total = bill.total();
+
+        let even = bill.split_evenly(3).unwrap();
+        assert_eq!(even.iter().copied().sum::<Money>(), total);
+        assert!(even[0] >= even[2] && even[0] - even[2] <= Money::from_cents(1));
+        assert_eq!(
+            bill.split_evenly(MAX_SHARES).unwrap().into_iter().sum::<Money>(),
+            total
+        );
+        assert_eq!(bill.split_evenly(4_000_000_000), Err(BillingError::TooManyShares(4_000_000_000)));
+
+        let by_seat = bill.split_by_seat();
+        assert_eq!(by_seat.iter().map(|s| s.0).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(by_seat.iter().map(|s| s.1).sum::<Money>(), total);
+
+        // the salad is shared by the first two guests
+        let by_item = bill.split_by_item(&[vec![0, 2], vec![1, 2]]).unwrap();
+        assert_eq!(by_item.iter().copied().sum::<Money>(), total);
+        assert_eq!(bill.split_by_item(&[vec![0, 1]]), Err(BillingError::UnclaimedLine(2)));
+    }
+
+    #[test]
+    fn payments_reconcile_to_the_total() {
+        let bill = Bill::from_order(&order(), &Menu::default(), policy()).unwrap();
+        let total = bill.total();
+
+        let card = Payment {
+            method: PaymentMethod::Card,
+            amount: total - Money::from_cents(1000),
+        };
+        let cash = Payment {
+            method: PaymentMethod::Cash,
+            amount: Money::from_cents(2000),
+        };
+        let receipt = take_payment(&bill, vec![card.clone(), cash]).unwrap();
+        assert_eq!(receipt.change, Money::from_cents(1000));
+        assert_eq!(receipt.tendered - receipt.change, total);
+
+        assert_eq!(
+            take_payment(&bill, vec![card]),
+            Err(PaymentError::Short {
+                remaining: Money::from_cents(1000)
+            })
+        );
+        let overcharged = Payment {
+            method: PaymentMethod::Card,
+            amount: total + Money::from_cents(1),
+        };
+        assert_eq!(
+            take_payment(&bill, vec![overcharged]),
+            Err(PaymentError::Overcharged {
+                excess: Money::from_cents(1)
+            })
+        );
+    }
+}
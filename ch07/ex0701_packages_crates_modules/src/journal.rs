@@ -0,0 +1,564 @@
+// the journal keeps the state of a service on disk
+// every change is appended to the journal file before it counts
+// so after a crash replaying the journal recovers the service
+//
+// every record is one line: a CRC-32 checksum of the payload, a space and the payload
+// a crash in the middle of a write leaves a line without a newline or with a wrong checksum
+// such a torn record can only be at the end of the file and is cut off when the journal is opened
+// a damaged record anywhere else is an error and the file is left alone
+// cutting it off would throw away every record after it
+//
+// compaction writes the whole state into a snapshot file and starts an empty journal
+// both files begin with a generation number
+// a journal older than the snapshot was already compacted and is ignored
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::hosting::Party;
+use crate::menu::{Appetizer, MenuItem};
+use crate::restaurant::{Change, Restaurant, StateError};
+use crate::serving::billing::Tip;
+
+/// what was found while opening a journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Recovery {
+    pub generation: u64,
+    pub from_snapshot: usize,
+    pub replayed: usize,
+    // bytes of a torn record at the end of the journal that were cut off
+    pub torn_bytes: u64,
+}
+
+#[derive(Debug)]
+pub enum JournalError {
+    Io(io::Error),
+    // the snapshot is written completely before it replaces the old one
+    // so a damaged snapshot means something else went wrong
+    CorruptSnapshot { line: usize },
+    // the journal was started after a snapshot that isn't there anymore
+    MissingSnapshot { generation: u64 },
+    // a damaged or unreadable record before the last line of the journal
+    CorruptJournal { line: usize },
+    Replay { line: usize, error: StateError },
+    Rejected(StateError),
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JournalError::Io(error) => write!(f, "{}", error),
+            JournalError::CorruptSnapshot { line } => write!(f, "the snapshot is corrupt at line {}", line),
+            JournalError::MissingSnapshot { generation } => {
+                write!(f, "the journal continues snapshot {} which is missing", generation)
+            }
+            JournalError::CorruptJournal { line } => write!(f, "the journal is corrupt at line {}", line),
+            JournalError::Replay { line, error } => write!(f, "line {} of the journal can't be replayed: {}", line, error),
+            JournalError::Rejected(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for JournalError {}
+
+impl From<io::Error> for JournalError {
+    fn from(error: io::Error) -> JournalError {
+        JournalError::Io(error)
+    }
+}
+
+/// an open journal, the only way its restaurant should change
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    generation: u64,
+    records: usize,
+}
+
+impl Journal {
+    /// opens the journal at path and recovers the state it describes
+    /// the snapshot lives next to the journal with the extension "snapshot"
+    /// missing files are created, so the first service starts empty
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<(Journal, Restaurant, Recovery), JournalError> {
+        let path = path.as_ref().to_path_buf();
+        let mut restaurant = Restaurant::new();
+        let mut recovery = Recovery::default();
+
+        let snapshot_path = path.with_extension("snapshot");
+        let mut generation = 0;
+        if snapshot_path.exists() {
+            let bytes = fs::read(&snapshot_path)?;
+            let (records, tail) = read_records(&bytes);
+            match tail {
+                Tail::Clean => {}
+                Tail::Torn(_) => return Err(JournalError::CorruptSnapshot { line: records.len() + 1 }),
+                Tail::Corrupt { line } => return Err(JournalError::CorruptSnapshot { line }),
+            }
+            let mut records = records.into_iter();
+            generation = match records.next().and_then(|header| parse_header(&header, "snapshot")) {
+                Some(generation) => generation,
+                None => return Err(JournalError::CorruptSnapshot { line: 1 }),
+            };
+            for (index, record) in records.enumerate() {
+                let change = decode(&record).ok_or(JournalError::CorruptSnapshot { line: index + 2 })?;
+                restaurant.apply(&change).map_err(|error| JournalError::Replay { line: index + 2, error })?;
+                recovery.from_snapshot += 1;
+            }
+        }
+
+        let bytes = if path.exists() { fs::read(&path)? } else { Vec::new() };
+        let (records, tail) = read_records(&bytes);
+        // only an empty journal or one torn while its header was written is started anew
+        // any other journal without a header is damaged and left alone like a damaged record
+        let journal_generation = match (records.first(), tail) {
+            (Some(header), _) => {
+                Some(parse_header(header, "journal").ok_or(JournalError::CorruptJournal { line: 1 })?)
+            }
+            (None, Tail::Corrupt { line }) => return Err(JournalError::CorruptJournal { line }),
+            (None, Tail::Clean | Tail::Torn(_)) => None,
+        };
+
+        let mut records_kept = 0;
+        if let Some(journal_generation) = journal_generation.filter(|&g| g > generation) {
+            return Err(JournalError::MissingSnapshot {
+                generation: journal_generation,
+            });
+        }
+        if journal_generation == Some(generation) {
+            if let Tail::Corrupt { line } = tail {
+                return Err(JournalError::CorruptJournal { line });
+            }
+            for (index, record) in records.iter().enumerate().skip(1) {
+                // a record with a valid checksum that doesn't decode wasn't torn by a crash
+                // so it is reported instead of being cut off with everything after it
+                let change = decode(record).ok_or(JournalError::CorruptJournal { line: index + 1 })?;
+                restaurant.apply(&change).map_err(|error| JournalError::Replay { line: index + 1, error })?;
+                recovery.replayed += 1;
+            }
+            records_kept = recovery.replayed + 1;
+        }
+
+        let file = if records_kept == 0 {
+            // no journal yet, or one that was already compacted into the snapshot
+            start_journal(&path, generation)?
+        } else {
+            let kept: usize = records.iter().take(records_kept).map(|record| encoded_len(record)).sum();
+            recovery.torn_bytes = bytes.len() as u64 - kept as u64;
+            let file = OpenOptions::new().append(true).open(&path)?;
+            if recovery.torn_bytes > 0 {
+                file.set_len(kept as u64)?;
+                file.sync_all()?;
+            }
+            file
+        };
+        recovery.generation = generation;
+
+        let journal = Journal {
+            path,
+            file,
+            generation,
+            records: recovery.replayed,
+        };
+        Ok((journal, restaurant, recovery))
+    }
+
+    /// applies a change and makes it durable
+    /// a change that doesn't fit the state is rejected and never written
+    pub fn record(&mut self, restaurant: &mut Restaurant, change: Change) -> Result<(), JournalError> {
+        let mut next = restaurant.clone();
+        next.apply(&change).map_err(JournalError::Rejected)?;
+        self.file.write_all(&frame(&encode(&change)))?;
+        self.file.sync_data()?;
+        self.records += 1;
+        *restaurant = next;
+        Ok(())
+    }
+
+    /// the number of changes in the journal since the last compaction
+    pub fn len(&self) -> usize {
+        self.records
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records == 0
+    }
+
+    /// writes the whole state into a new snapshot and empties the journal
+    /// the snapshot is written to a temporary file first and then renamed
+    /// so a crash leaves either the old or the new snapshot, never half of one
+    pub fn compact(&mut self, restaurant: &Restaurant) -> Result<(), JournalError> {
+        let generation = self.generation + 1;
+        let snapshot_path = self.path.with_extension("snapshot");
+        let temporary_path = self.path.with_extension("snapshot.tmp");
+
+        let mut contents = frame(&format!("snapshot {}", generation));
+        for change in restaurant.changes() {
+            contents.extend(frame(&encode(&change)));
+        }
+        let mut temporary = File::create(&temporary_path)?;
+        temporary.write_all(&contents)?;
+        temporary.sync_all()?;
+        fs::rename(&temporary_path, &snapshot_path)?;
+
+        // if we crash right here the old journal has an older generation than the snapshot
+        // and is ignored the next time the journal is opened
+        self.file = start_journal(&self.path, generation)?;
+        self.generation = generation;
+        self.records = 0;
+        Ok(())
+    }
+}
+
+// creates an empty journal that only holds its header
+fn start_journal(path: &Path, generation: u64) -> io::Result<File> {
+    let mut file = File::create(path)?;
+    file.write_all(&frame(&format!("journal {}", generation)))?;
+    file.sync_all()?;
+    Ok(file)
+}
+
+fn parse_header(record: &str, kind: &str) -> Option<u64> {
+    let (name, generation) = record.split_once(' ')?;
+    if name != kind {
+        return None;
+    }
+    generation.parse().ok()
+}
+
+// how a file of records ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tail {
+    Clean,
+    // the bytes of a torn last line
+    Torn(u64),
+    // a damaged line with more lines after it
+    Corrupt { line: usize },
+}
+
+// the payloads of the intact records at the start of the file and how the file ends
+fn read_records(bytes: &[u8]) -> (Vec<String>, Tail) {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let torn = Tail::Torn((bytes.len() - offset) as u64);
+        let Some(end) = bytes[offset..].iter().position(|&b| b == b'\n') else {
+            return (records, torn);
+        };
+        let Some(payload) = unframe(&bytes[offset..offset + end]) else {
+            if offset + end + 1 == bytes.len() {
+                return (records, torn);
+            }
+            let line = records.len() + 1;
+            return (records, Tail::Corrupt { line });
+        };
+        records.push(payload);
+        offset += end + 1;
+    }
+    (records, Tail::Clean)
+}
+
+fn frame(payload: &str) -> Vec<u8> {
+    format!("{:08x} {}\n", crc32(payload.as_bytes()), payload).into_bytes()
+}
+
+fn unframe(line: &[u8]) -> Option<String> {
+    let line = std::str::from_utf8(line).ok()?;
+    let (checksum, payload) = line.split_once(' ')?;
+    let checksum = u32::from_str_radix(checksum, 16).ok()?;
+    (crc32(payload.as_bytes()) == checksum).then(|| String::from(payload))
+}
+
+fn encoded_len(payload: &str) -> usize {
+    // eight hex digits, a space, the payload and the newline
+    8 + 1 + payload.len() + 1
+}
+
+/// the CRC-32 checksum used by zip and PNG files, computed bit by bit
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+// payloads are words separated by single spaces
+// names can contain anything so spaces, line breaks and % are escaped like in URLs
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '%' | ' ' | '\n' | '\r' => escaped.push_str(&format!("%{:02X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut bytes = Vec::new();
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn encode_item(item: &MenuItem) -> String {
+    match item {
+        MenuItem::Breakfast(toast) => format!("breakfast:{}", escape(toast)),
+        MenuItem::Appetizer(Appetizer::Soup) => String::from("soup"),
+        MenuItem::Appetizer(Appetizer::Salad) => String::from("salad"),
+    }
+}
+
+fn decode_item(word: &str) -> Option<MenuItem> {
+    match word.split_once(':') {
+        Some(("breakfast", toast)) => Some(MenuItem::Breakfast(unescape(toast)?)),
+        _ if word == "soup" => Some(MenuItem::Appetizer(Appetizer::Soup)),
+        _ if word == "salad" => Some(MenuItem::Appetizer(Appetizer::Salad)),
+        _ => None,
+    }
+}
+
+fn encode(change: &Change) -> String {
+    match change {
+        Change::AddTable { number, seats } => format!("table {} {}", number, seats),
+        Change::Waitlist(party) => format!("wait {} {}", party.size, escape(&party.name)),
+        Change::LeaveWaitlist { name } => format!("leave {}", escape(name)),
+        Change::Seat { table, party } => format!("seat {} {} {}", table, party.size, escape(&party.name)),
+        Change::Order {
+            table,
+            seat,
+            item,
+            quantity,
+        } => format!("order {} {} {} {}", table, seat, quantity, encode_item(item)),
+        Change::Tip {
+            table,
+            tip: Tip::Amount(amount),
+        } => format!("tip {} amount {}", table, amount),
+        Change::Tip {
+            table,
+            tip: Tip::Percent(rate),
+        } => format!("tip {} percent {}", table, rate),
+        Change::Clear { table } => format!("clear {}", table),
+    }
+}
+
+fn decode(payload: &str) -> Option<Change> {
+    let words: Vec<&str> = payload.split(' ').collect();
+    let number = |index: usize| words.get(index)?.parse::<u32>().ok();
+    let text = |index: usize| unescape(words.get(index)?);
+    let change = match words[0] {
+        "table" if words.len() == 3 => Change::AddTable {
+            number: number(1)?,
+            seats: number(2)?,
+        },
+        "wait" if words.len() == 3 => Change::Waitlist(Party {
+            size: number(1)?,
+            name: text(2)?,
+        }),
+        "leave" if words.len() == 2 => Change::LeaveWaitlist { name: text(1)? },
+        "seat" if words.len() == 4 => Change::Seat {
+            table: number(1)?,
+            party: Party {
+                size: number(2)?,
+                name: text(3)?,
+            },
+        },
+        "order" if words.len() == 5 => Change::Order {
+            table: number(1)?,
+            seat: number(2)?,
+            quantity: number(3)?,
+            item: decode_item(words[4])?,
+        },
+        "tip" if words.len() == 4 => Change::Tip {
+            table: number(1)?,
+            // amounts and rates print exactly the way they are parsed
+            tip: match words[2] {
+                "amount" => Tip::Amount(words[3].parse().ok()?),
+                "percent" => Tip::Percent(words[3].parse().ok()?),
+                _ => return None,
+            },
+        },
+        "clear" if words.len() == 2 => Change::Clear { table: number(1)? },
+        _ => return None,
+    };
+    Some(change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    // every test works in a directory of its own
+    fn journal_path(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("restaurant-journal-{}-{}", process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("service.journal")
+    }
+
+    fn open_service(journal: &mut Journal, restaurant: &mut Restaurant) {
+        journal
+            .record(restaurant, Change::AddTable { number: 1, seats: 4 })
+            .unwrap();
+        journal
+            .record(restaurant, Change::Waitlist(Party::new("Ada Lovelace", 3)))
+            .unwrap();
+        journal
+            .record(
+                restaurant,
+                Change::Seat {
+                    table: 1,
+                    party: Party::new("Ada Lovelace", 3),
+                },
+            )
+            .unwrap();
+        journal
+            .record(
+                restaurant,
+                Change::Order {
+                    table: 1,
+                    seat: 2,
+                    item: MenuItem::breakfast("Whole wheat"),
+                    quantity: 1,
+                },
+            )
+            .unwrap();
+        journal
+            .record(restaurant, Change::Waitlist(Party::new("50% off", 2)))
+            .unwrap();
+    }
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn replaying_the_journal_recovers_the_service() {
+        let path = journal_path("replay");
+        let (mut journal, mut restaurant, _) = Journal::open(&path).unwrap();
+        open_service(&mut journal, &mut restaurant);
+        drop(journal);
+
+        let (_, recovered, recovery) = Journal::open(&path).unwrap();
+        assert_eq!(recovered, restaurant);
+        assert_eq!(recovery.replayed, 5);
+        assert_eq!(recovery.torn_bytes, 0);
+    }
+
+    #[test]
+    fn a_torn_write_at_the_tail_is_skipped() {
+        let path = journal_path("torn");
+        let (mut journal, mut restaurant, _) = Journal::open(&path).unwrap();
+        open_service(&mut journal, &mut restaurant);
+        drop(journal);
+
+        // half a record without its newline, as if the power went out
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"1234abcd clear").unwrap();
+        drop(file);
+
+        let (mut journal, mut recovered, recovery) = Journal::open(&path).unwrap();
+        assert_eq!(recovered, restaurant);
+        assert_eq!(recovery.torn_bytes, 14);
+
+        // the journal keeps working after the torn record was cut off
+        journal.record(&mut recovered, Change::Clear { table: 1 }).unwrap();
+        let (_, reopened, recovery) = Journal::open(&path).unwrap();
+        assert_eq!(reopened, recovered);
+        assert_eq!(recovery.torn_bytes, 0);
+    }
+
+    #[test]
+    fn a_damaged_record_in_the_middle_is_an_error() {
+        let path = journal_path("damaged");
+        let (mut journal, mut restaurant, _) = Journal::open(&path).unwrap();
+        open_service(&mut journal, &mut restaurant);
+        drop(journal);
+        let intact = fs::read_to_string(&path).unwrap();
+        let mut lines: Vec<String> = intact.lines().map(String::from).collect();
+
+        // a record with a valid checksum that no version of the journal wrote
+        lines[2] = String::from_utf8(frame("dance 1 2")).unwrap().trim_end().to_string();
+        let damaged = lines.join("\n") + "\n";
+        fs::write(&path, &damaged).unwrap();
+        let error = Journal::open(&path).unwrap_err();
+        assert!(matches!(error, JournalError::CorruptJournal { line: 3 }));
+        assert_eq!(fs::read_to_string(&path).unwrap(), damaged);
+
+        // a flipped bit in the payload breaks the checksum
+        lines[2] = intact.lines().nth(2).unwrap().replacen("wait", "waiu", 1);
+        let damaged = lines.join("\n") + "\n";
+        fs::write(&path, &damaged).unwrap();
+        let error = Journal::open(&path).unwrap_err();
+        assert!(matches!(error, JournalError::CorruptJournal { line: 3 }));
+        assert_eq!(fs::read_to_string(&path).unwrap(), damaged);
+    }
+
+    #[test]
+    fn a_damaged_header_is_an_error() {
+        let path = journal_path("header");
+        let (mut journal, mut restaurant, _) = Journal::open(&path).unwrap();
+        open_service(&mut journal, &mut restaurant);
+        drop(journal);
+        let intact = fs::read_to_string(&path).unwrap();
+        let mut lines: Vec<String> = intact.lines().map(String::from).collect();
+
+        // a broken checksum and a valid one on something that isn't a header
+        for header in [lines[0].replacen("journal", "journam", 1), String::from_utf8(frame("diary 0")).unwrap()] {
+            lines[0] = header.trim_end().to_string();
+            let damaged = lines.join("\n") + "\n";
+            fs::write(&path, &damaged).unwrap();
+            let error = Journal::open(&path).unwrap_err();
+            assert!(matches!(error, JournalError::CorruptJournal { line: 1 }));
+            assert_eq!(fs::read(&path).unwrap(), damaged.as_bytes());
+        }
+    }
+
+    #[test]
+    fn compaction_keeps_the_state_and_empties_the_journal() {
+        let path = journal_path("compact");
+        let (mut journal, mut restaurant, _) = Journal::open(&path).unwrap();
+        open_service(&mut journal, &mut restaurant);
+        journal.compact(&restaurant).unwrap();
+        assert!(journal.is_empty());
+        journal
+            .record(&mut restaurant, Change::LeaveWaitlist { name: String::from("50% off") })
+            .unwrap();
+        drop(journal);
+
+        let (_, recovered, recovery) = Journal::open(&path).unwrap();
+        assert_eq!(recovered, restaurant);
+        assert_eq!(recovery.generation, 1);
+        // the table, the seated party, its order and the party still waiting
+        assert_eq!(recovery.from_snapshot, 4);
+        assert_eq!(recovery.replayed, 1);
+    }
+
+    #[test]
+    fn rejected_changes_are_never_written() {
+        let path = journal_path("rejected");
+        let (mut journal, mut restaurant, _) = Journal::open(&path).unwrap();
+        let result = journal.record(&mut restaurant, Change::Clear { table: 9 });
+        assert!(matches!(result, Err(JournalError::Rejected(StateError::NoSuchTable(9)))));
+        assert!(journal.is_empty());
+    }
+}
@@ -1,13 +1,27 @@
-/// grouping related code in modules
-/// example: a library crate that provides the functionality of a restaurant
-/// "front of house" part of a restaurant is where customers are
-/// "back of house" part is where the chefs and cooks work in the kitchen
-/// to structure our crate in this way we organize its functions into nested modules
+//! grouping related code in modules
+//! example: a library crate that provides the functionality of a restaurant
+//! "front of house" part of a restaurant is where customers are
+//! "back of house" part is where the chefs and cooks work in the kitchen
+//! to structure our crate in this way we organize its functions into nested modules
 
 /// front of house module section
 /// the entire module tree is rooted under the implicit module named crate
 mod front_of_house;
 
+/// the menu is shared by both halves of the restaurant
+/// guests order from it and the kitchen cooks from it
+pub mod menu;
+
+/// a simulated service drives both halves of the restaurant on a virtual clock
+pub mod simulation;
+
+/// the state of a running service and the journal that keeps it on disk
+pub mod restaurant;
+pub mod journal;
+
+/// the commands of the terminal front end in src/main.rs
+pub mod repl;
+
 /// creating a shortcut to a path with the use keyword
 /// bringing the crate::front_of_house::hosting module into scope
 /// use only creates the shortcut for the particular scope in which the use occurs
@@ -16,21 +30,24 @@ mod front_of_house;
 /// we’re bringing an item into scope 
 /// but also making that item available for others to bring into their scope
 pub use crate::front_of_house::hosting;
-/// external code can now use the path ex0701_packages_crates_modules::hosting::add_to_waitlist()
+pub use crate::front_of_house::serving;
+// the kitchen is private but its pantry and cooking can be reached through re-exports
+pub use crate::back_of_house::{cook_order, pantry};
+// external code can now use the path ex0701_packages_crates_modules::hosting::add_to_waitlist()
 
-/// if we want to bring two types of the same name into the same scope with use
-/// we can specify as and a new local name, or alias, for the type
+// if we want to bring two types of the same name into the same scope with use
+// we can specify as and a new local name, or alias, for the type
 /* example:
 use std::fmt::Result;
 use std::io::Result as IoResult;
 */
 
-/// The Glob Operator
-/// bringing all public items defined in a path into scope can be done with the * glob operator
+// The Glob Operator
+// bringing all public items defined in a path into scope can be done with the * glob operator
 /* use std::collections::*;
  */
 
-/// using nested paths to clean up large use lists
+// using nested paths to clean up large use lists
 /* example
 use std::{cmp::Ordering, io};
 */
@@ -71,6 +88,7 @@ mod back_of_house {
     }
     
     // making an enum public makes all of its variants public too
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub enum Appetizer {
         Soup,
         Salad,
@@ -90,8 +108,16 @@ mod back_of_house {
         }
     }
 
-    fn _fix_incorrect_order() {
-        _cook_order();
+    // the pantry keeps track of the ingredients in the kitchen
+    // an inline module can still keep its children in their own files
+    // the pantry module lives in src/back_of_house/pantry.rs
+    pub mod pantry;
+
+    use crate::serving::Order;
+    use pantry::{KitchenError, Pantry};
+
+    fn _fix_incorrect_order(pantry: &mut Pantry, order: &Order) {
+        let _ = cook_order(pantry, order);
         // starting relative path with super
         // it's like starting a filesystem path with the .. syntax
         // using super allow to reference an item that is in the parent module
@@ -102,7 +128,11 @@ mod back_of_house {
         super::_deliver_order();
     }
 
-    fn _cook_order() {}
+    /// cooks an order with the ingredients of the pantry
+    /// items that run out of stock along the way are 86'd automatically
+    pub fn cook_order(pantry: &mut Pantry, order: &Order) -> Result<(), KitchenError> {
+        pantry.cook(order)
+    }
 }
 
 fn _deliver_order() {}
@@ -0,0 +1,89 @@
+// a package can contain a library crate and a binary crate with the same name
+// src/main.rs is the crate root of the binary, src/lib.rs the one of the library
+// the binary uses the library like any other crate, through its public API
+//
+// usage: ex0701_packages_crates_modules [--journal FILE] [--tax RATE] [--service RATE] [SCRIPT]
+// without a script the commands are read from the terminal
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::process;
+
+use ex0701_packages_crates_modules::menu::Menu;
+use ex0701_packages_crates_modules::repl::{Mode, Session, HELP};
+use ex0701_packages_crates_modules::serving::billing::BillingPolicy;
+
+struct Options {
+    journal: Option<String>,
+    script: Option<String>,
+    policy: BillingPolicy,
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        journal: None,
+        script: None,
+        policy: BillingPolicy::default(),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "--journal" => options.journal = Some(value("--journal")?),
+            "--tax" => options.policy.tax = value("--tax")?.parse()?,
+            "--service" => options.policy.service_charge = value("--service")?.parse()?,
+            "--help" | "-h" => return Err(String::new()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => options.script = Some(arg),
+        }
+    }
+    Ok(options)
+}
+
+fn main() {
+    let options = parse_options(env::args().skip(1)).unwrap_or_else(|error| {
+        if !error.is_empty() {
+            eprintln!("{}", error);
+        }
+        eprintln!("usage: ex0701_packages_crates_modules [--journal FILE] [--tax RATE] [--service RATE] [SCRIPT]");
+        eprintln!("{}", HELP);
+        process::exit(2);
+    });
+
+    let mut session = match &options.journal {
+        Some(path) => {
+            let (session, message) =
+                Session::with_journal(path, Menu::default(), options.policy).unwrap_or_else(|error| {
+                    eprintln!("can't open the journal {}: {}", path, error);
+                    process::exit(1);
+                });
+            eprintln!("{}", message);
+            session
+        }
+        None => Session::new(Menu::default(), options.policy),
+    };
+
+    let result = match &options.script {
+        Some(path) => {
+            let file = File::open(path).unwrap_or_else(|error| {
+                eprintln!("can't open the script {}: {}", path, error);
+                process::exit(1);
+            });
+            session.run(BufReader::new(file), io::stdout(), Mode::Script)
+        }
+        None => {
+            println!("type help for a list of commands");
+            session.run(io::stdin().lock(), io::stdout(), Mode::Interactive)
+        }
+    };
+
+    match result {
+        Ok(0) => {}
+        // a script that failed somewhere shouldn't look like a success
+        Ok(_) if options.script.is_some() => process::exit(1),
+        Ok(_) => {}
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// the menu lists everything a guest can order
+// the front of house takes orders and prices them from the menu
+// the back of house looks up how to cook each item
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+// re-exporting the appetizers of the back of house
+// so that guests can order them without seeing the kitchen
+pub use crate::back_of_house::Appetizer;
+use crate::serving::billing::Money;
+
+/// one item a guest can order
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MenuItem {
+    // a breakfast with the guest's choice of toast
+    // just like back_of_house::Breakfast::summer
+    Breakfast(String),
+    Appetizer(Appetizer),
+}
+
+impl MenuItem {
+    pub fn breakfast(toast: &str) -> MenuItem {
+        MenuItem::Breakfast(String::from(toast))
+    }
+
+    /// the dish an item belongs to, no matter how it is varied
+    /// a breakfast costs the same with rye or with wheat toast
+    pub fn dish(&self) -> &'static str {
+        match self {
+            MenuItem::Breakfast(_) => "breakfast",
+            MenuItem::Appetizer(Appetizer::Soup) => "soup",
+            MenuItem::Appetizer(Appetizer::Salad) => "salad",
+        }
+    }
+}
+
+/// parses the names guests and hosts type, like "soup" or "breakfast:wheat"
+/// a breakfast without a toast comes with rye toast
+impl FromStr for MenuItem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MenuItem, String> {
+        let (dish, variation) = match s.split_once(':') {
+            Some((dish, variation)) => (dish, Some(variation)),
+            None => (s, None),
+        };
+        match (dish.to_ascii_lowercase().as_str(), variation) {
+            ("breakfast", None) => Ok(MenuItem::breakfast("Rye")),
+            ("breakfast", Some(toast)) if !toast.is_empty() => {
+                // toast names are capitalized like in Breakfast::summer("Rye")
+                let mut chars = toast.chars();
+                let first = chars.next().map(|c| c.to_ascii_uppercase()).into_iter();
+                let toast: String = first.chain(chars.map(|c| c.to_ascii_lowercase())).collect();
+                Ok(MenuItem::Breakfast(toast))
+            }
+            ("soup", None) => Ok(MenuItem::Appetizer(Appetizer::Soup)),
+            ("salad", None) => Ok(MenuItem::Appetizer(Appetizer::Salad)),
+            _ => Err(format!("`{}` is not on the menu", s)),
+        }
+    }
+}
+
+impl fmt::Display for MenuItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MenuItem::Breakfast(toast) => write!(f, "breakfast ({} toast)", toast),
+            MenuItem::Appetizer(_) => write!(f, "{}", self.dish()),
+        }
+    }
+}
+
+/// the price of every dish on the menu
+#[derive(Debug, Clone, PartialEq)]
+pub struct Menu {
+    prices: HashMap<String, Money>,
+}
+
+impl Menu {
+    /// an empty menu, prices are added with set_price
+    pub fn new() -> Menu {
+        Menu {
+            prices: HashMap::new(),
+        }
+    }
+
+    pub fn set_price(&mut self, dish: &str, price: Money) {
+        self.prices.insert(String::from(dish), price);
+    }
+
+    /// None if the dish of the item isn't on the menu
+    pub fn price_of(&self, item: &MenuItem) -> Option<Money> {
+        self.prices.get(item.dish()).copied()
+    }
+}
+
+// the default menu is the summer menu of the restaurant
+impl Default for Menu {
+    fn default() -> Menu {
+        let mut menu = Menu::new();
+        menu.set_price("breakfast", Money::from_cents(1250));
+        menu.set_price("soup", Money::from_cents(675));
+        menu.set_price("salad", Money::from_cents(725));
+        menu
+    }
+}
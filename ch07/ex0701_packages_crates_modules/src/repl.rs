@@ -0,0 +1,353 @@
+// the commands a host types to run a service from a terminal
+// every command goes through the same public API as eat_at_reastaurant
+// and the binary in main.rs only wires a Session to stdin or to a script file
+// keeping the commands in the library lets the tests replay whole scenarios
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::hosting::{self, Party};
+use crate::journal::{Journal, JournalError};
+use crate::menu::{Menu, MenuItem};
+use crate::restaurant::{Change, Restaurant};
+use crate::serving::billing::{Bill, BillingError, BillingPolicy, Money, Payment, Tip, MAX_SHARES};
+use crate::serving;
+
+pub const HELP: &str = "\
+commands:
+  table add NUMBER SEATS         add a table to the floor
+  tables                         show every table and who sits there
+  waitlist add NAME SIZE         put a party on the waitlist
+  waitlist remove NAME           take a party off the waitlist
+  waitlist                       show the waitlist
+  seat [NAME TABLE]              seat the next party that fits, or a party at a table
+  order TABLE ITEM[@SEAT]...     order items like soup, salad or breakfast:wheat
+  tip TABLE AMOUNT|PERCENT%      add a tip to the bill of a table
+  bill TABLE                     show the bill of a table
+  split TABLE WAYS|seats         split the bill evenly or by seat
+  pay TABLE METHOD AMOUNT...     pay with cash, card or gift and free the table
+  compact                        write a snapshot and empty the journal
+  help                           show this help
+  quit                           end the session";
+
+/// what a command asks the session to do next
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Continue(String),
+    Quit,
+}
+
+/// how the input of a session is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    // a prompt is shown before every command typed by a host
+    Interactive,
+    // every command of a script is echoed before its output so the run reads like a transcript
+    Script,
+}
+
+/// the state of one terminal session
+#[derive(Debug)]
+pub struct Session {
+    restaurant: Restaurant,
+    journal: Option<Journal>,
+    menu: Menu,
+    policy: BillingPolicy,
+}
+
+impl Session {
+    /// a session that keeps everything in memory
+    pub fn new(menu: Menu, policy: BillingPolicy) -> Session {
+        Session {
+            restaurant: Restaurant::new(),
+            journal: None,
+            menu,
+            policy,
+        }
+    }
+
+    /// a session that records every change in a journal
+    /// and picks up the service where the journal left off
+    pub fn with_journal<P: AsRef<Path>>(path: P, menu: Menu, policy: BillingPolicy) -> Result<(Session, String), JournalError> {
+        let (journal, restaurant, recovery) = Journal::open(path)?;
+        let mut message = format!(
+            "recovered {} changes from the snapshot and {} from the journal",
+            recovery.from_snapshot, recovery.replayed
+        );
+        if recovery.torn_bytes > 0 {
+            message.push_str(&format!(", skipped a torn record of {} bytes", recovery.torn_bytes));
+        }
+        let session = Session {
+            restaurant,
+            journal: Some(journal),
+            menu,
+            policy,
+        };
+        Ok((session, message))
+    }
+
+    pub fn restaurant(&self) -> &Restaurant {
+        &self.restaurant
+    }
+
+    /// runs one command line, blank lines and # comments do nothing
+    pub fn execute(&mut self, line: &str) -> Result<Outcome, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let output = match words.as_slice() {
+            [] => String::new(),
+            [first, ..] if first.starts_with('#') => String::new(),
+            ["help"] => String::from(HELP),
+            ["quit"] | ["exit"] => return Ok(Outcome::Quit),
+            ["table", "add", number, seats] => {
+                let number = parse_number(number, "table")?;
+                let seats = parse_number(seats, "seats")?;
+                self.change(Change::AddTable { number, seats })?;
+                format!("added table {} with {} seats", number, seats)
+            }
+            ["tables"] => self.tables(),
+            ["waitlist"] => self.waitlist(),
+            ["waitlist", "add", name, size] => {
+                let size = parse_number(size, "party size")?;
+                if size == 0 {
+                    return Err(String::from("a party needs at least one guest"));
+                }
+                self.change(Change::Waitlist(Party::new(name, size)))?;
+                format!("{} ({}) is number {} on the waitlist", name, size, self.restaurant.waitlist.len())
+            }
+            ["waitlist", "remove", name] => {
+                self.change(Change::LeaveWaitlist {
+                    name: String::from(*name),
+                })?;
+                format!("{} left the waitlist", name)
+            }
+            ["seat"] => {
+                // finding the table on copies keeps the state untouched
+                // until the change was recorded
+                let mut waitlist = self.restaurant.waitlist.clone();
+                let mut floor = self.restaurant.floor.clone();
+                let (table, party) = hosting::seat_at_table(&mut waitlist, &mut floor)
+                    .ok_or_else(|| String::from("no waiting party fits a free table"))?;
+                self.change(Change::Seat { table, party: party.clone() })?;
+                format!("seated {} ({}) at table {}", party.name, party.size, table)
+            }
+            ["seat", name, table] => {
+                let table = parse_number(table, "table")?;
+                let party = self
+                    .restaurant
+                    .waitlist
+                    .parties()
+                    .find(|party| party.name == *name)
+                    .cloned()
+                    .ok_or_else(|| format!("{} is not on the waitlist", name))?;
+                self.change(Change::Seat { table, party: party.clone() })?;
+                format!("seated {} ({}) at table {}", party.name, party.size, table)
+            }
+            ["order", table, items @ ..] if !items.is_empty() => {
+                let table = parse_number(table, "table")?;
+                let mut ordered = Vec::new();
+                for item in items {
+                    let (item, seat) = match item.split_once('@') {
+                        Some((item, seat)) => (item, parse_number(seat, "seat")?),
+                        None => (*item, 1),
+                    };
+                    let item: MenuItem = item.parse()?;
+                    if self.menu.price_of(&item).is_none() {
+                        return Err(format!("{} has no price on the menu", item));
+                    }
+                    ordered.push((seat, item));
+                }
+                for (seat, item) in &ordered {
+                    self.change(Change::Order {
+                        table,
+                        seat: *seat,
+                        item: item.clone(),
+                        quantity: 1,
+                    })?;
+                }
+                format!("table {} ordered {} items", table, ordered.len())
+            }
+            ["tip", table, amount] => {
+                let table = parse_number(table, "table")?;
+                let tip = match amount.strip_suffix('%') {
+                    Some(percent) => Tip::Percent(percent.parse()?),
+                    None => Tip::Amount(amount.parse()?),
+                };
+                self.bill(table)?.with_tip(tip).map_err(|error| error.to_string())?;
+                self.change(Change::Tip { table, tip })?;
+                format!("table {} tips {}", table, amount)
+            }
+            ["bill", table] => {
+                let table = parse_number(table, "table")?;
+                format_bill(&self.bill(table)?)
+            }
+            ["split", table, "seats"] => {
+                let table = parse_number(table, "table")?;
+                let bill = self.bill(table)?;
+                let shares: Vec<String> = bill
+                    .split_by_seat()
+                    .iter()
+                    .map(|(seat, amount)| format!("  seat {:<3} {:>9}", seat, amount.to_string()))
+                    .collect();
+                shares.join("\n")
+            }
+            ["split", table, ways] => {
+                let table = parse_number(table, "table")?;
+                let ways = parse_number(ways, "ways")? as usize;
+                // checked before anything is split, a typo like 4000000000 is just an error
+                if ways > MAX_SHARES {
+                    return Err(BillingError::TooManyShares(ways).to_string());
+                }
+                let bill = self.bill(table)?;
+                let shares = bill.split_evenly(ways).map_err(|error| error.to_string())?;
+                let shares: Vec<String> = shares
+                    .iter()
+                    .enumerate()
+                    .map(|(guest, amount)| format!("  guest {:<3} {:>9}", guest + 1, amount.to_string()))
+                    .collect();
+                shares.join("\n")
+            }
+            ["pay", table, payments @ ..] if !payments.is_empty() && payments.len() % 2 == 0 => {
+                let table = parse_number(table, "table")?;
+                let mut tendered = Vec::new();
+                for pair in payments.chunks(2) {
+                    tendered.push(Payment {
+                        method: pair[0].parse()?,
+                        amount: pair[1].parse()?,
+                    });
+                }
+                let bill = self.bill(table)?;
+                let receipt = serving::take_payment(&bill, tendered).map_err(|error| error.to_string())?;
+                // clearing the table drops its tip too
+                self.change(Change::Clear { table })?;
+                format!(
+                    "table {} paid {}, tendered {}, change {}",
+                    table, receipt.total, receipt.tendered, receipt.change
+                )
+            }
+            ["compact"] => match &mut self.journal {
+                Some(journal) => {
+                    journal.compact(&self.restaurant).map_err(|error| error.to_string())?;
+                    String::from("the journal was compacted into a snapshot")
+                }
+                None => return Err(String::from("this session has no journal")),
+            },
+            _ => return Err(format!("unknown command `{}`, type help for a list", line.trim())),
+        };
+        Ok(Outcome::Continue(output))
+    }
+
+    /// runs every line of the input until it ends or quit is typed
+    /// errors are reported and the session goes on
+    /// returns the number of commands that failed
+    pub fn run<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W, mode: Mode) -> io::Result<usize> {
+        let mut failures = 0;
+        let mut line = String::new();
+        loop {
+            if mode == Mode::Interactive {
+                write!(output, "> ")?;
+                output.flush()?;
+            }
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                break;
+            }
+            if mode == Mode::Script && !line.trim().is_empty() {
+                writeln!(output, "> {}", line.trim())?;
+            }
+            match self.execute(&line) {
+                Ok(Outcome::Quit) => break,
+                Ok(Outcome::Continue(text)) if text.is_empty() => {}
+                Ok(Outcome::Continue(text)) => writeln!(output, "{}", text)?,
+                Err(error) => {
+                    failures += 1;
+                    writeln!(output, "error: {}", error)?;
+                }
+            }
+        }
+        output.flush()?;
+        Ok(failures)
+    }
+
+    fn change(&mut self, change: Change) -> Result<(), String> {
+        match &mut self.journal {
+            Some(journal) => journal
+                .record(&mut self.restaurant, change)
+                .map_err(|error| error.to_string()),
+            None => self.restaurant.apply(&change).map_err(|error| error.to_string()),
+        }
+    }
+
+    fn bill(&self, table: u32) -> Result<Bill, String> {
+        let order = self
+            .restaurant
+            .orders
+            .get(&table)
+            .ok_or_else(|| format!("table {} has no open order", table))?;
+        let bill = Bill::from_order(order, &self.menu, self.policy).map_err(|error| error.to_string())?;
+        Ok(match self.restaurant.tips.get(&table) {
+            Some(tip) => bill.with_tip(*tip).map_err(|error| error.to_string())?,
+            None => bill,
+        })
+    }
+
+    fn tables(&self) -> String {
+        let lines: Vec<String> = self
+            .restaurant
+            .floor
+            .tables()
+            .iter()
+            .map(|table| match &table.party {
+                Some(party) => format!("  table {:<3} {} seats  {} ({})", table.number, table.seats, party.name, party.size),
+                None => format!("  table {:<3} {} seats  free", table.number, table.seats),
+            })
+            .collect();
+        if lines.is_empty() {
+            String::from("there are no tables yet")
+        } else {
+            lines.join("\n")
+        }
+    }
+
+    fn waitlist(&self) -> String {
+        let lines: Vec<String> = self
+            .restaurant
+            .waitlist
+            .parties()
+            .enumerate()
+            .map(|(index, party)| format!("  {:>2}. {} ({})", index + 1, party.name, party.size))
+            .collect();
+        if lines.is_empty() {
+            String::from("nobody is waiting")
+        } else {
+            lines.join("\n")
+        }
+    }
+}
+
+fn parse_number(word: &str, what: &str) -> Result<u32, String> {
+    word.parse().map_err(|_| format!("`{}` is not a valid {}", word, what))
+}
+
+fn format_bill(bill: &Bill) -> String {
+    let mut lines = vec![format!("bill for table {}", bill.table())];
+    for line in bill.lines() {
+        lines.push(format!(
+            "  seat {:<3} {:<24} {:>3} x {:>7} {:>9}",
+            line.seat,
+            line.item.to_string(),
+            line.quantity,
+            line.unit_price.to_string(),
+            line.amount.to_string()
+        ));
+    }
+    let totals: [(&str, Money); 5] = [
+        ("subtotal", bill.subtotal()),
+        ("service charge", bill.service_charge()),
+        ("tax", bill.tax()),
+        ("tip", bill.tip()),
+        ("total", bill.total()),
+    ];
+    for (label, amount) in totals {
+        lines.push(format!("  {:<47} {:>9}", label, amount.to_string()));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,159 @@
+// the state of a running service: who is waiting, who sits where and what they ordered
+// the state only ever changes by applying a Change
+// so replaying the same changes always rebuilds the same state
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::hosting::{Floor, Party, SeatingError, Waitlist};
+use crate::menu::MenuItem;
+use crate::serving::billing::Tip;
+use crate::serving::Order;
+
+/// one change to the state of the restaurant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    AddTable { number: u32, seats: u32 },
+    Waitlist(Party),
+    LeaveWaitlist { name: String },
+    // a party on the waitlist with the same name is taken off it
+    Seat { table: u32, party: Party },
+    Order { table: u32, seat: u32, item: MenuItem, quantity: u32 },
+    // replaces an earlier tip of the table
+    Tip { table: u32, tip: Tip },
+    // the party at the table left and its order is closed
+    Clear { table: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    TableExists(u32),
+    NoSuchTable(u32),
+    NotWaiting(String),
+    EmptyTable(u32),
+    Seating(SeatingError),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::TableExists(table) => write!(f, "table {} already exists", table),
+            StateError::NoSuchTable(table) => write!(f, "there is no table {}", table),
+            StateError::NotWaiting(name) => write!(f, "{} is not on the waitlist", name),
+            StateError::EmptyTable(table) => write!(f, "nobody is sitting at table {}", table),
+            StateError::Seating(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl From<SeatingError> for StateError {
+    fn from(error: SeatingError) -> StateError {
+        StateError::Seating(error)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Restaurant {
+    pub waitlist: Waitlist,
+    pub floor: Floor,
+    // the open order of every occupied table that ordered something
+    pub orders: BTreeMap<u32, Order>,
+    // the tips of occupied tables that haven't paid yet
+    pub tips: BTreeMap<u32, Tip>,
+}
+
+impl Restaurant {
+    pub fn new() -> Restaurant {
+        Restaurant::default()
+    }
+
+    /// applies a change or leaves the state untouched if it doesn't fit
+    pub fn apply(&mut self, change: &Change) -> Result<(), StateError> {
+        match change {
+            Change::AddTable { number, seats } => {
+                if self.floor.table(*number).is_some() {
+                    return Err(StateError::TableExists(*number));
+                }
+                self.floor.add_table(*number, *seats);
+            }
+            Change::Waitlist(party) => self.waitlist.add(party.clone()),
+            Change::LeaveWaitlist { name } => {
+                self.waitlist
+                    .remove(name)
+                    .ok_or_else(|| StateError::NotWaiting(name.clone()))?;
+            }
+            Change::Seat { table, party } => {
+                self.floor.seat(*table, party.clone())?;
+                self.waitlist.remove(&party.name);
+            }
+            Change::Order {
+                table,
+                seat,
+                item,
+                quantity,
+            } => {
+                self.occupied(*table)?;
+                self.orders
+                    .entry(*table)
+                    .or_insert_with(|| Order::new(*table))
+                    .add(*seat, item.clone(), *quantity);
+            }
+            Change::Tip { table, tip } => {
+                self.occupied(*table)?;
+                self.tips.insert(*table, *tip);
+            }
+            Change::Clear { table } => {
+                self.occupied(*table)?;
+                self.floor.clear(*table);
+                self.orders.remove(table);
+                self.tips.remove(table);
+            }
+        }
+        Ok(())
+    }
+
+    /// the changes that rebuild this state in an empty restaurant
+    pub fn changes(&self) -> Vec<Change> {
+        let mut changes = Vec::new();
+        for table in self.floor.tables() {
+            changes.push(Change::AddTable {
+                number: table.number,
+                seats: table.seats,
+            });
+        }
+        for table in self.floor.tables() {
+            if let Some(party) = &table.party {
+                changes.push(Change::Seat {
+                    table: table.number,
+                    party: party.clone(),
+                });
+            }
+        }
+        for order in self.orders.values() {
+            for line in &order.lines {
+                changes.push(Change::Order {
+                    table: order.table,
+                    seat: line.seat,
+                    item: line.item.clone(),
+                    quantity: line.quantity,
+                });
+            }
+        }
+        for (&table, &tip) in &self.tips {
+            changes.push(Change::Tip { table, tip });
+        }
+        for party in self.waitlist.parties() {
+            changes.push(Change::Waitlist(party.clone()));
+        }
+        changes
+    }
+
+    fn occupied(&self, number: u32) -> Result<(), StateError> {
+        match self.floor.table(number) {
+            None => Err(StateError::NoSuchTable(number)),
+            Some(table) if table.is_free() => Err(StateError::EmptyTable(number)),
+            Some(_) => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,687 @@
+// a discrete-event simulation of a whole service
+// nothing waits for real time, the clock jumps from one event to the next
+// events are kept in a priority queue ordered by the minute they happen at
+// all randomness comes from generators seeded by the configuration
+// so the same configuration always plays out exactly the same way
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::cook_order;
+use crate::hosting::{self, Floor, Party, Waitlist};
+use crate::menu::{Menu, MenuItem};
+use crate::pantry::{Pantry, RecipeBook, Unit};
+use crate::serving::billing::{Bill, BillingError, BillingPolicy, Money, Payment, PaymentMethod, Rate, Tip};
+use crate::serving::{self, Order};
+
+/// how many people work a shift
+/// servers take orders and payments, cooks prepare the orders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Staffing {
+    pub servers: u32,
+    pub cooks: u32,
+}
+
+/// everything that shapes a simulated service, all durations are in minutes
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub seed: u64,
+    // no new parties arrive after the doors close but everyone inside is still served
+    pub open_minutes: u32,
+    // parties arrive at random with this average gap between them
+    pub mean_arrival_gap: f64,
+    pub party_sizes: RangeInclusive<u32>,
+    // a party leaves the waitlist if it isn't seated within this time
+    pub patience: u32,
+    // the number of seats of every table
+    pub tables: Vec<u32>,
+    pub staffing: Staffing,
+    pub reading_menu: RangeInclusive<u32>,
+    // the time a server needs to take an order or a payment
+    pub server_task: RangeInclusive<u32>,
+    pub cooking_per_item: RangeInclusive<u32>,
+    pub eating: RangeInclusive<u32>,
+    pub tip_percent: RangeInclusive<u32>,
+    pub menu: Menu,
+    pub policy: BillingPolicy,
+    pub pantry: Pantry,
+}
+
+impl Default for SimulationConfig {
+    // a three hour lunch service with the summer menu and a well stocked pantry
+    fn default() -> SimulationConfig {
+        let mut pantry = Pantry::new(RecipeBook::summer());
+        for (ingredient, unit, quantity) in [
+            ("rye bread", Unit::Pieces, 80),
+            ("wheat bread", Unit::Pieces, 80),
+            ("white bread", Unit::Pieces, 80),
+            ("butter", Unit::Grams, 2_000),
+            ("eggs", Unit::Pieces, 120),
+            ("peaches", Unit::Pieces, 60),
+            ("vegetable stock", Unit::Milliliters, 15_000),
+            ("carrots", Unit::Grams, 5_000),
+            ("onions", Unit::Grams, 3_000),
+            ("lettuce", Unit::Grams, 6_000),
+            ("tomatoes", Unit::Pieces, 60),
+            ("olive oil", Unit::Milliliters, 1_000),
+        ] {
+            pantry.stock_ingredient(ingredient, unit, quantity / 4, quantity);
+            pantry.receive(ingredient, quantity);
+        }
+
+        SimulationConfig {
+            seed: 7,
+            open_minutes: 180,
+            mean_arrival_gap: 4.0,
+            party_sizes: 1..=6,
+            patience: 30,
+            tables: vec![2, 2, 2, 4, 4, 4, 6],
+            staffing: Staffing { servers: 2, cooks: 2 },
+            reading_menu: 3..=8,
+            server_task: 2..=4,
+            cooking_per_item: 2..=4,
+            eating: 20..=45,
+            tip_percent: 10..=20,
+            menu: Menu::default(),
+            policy: BillingPolicy {
+                tax: Rate::percent(8).expect("8% is a valid rate"),
+                ..BillingPolicy::default()
+            },
+            pantry,
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// checks everything a service can't run without
+    /// a simulation only runs with a configuration that passed this check
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.open_minutes == 0 {
+            return Err(ConfigError::NeverOpen);
+        }
+        if !(self.mean_arrival_gap.is_finite() && self.mean_arrival_gap > 0.0) {
+            return Err(ConfigError::InvalidArrivalGap(self.mean_arrival_gap));
+        }
+        for (name, range) in [
+            ("party_sizes", &self.party_sizes),
+            ("reading_menu", &self.reading_menu),
+            ("server_task", &self.server_task),
+            ("cooking_per_item", &self.cooking_per_item),
+            ("eating", &self.eating),
+            ("tip_percent", &self.tip_percent),
+        ] {
+            if range.is_empty() {
+                return Err(ConfigError::EmptyRange(name));
+            }
+        }
+        if *self.party_sizes.start() == 0 {
+            return Err(ConfigError::EmptyParty);
+        }
+        if Rate::percent(*self.tip_percent.end()).is_none() {
+            return Err(ConfigError::TipTooLarge(*self.tip_percent.end()));
+        }
+        if self.tables.is_empty() || self.tables.contains(&0) {
+            return Err(ConfigError::NoSeats);
+        }
+        // without servers or cooks seated parties would never pay
+        // and the report couldn't account for them
+        if self.staffing.servers == 0 || self.staffing.cooks == 0 {
+            return Err(ConfigError::Understaffed(self.staffing));
+        }
+        Ok(())
+    }
+}
+
+/// why a configuration can't be simulated
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    NeverOpen,
+    InvalidArrivalGap(f64),
+    // the name of the field whose range has no values
+    EmptyRange(&'static str),
+    EmptyParty,
+    TipTooLarge(u32),
+    NoSeats,
+    Understaffed(Staffing),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::NeverOpen => write!(f, "the restaurant has to be open for at least a minute"),
+            ConfigError::InvalidArrivalGap(gap) => {
+                write!(f, "the mean gap between arrivals has to be positive, not {}", gap)
+            }
+            ConfigError::EmptyRange(name) => write!(f, "the range of {} is empty", name),
+            ConfigError::EmptyParty => write!(f, "a party has at least one guest"),
+            ConfigError::TipTooLarge(percent) => write!(f, "a tip of {}% is too large", percent),
+            ConfigError::NoSeats => write!(f, "every table needs seats and there has to be a table"),
+            ConfigError::Understaffed(staffing) => write!(
+                f,
+                "a service needs at least one server and one cook, not {} and {}",
+                staffing.servers, staffing.cooks
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// what happened during a simulated service
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationReport {
+    pub staffing: Staffing,
+    pub parties_arrived: u32,
+    pub parties_seated: u32,
+    pub parties_paid: u32,
+    // parties that gave up waiting for a table
+    pub parties_walked_out: u32,
+    // parties that were seated but found nothing left they could order
+    pub parties_left_hungry: u32,
+    pub guests_served: u32,
+    // minutes from arrival until being seated, averaged over the seated parties
+    pub average_wait: f64,
+    pub longest_wait: u32,
+    // how often each table was seated, in the order of the configured tables
+    pub turns_per_table: Vec<u32>,
+    pub revenue: Money,
+    pub tips: Money,
+    // the minute the last party left
+    pub closing_time: u32,
+    pub events: u32,
+}
+
+impl SimulationReport {
+    /// the average number of times a table was seated
+    pub fn table_turns(&self) -> f64 {
+        if self.turns_per_table.is_empty() {
+            return 0.0;
+        }
+        let turns: u32 = self.turns_per_table.iter().sum();
+        f64::from(turns) / self.turns_per_table.len() as f64
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "staffing        {} servers, {} cooks",
+            self.staffing.servers, self.staffing.cooks
+        )?;
+        writeln!(
+            f,
+            "parties         {} arrived, {} seated, {} paid, {} walked out, {} left hungry",
+            self.parties_arrived,
+            self.parties_seated,
+            self.parties_paid,
+            self.parties_walked_out,
+            self.parties_left_hungry
+        )?;
+        writeln!(f, "guests served   {}", self.guests_served)?;
+        writeln!(
+            f,
+            "wait            {:.1} min on average, {} min at most",
+            self.average_wait, self.longest_wait
+        )?;
+        writeln!(f, "table turns     {:.2} ({:?})", self.table_turns(), self.turns_per_table)?;
+        writeln!(f, "revenue         {} (tips {})", self.revenue, self.tips)?;
+        write!(f, "closing time    minute {}", self.closing_time)
+    }
+}
+
+// events only need to be ordered so they fit in the priority queue
+// the minute and the sequence number always decide first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Event {
+    Arrival(usize),
+    GiveUp(usize),
+    ReadyToOrder(usize),
+    OrderTaken(usize),
+    OrderCooked(usize),
+    DoneEating(usize),
+    Paid(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Task {
+    TakeOrder(usize),
+    TakePayment(usize),
+}
+
+struct Guests {
+    party: Party,
+    arrived: u32,
+    table: Option<u32>,
+    order: Option<Order>,
+}
+
+// the state of the restaurant while the simulation runs
+struct Service<'a> {
+    config: &'a SimulationConfig,
+    // arrivals have a generator of their own
+    // so the same parties arrive no matter how fast they are served
+    arrivals: StdRng,
+    rng: StdRng,
+    now: u32,
+    sequence: u64,
+    events: BinaryHeap<Reverse<(u32, u64, Event)>>,
+    parties: Vec<Guests>,
+    waitlist: Waitlist,
+    floor: Floor,
+    pantry: Pantry,
+    idle_servers: u32,
+    server_tasks: VecDeque<Task>,
+    idle_cooks: u32,
+    kitchen_queue: VecDeque<usize>,
+    report: SimulationReport,
+    total_wait: u64,
+}
+
+impl<'a> Service<'a> {
+    fn new(config: &'a SimulationConfig) -> Service<'a> {
+        Service {
+            config,
+            arrivals: StdRng::seed_from_u64(config.seed),
+            rng: StdRng::seed_from_u64(config.seed.wrapping_add(1)),
+            now: 0,
+            sequence: 0,
+            events: BinaryHeap::new(),
+            parties: Vec::new(),
+            waitlist: Waitlist::new(),
+            floor: Floor::with_tables(&config.tables),
+            pantry: config.pantry.clone(),
+            idle_servers: config.staffing.servers,
+            server_tasks: VecDeque::new(),
+            idle_cooks: config.staffing.cooks,
+            kitchen_queue: VecDeque::new(),
+            report: SimulationReport {
+                staffing: config.staffing,
+                parties_arrived: 0,
+                parties_seated: 0,
+                parties_paid: 0,
+                parties_walked_out: 0,
+                parties_left_hungry: 0,
+                guests_served: 0,
+                average_wait: 0.0,
+                longest_wait: 0,
+                turns_per_table: vec![0; config.tables.len()],
+                revenue: Money::ZERO,
+                tips: Money::ZERO,
+                closing_time: 0,
+                events: 0,
+            },
+            total_wait: 0,
+        }
+    }
+
+    // the sequence number breaks ties so events at the same minute keep their order
+    // an event too far away for the clock happens at its last minute, like a patience of u32::MAX
+    fn schedule(&mut self, delay: u32, event: Event) {
+        self.sequence += 1;
+        self.events.push(Reverse((self.now.saturating_add(delay), self.sequence, event)));
+    }
+
+    fn duration(&mut self, range: &RangeInclusive<u32>) -> u32 {
+        self.rng.gen_range(range.clone())
+    }
+
+    // the gaps between arrivals of a Poisson process are exponentially distributed
+    fn arrival_gap(&mut self) -> u32 {
+        let uniform: f64 = self.arrivals.gen_range(f64::EPSILON..1.0);
+        (-uniform.ln() * self.config.mean_arrival_gap).round().max(1.0) as u32
+    }
+
+    fn new_party(&mut self) -> usize {
+        let id = self.parties.len();
+        let size = self.arrivals.gen_range(self.config.party_sizes.clone());
+        self.parties.push(Guests {
+            party: Party::new(&format!("party-{}", id + 1), size),
+            arrived: 0,
+            table: None,
+            order: None,
+        });
+        id
+    }
+
+    fn run(mut self) -> SimulationReport {
+        // the first party arrives under the same rule as all the others
+        let gap = self.arrival_gap();
+        if gap <= self.config.open_minutes {
+            let first = self.new_party();
+            self.schedule(gap, Event::Arrival(first));
+        }
+
+        while let Some(Reverse((time, _, event))) = self.events.pop() {
+            self.now = time;
+            self.report.events += 1;
+            self.handle(event);
+        }
+
+        let seated = self.report.parties_seated;
+        if seated > 0 {
+            self.report.average_wait = self.total_wait as f64 / f64::from(seated);
+        }
+        self.report.closing_time = self.now;
+        self.report
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Arrival(id) => {
+                self.report.parties_arrived += 1;
+                self.parties[id].arrived = self.now;
+                self.waitlist.add(self.parties[id].party.clone());
+                self.schedule(self.config.patience, Event::GiveUp(id));
+                self.seat_parties();
+
+                let gap = self.arrival_gap();
+                if self.now.saturating_add(gap) <= self.config.open_minutes {
+                    let next = self.new_party();
+                    self.schedule(gap, Event::Arrival(next));
+                }
+            }
+            Event::GiveUp(id) => {
+                if self.waitlist.remove(&self.parties[id].party.name).is_some() {
+                    self.report.parties_walked_out += 1;
+                }
+            }
+            Event::ReadyToOrder(id) => self.assign(Task::TakeOrder(id)),
+            Event::OrderTaken(id) => {
+                self.server_done();
+                self.place_order(id);
+            }
+            Event::OrderCooked(id) => {
+                self.idle_cooks += 1;
+                self.start_cooking();
+                let eating = self.duration(&self.config.eating.clone());
+                self.schedule(eating, Event::DoneEating(id));
+            }
+            Event::DoneEating(id) => self.assign(Task::TakePayment(id)),
+            Event::Paid(id) => {
+                self.server_done();
+                self.settle(id);
+                self.leave(id);
+            }
+        }
+    }
+
+    // seats waiting parties for as long as a free table fits one of them
+    fn seat_parties(&mut self) {
+        while let Some((number, party)) = hosting::seat_at_table(&mut self.waitlist, &mut self.floor) {
+            let id = self
+                .parties
+                .iter()
+                .position(|guests| guests.party.name == party.name)
+                .expect("every seated party was generated by the simulation");
+            let wait = self.now - self.parties[id].arrived;
+            self.total_wait += u64::from(wait);
+            self.report.longest_wait = self.report.longest_wait.max(wait);
+            self.report.parties_seated += 1;
+            if let Some(index) = self.floor.tables().iter().position(|table| table.number == number) {
+                self.report.turns_per_table[index] += 1;
+            }
+            self.parties[id].table = Some(number);
+            let reading = self.duration(&self.config.reading_menu.clone());
+            self.schedule(reading, Event::ReadyToOrder(id));
+        }
+    }
+
+    fn assign(&mut self, task: Task) {
+        self.server_tasks.push_back(task);
+        self.start_server_tasks();
+    }
+
+    fn server_done(&mut self) {
+        self.idle_servers += 1;
+        self.start_server_tasks();
+    }
+
+    fn start_server_tasks(&mut self) {
+        while self.idle_servers > 0 {
+            let Some(task) = self.server_tasks.pop_front() else {
+                break;
+            };
+            self.idle_servers -= 1;
+            let time = self.duration(&self.config.server_task.clone());
+            match task {
+                Task::TakeOrder(id) => self.schedule(time, Event::OrderTaken(id)),
+                Task::TakePayment(id) => self.schedule(time, Event::Paid(id)),
+            }
+        }
+    }
+
+    // every guest orders one of the items that are still available
+    // the ingredients leave the pantry as soon as the order is taken
+    fn place_order(&mut self, id: usize) {
+        let table = self.parties[id].table.unwrap_or_default();
+        let mut order = serving::take_order(table);
+        for seat in 1..=self.parties[id].party.size {
+            // the recipe book is a HashMap so the choices are sorted
+            // to keep the random picks reproducible
+            let mut choices: Vec<MenuItem> = self
+                .pantry
+                .recipes()
+                .items()
+                .filter(|item| self.pantry.is_available(item) && self.config.menu.price_of(item).is_some())
+                .cloned()
+                .collect();
+            choices.sort_by_key(|item| item.to_string());
+
+            while !choices.is_empty() {
+                let item = choices.remove(self.rng.gen_range(0..choices.len()));
+                let mut line = Order::new(table);
+                line.add(seat, item.clone(), 1);
+                if cook_order(&mut self.pantry, &line).is_ok() {
+                    order.add(seat, item, 1);
+                    break;
+                }
+            }
+        }
+
+        if order.lines.is_empty() {
+            self.report.parties_left_hungry += 1;
+            self.leave(id);
+            return;
+        }
+        self.parties[id].order = Some(order);
+        self.kitchen_queue.push_back(id);
+        self.start_cooking();
+    }
+
+    fn start_cooking(&mut self) {
+        while self.idle_cooks > 0 {
+            let Some(id) = self.kitchen_queue.pop_front() else {
+                break;
+            };
+            self.idle_cooks -= 1;
+            let items = self.parties[id].order.as_ref().map_or(0, |order| order.lines.len());
+            let time = (0..items)
+                .map(|_| self.duration(&self.config.cooking_per_item.clone()))
+                .fold(0, u32::saturating_add);
+            self.schedule(time, Event::OrderCooked(id));
+        }
+    }
+
+    fn settle(&mut self, id: usize) {
+        let Some(order) = self.parties[id].order.take() else {
+            return;
+        };
+        let tip = self.duration(&self.config.tip_percent.clone());
+        let bill = Bill::from_order(&order, &self.config.menu, self.config.policy).and_then(|bill| {
+            let rate = Rate::percent(tip).ok_or(BillingError::TooLarge)?;
+            bill.with_tip(Tip::Percent(rate))
+        });
+        let Ok(bill) = bill else {
+            return;
+        };
+        let payment = Payment {
+            method: PaymentMethod::Card,
+            amount: bill.total(),
+        };
+        if let Ok(receipt) = serving::take_payment(&bill, vec![payment]) {
+            self.report.parties_paid += 1;
+            self.report.guests_served += self.parties[id].party.size;
+            self.report.revenue += receipt.total;
+            self.report.tips += bill.tip();
+        }
+    }
+
+    fn leave(&mut self, id: usize) {
+        if let Some(number) = self.parties[id].table.take() {
+            self.floor.clear(number);
+        }
+        self.seat_parties();
+    }
+}
+
+/// runs one service from opening until the last party has left
+pub fn simulate(config: &SimulationConfig) -> Result<SimulationReport, ConfigError> {
+    config.validate()?;
+    Ok(Service::new(config).run())
+}
+
+/// runs the same service with every staffing
+/// because the seed stays the same the parties arrive identically in every run
+/// and only the staffing explains the differences between the reports
+pub fn compare_staffing(
+    config: &SimulationConfig,
+    staffings: &[Staffing],
+) -> Result<Vec<SimulationReport>, ConfigError> {
+    staffings
+        .iter()
+        .map(|&staffing| {
+            let config = SimulationConfig {
+                staffing,
+                ..config.clone()
+            };
+            simulate(&config)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_plays_out_the_same_service() {
+        let config = SimulationConfig::default();
+        assert_eq!(simulate(&config), simulate(&config));
+    }
+
+    fn every_party_is_accounted_for_in(report: &SimulationReport) {
+        assert_eq!(
+            report.parties_arrived,
+            report.parties_paid + report.parties_walked_out + report.parties_left_hungry
+        );
+        assert_eq!(report.turns_per_table.iter().sum::<u32>(), report.parties_seated);
+    }
+
+    #[test]
+    fn every_party_is_accounted_for() {
+        let report = simulate(&SimulationConfig::default()).unwrap();
+        assert!(report.parties_arrived > 0);
+        every_party_is_accounted_for_in(&report);
+        assert!(report.revenue > Money::ZERO);
+
+        // a single minute of opening hours and a single server still settle every party
+        let config = SimulationConfig {
+            open_minutes: 1,
+            mean_arrival_gap: 0.5,
+            staffing: Staffing { servers: 1, cooks: 1 },
+            ..SimulationConfig::default()
+        };
+        let report = simulate(&config).unwrap();
+        assert!(report.parties_arrived <= 1);
+        every_party_is_accounted_for_in(&report);
+    }
+
+    #[test]
+    fn staffing_changes_nothing_but_the_staff() {
+        let config = SimulationConfig::default();
+        let reports = compare_staffing(
+            &config,
+            &[Staffing { servers: 1, cooks: 1 }, Staffing { servers: 4, cooks: 4 }],
+        )
+        .unwrap();
+        // arrivals only depend on the seed, not on the staffing
+        assert_eq!(reports[0].parties_arrived, reports[1].parties_arrived);
+        assert!(reports[0].average_wait >= reports[1].average_wait);
+    }
+
+    #[test]
+    fn invalid_configurations_are_rejected() {
+        let config = SimulationConfig::default;
+        #[allow(clippy::reversed_empty_ranges)]
+        let party_sizes = 5..=1;
+        let cases = [
+            (
+                SimulationConfig {
+                    party_sizes,
+                    ..config()
+                },
+                ConfigError::EmptyRange("party_sizes"),
+            ),
+            (
+                SimulationConfig {
+                    staffing: Staffing { servers: 0, cooks: 2 },
+                    ..config()
+                },
+                ConfigError::Understaffed(Staffing { servers: 0, cooks: 2 }),
+            ),
+            (
+                SimulationConfig {
+                    staffing: Staffing { servers: 2, cooks: 0 },
+                    ..config()
+                },
+                ConfigError::Understaffed(Staffing { servers: 2, cooks: 0 }),
+            ),
+            (
+                SimulationConfig {
+                    open_minutes: 0,
+                    ..config()
+                },
+                ConfigError::NeverOpen,
+            ),
+            (
+                SimulationConfig {
+                    mean_arrival_gap: f64::NAN,
+                    ..config()
+                },
+                ConfigError::InvalidArrivalGap(f64::NAN),
+            ),
+            (
+                SimulationConfig {
+                    tables: Vec::new(),
+                    ..config()
+                },
+                ConfigError::NoSeats,
+            ),
+        ];
+        for (config, expected) in cases {
+            let error = simulate(&config).unwrap_err();
+            // NaN isn't equal to itself so the variants are compared by their message
+            assert_eq!(error.to_string(), expected.to_string());
+        }
+        let staffings = [Staffing { servers: 1, cooks: 1 }, Staffing { servers: 1, cooks: 0 }];
+        assert!(compare_staffing(&config(), &staffings).is_err());
+    }
+
+    #[test]
+    fn endless_durations_end_at_the_last_minute() {
+        let config = SimulationConfig {
+            patience: u32::MAX,
+            cooking_per_item: u32::MAX - 1..=u32::MAX,
+            eating: u32::MAX - 1..=u32::MAX,
+            ..SimulationConfig::default()
+        };
+        let report = simulate(&config).unwrap();
+        every_party_is_accounted_for_in(&report);
+        assert_eq!(report.closing_time, u32::MAX);
+    }
+}
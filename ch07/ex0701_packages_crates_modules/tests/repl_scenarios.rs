@@ -0,0 +1,106 @@
+// replaying scripted services through the same session the terminal front end uses
+use std::env;
+use std::fs;
+use std::io::Cursor;
+use std::process;
+
+use ex0701_packages_crates_modules::menu::Menu;
+use ex0701_packages_crates_modules::repl::{Mode, Session};
+use ex0701_packages_crates_modules::serving::billing::{BillingPolicy, Rate};
+
+fn policy() -> BillingPolicy {
+    BillingPolicy {
+        tax: "8.875".parse().unwrap(),
+        service_charge: Rate::percent(10).unwrap(),
+        ..BillingPolicy::default()
+    }
+}
+
+// runs a script and returns the transcript and the number of failed commands
+fn replay(session: &mut Session, script: &str) -> (String, usize) {
+    let mut output = Vec::new();
+    let failures = session
+        .run(Cursor::new(script), &mut output, Mode::Script)
+        .unwrap();
+    (String::from_utf8(output).unwrap(), failures)
+}
+
+#[test]
+fn a_lunch_service_runs_from_a_script() {
+    let script = fs::read_to_string("tests/scenarios/lunch.txt").unwrap();
+    let mut session = Session::new(Menu::default(), policy());
+    let (transcript, failures) = replay(&mut session, &script);
+
+    assert_eq!(failures, 0, "{}", transcript);
+    assert!(transcript.contains("seated Ada (2) at table 1"));
+    assert!(transcript.contains("table 1 paid 22.88, tendered 25.00, change 2.12"));
+    assert!(transcript.contains("table 2 paid 45.19, tendered 50.00, change 4.81"));
+
+    // Linus waited until the table for four was free again
+    let restaurant = session.restaurant();
+    assert!(restaurant.waitlist.is_empty());
+    assert_eq!(restaurant.floor.table(2).unwrap().party.as_ref().unwrap().name, "Linus");
+    assert!(restaurant.orders.is_empty());
+}
+
+#[test]
+fn failed_commands_are_reported_and_the_session_goes_on() {
+    let mut session = Session::new(Menu::default(), policy());
+    let (transcript, failures) = replay(
+        &mut session,
+        "table add 1 4\nwaitlist add Ada 2\nseat\norder 1 pizza\norder 1 soup\nsplit 1 4000000000\n\
+         pay 1 card 1.00\ntables\n",
+    );
+
+    assert_eq!(failures, 3);
+    assert!(transcript.contains("error: `pizza` is not on the menu"));
+    assert!(transcript.contains("error: a bill can't be split 4000000000 ways, at most 100"));
+    assert!(transcript.contains("error: 7.03 is still owed"));
+    assert!(transcript.contains("table 1   4 seats  Ada (2)"));
+}
+
+#[test]
+fn a_service_continues_after_a_restart() {
+    let dir = env::temp_dir().join(format!("restaurant-repl-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let journal = dir.join("service.journal");
+
+    let (mut session, _) = Session::with_journal(&journal, Menu::default(), policy()).unwrap();
+    let (_, failures) = replay(&mut session, "table add 1 4\nwaitlist add Ada 2\nseat\norder 1 soup salad\n");
+    assert_eq!(failures, 0);
+    drop(session);
+
+    let (mut session, message) = Session::with_journal(&journal, Menu::default(), policy()).unwrap();
+    assert_eq!(message, "recovered 0 changes from the snapshot and 5 from the journal");
+    let (transcript, failures) = replay(&mut session, "compact\npay 1 card 16.64\n");
+    assert_eq!(failures, 0, "{}", transcript);
+    assert!(transcript.contains("table 1 paid 16.64"));
+}
+
+#[test]
+fn a_tip_survives_a_restart() {
+    let dir = env::temp_dir().join(format!("restaurant-repl-tip-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let journal = dir.join("service.journal");
+
+    let (mut session, _) = Session::with_journal(&journal, Menu::default(), policy()).unwrap();
+    let script = "table add 1 4\nwaitlist add Ada 2\nseat\norder 1 soup salad\ntip 1 10%\n";
+    let (transcript, failures) = replay(&mut session, script);
+    assert_eq!(failures, 0, "{}", transcript);
+    drop(session);
+
+    // the tip is in the journal, and after compacting in the snapshot
+    let (mut session, message) = Session::with_journal(&journal, Menu::default(), policy()).unwrap();
+    assert_eq!(message, "recovered 0 changes from the snapshot and 6 from the journal");
+    let (_, failures) = replay(&mut session, "compact\n");
+    assert_eq!(failures, 0);
+    drop(session);
+
+    let (mut session, _) = Session::with_journal(&journal, Menu::default(), policy()).unwrap();
+    let (transcript, failures) = replay(&mut session, "pay 1 card 18.04\n");
+    assert_eq!(failures, 0, "{}", transcript);
+    assert!(transcript.contains("table 1 paid 18.04"));
+    assert!(session.restaurant().tips.is_empty());
+}
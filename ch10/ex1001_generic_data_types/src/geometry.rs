@@ -0,0 +1,412 @@
+// points and vectors, grown from Point1<T>, Point<T, U> and Point99 in main.rs
+// Point1<f32> could only measure its distance from the origin, and only for f32
+// here points and vectors work for any number type that can be added, subtracted and multiplied
+// and every distance is measured in f64, so even points of different types can be compared
+//
+// a point is a position, a vector is a direction with a length
+// a point minus a point is the vector between them, a point plus a vector is another point
+// with integers the arithmetic can overflow like any other integer arithmetic
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::statistics::Number;
+
+/// the numbers points and vectors can be made of
+/// the bounds are the ones the arithmetic needs, Default gives the zero
+pub trait Scalar: Number + Default + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {}
+
+// every type with the bounds is a Scalar, without implementing it one by one
+impl<T> Scalar for T where T: Number + Default + Add<Output = T> + Sub<Output = T> + Mul<Output = T> {}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Vector2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Vector3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Point2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Point3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Scalar> Vector2<T> {
+    pub fn new(x: T, y: T) -> Vector2<T> {
+        Vector2 { x, y }
+    }
+
+    pub fn dot(self, other: Vector2<T>) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// the z of the cross product of the two vectors lying in the xy plane
+    /// positive if other turns counterclockwise from self, negative if clockwise, zero if parallel
+    pub fn cross(self, other: Vector2<T>) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length(self) -> f64 {
+        self.x.to_f64().hypot(self.y.to_f64())
+    }
+
+    pub fn to_f64(self) -> Vector2<f64> {
+        Vector2::new(self.x.to_f64(), self.y.to_f64())
+    }
+}
+
+impl<T: Scalar> Vector3<T> {
+    pub fn new(x: T, y: T, z: T) -> Vector3<T> {
+        Vector3 { x, y, z }
+    }
+
+    pub fn dot(self, other: Vector3<T>) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// the vector standing on both vectors, with the length of the area they span
+    pub fn cross(self, other: Vector3<T>) -> Vector3<T> {
+        Vector3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(self) -> f64 {
+        let (x, y, z) = (self.x.to_f64(), self.y.to_f64(), self.z.to_f64());
+        (x * x + y * y + z * z).sqrt()
+    }
+
+    pub fn to_f64(self) -> Vector3<f64> {
+        Vector3::new(self.x.to_f64(), self.y.to_f64(), self.z.to_f64())
+    }
+}
+
+impl<T: Scalar> Point2<T> {
+    pub fn new(x: T, y: T) -> Point2<T> {
+        Point2 { x, y }
+    }
+
+    pub fn origin() -> Point2<T> {
+        Point2::default()
+    }
+
+    /// the distance to a point of any other number type, like Point<T, U> mixes types
+    pub fn distance_to<U: Scalar>(&self, other: &Point2<U>) -> f64 {
+        (self.to_f64() - other.to_f64()).length()
+    }
+
+    /// what Point1<f32>::distance_from_origin does, for every number type
+    pub fn distance_from_origin(&self) -> f64 {
+        self.distance_to(&Point2::<T>::origin())
+    }
+
+    pub fn to_f64(self) -> Point2<f64> {
+        Point2::new(self.x.to_f64(), self.y.to_f64())
+    }
+}
+
+impl Point2<f64> {
+    /// the nearest point with i64 coordinates, None if a coordinate is NaN or out of range
+    pub fn round(self) -> Option<Point2<i64>> {
+        Some(Point2::new(round_to_i64(self.x)?, round_to_i64(self.y)?))
+    }
+}
+
+impl<T: Scalar> Point3<T> {
+    pub fn new(x: T, y: T, z: T) -> Point3<T> {
+        Point3 { x, y, z }
+    }
+
+    pub fn origin() -> Point3<T> {
+        Point3::default()
+    }
+
+    pub fn distance_to<U: Scalar>(&self, other: &Point3<U>) -> f64 {
+        (self.to_f64() - other.to_f64()).length()
+    }
+
+    pub fn distance_from_origin(&self) -> f64 {
+        self.distance_to(&Point3::<T>::origin())
+    }
+
+    pub fn to_f64(self) -> Point3<f64> {
+        Point3::new(self.x.to_f64(), self.y.to_f64(), self.z.to_f64())
+    }
+}
+
+impl Point3<f64> {
+    pub fn round(self) -> Option<Point3<i64>> {
+        Some(Point3::new(round_to_i64(self.x)?, round_to_i64(self.y)?, round_to_i64(self.z)?))
+    }
+}
+
+// `as i64` would silently saturate, so a value that doesn't fit is None
+fn round_to_i64(value: f64) -> Option<i64> {
+    let rounded = value.round();
+    // i64::MAX as f64 rounds up to 2^63, which is already too large
+    if rounded.is_nan() || rounded < i64::MIN as f64 || rounded >= i64::MAX as f64 {
+        None
+    } else {
+        Some(rounded as i64)
+    }
+}
+
+// the operators, each one written out for both dimensions
+impl<T: Scalar> Add for Vector2<T> {
+    type Output = Vector2<T>;
+
+    fn add(self, other: Vector2<T>) -> Vector2<T> {
+        Vector2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Scalar> Sub for Vector2<T> {
+    type Output = Vector2<T>;
+
+    fn sub(self, other: Vector2<T>) -> Vector2<T> {
+        Vector2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+// scaling a vector by a number
+impl<T: Scalar> Mul<T> for Vector2<T> {
+    type Output = Vector2<T>;
+
+    fn mul(self, factor: T) -> Vector2<T> {
+        Vector2::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl<T: Scalar + Neg<Output = T>> Neg for Vector2<T> {
+    type Output = Vector2<T>;
+
+    fn neg(self) -> Vector2<T> {
+        Vector2::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Scalar> Add for Vector3<T> {
+    type Output = Vector3<T>;
+
+    fn add(self, other: Vector3<T>) -> Vector3<T> {
+        Vector3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl<T: Scalar> Sub for Vector3<T> {
+    type Output = Vector3<T>;
+
+    fn sub(self, other: Vector3<T>) -> Vector3<T> {
+        Vector3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl<T: Scalar> Mul<T> for Vector3<T> {
+    type Output = Vector3<T>;
+
+    fn mul(self, factor: T) -> Vector3<T> {
+        Vector3::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl<T: Scalar + Neg<Output = T>> Neg for Vector3<T> {
+    type Output = Vector3<T>;
+
+    fn neg(self) -> Vector3<T> {
+        Vector3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+// the vector from other to self
+impl<T: Scalar> Sub for Point2<T> {
+    type Output = Vector2<T>;
+
+    fn sub(self, other: Point2<T>) -> Vector2<T> {
+        Vector2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Scalar> Add<Vector2<T>> for Point2<T> {
+    type Output = Point2<T>;
+
+    fn add(self, vector: Vector2<T>) -> Point2<T> {
+        Point2::new(self.x + vector.x, self.y + vector.y)
+    }
+}
+
+impl<T: Scalar> Sub<Vector2<T>> for Point2<T> {
+    type Output = Point2<T>;
+
+    fn sub(self, vector: Vector2<T>) -> Point2<T> {
+        Point2::new(self.x - vector.x, self.y - vector.y)
+    }
+}
+
+impl<T: Scalar> Sub for Point3<T> {
+    type Output = Vector3<T>;
+
+    fn sub(self, other: Point3<T>) -> Vector3<T> {
+        Vector3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl<T: Scalar> Add<Vector3<T>> for Point3<T> {
+    type Output = Point3<T>;
+
+    fn add(self, vector: Vector3<T>) -> Point3<T> {
+        Point3::new(self.x + vector.x, self.y + vector.y, self.z + vector.z)
+    }
+}
+
+impl<T: Scalar> Sub<Vector3<T>> for Point3<T> {
+    type Output = Point3<T>;
+
+    fn sub(self, vector: Vector3<T>) -> Point3<T> {
+        Point3::new(self.x - vector.x, self.y - vector.y, self.z - vector.z)
+    }
+}
+
+/// a closed polygon, the last vertex connects back to the first one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon<T> {
+    vertices: Vec<Point2<T>>,
+}
+
+impl<T: Scalar> Polygon<T> {
+    /// None with fewer than three vertices, those don't enclose anything
+    pub fn new(vertices: Vec<Point2<T>>) -> Option<Polygon<T>> {
+        (vertices.len() >= 3).then_some(Polygon { vertices })
+    }
+
+    pub fn vertices(&self) -> &[Point2<T>] {
+        &self.vertices
+    }
+
+    // every edge as the pair of its start and end vertex, in f64
+    fn edges(&self) -> impl Iterator<Item = (Point2<f64>, Point2<f64>)> + '_ {
+        let next = self.vertices.iter().cycle().skip(1);
+        self.vertices
+            .iter()
+            .zip(next)
+            .map(|(a, b)| (a.to_f64(), b.to_f64()))
+    }
+
+    /// the shoelace formula: half the sum of the cross products of neighboring vertices
+    /// positive when the vertices go counterclockwise, negative when they go clockwise
+    pub fn signed_area(&self) -> f64 {
+        self.edges()
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum::<f64>()
+            / 2.0
+    }
+
+    pub fn area(&self) -> f64 {
+        self.signed_area().abs()
+    }
+
+    pub fn perimeter(&self) -> f64 {
+        self.edges().map(|(a, b)| a.distance_to(&b)).sum()
+    }
+
+    /// true if the point is inside the polygon or on its edge
+    /// a ray from the point to the right crosses the edges an odd number of times from the inside
+    pub fn contains<U: Scalar>(&self, point: &Point2<U>) -> bool {
+        let p = point.to_f64();
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            if on_segment(p, a, b) {
+                return true;
+            }
+            // only edges that span the height of the point, each one counted with one end
+            if (a.y > p.y) != (b.y > p.y) {
+                let crossing_x = a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+                if p.x < crossing_x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+}
+
+fn on_segment(p: Point2<f64>, a: Point2<f64>, b: Point2<f64>) -> bool {
+    (b - a).cross(p - a) == 0.0
+        && p.x >= a.x.min(b.x)
+        && p.x <= a.x.max(b.x)
+        && p.y >= a.y.min(b.y)
+        && p.y <= a.y.max(b.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_and_vectors_add_up() {
+        let a = Point2::new(1, 2);
+        let b = Point2::new(4, 6);
+        let ab = b - a;
+        assert_eq!(ab, Vector2::new(3, 4));
+        assert_eq!(a + ab * 2, Point2::new(7, 10));
+        assert_eq!(ab.length(), 5.0);
+        assert_eq!(ab.dot(Vector2::new(1, 0)), 3);
+        assert_eq!(Vector2::new(1, 0).cross(Vector2::new(0, 1)), 1);
+        assert_eq!(
+            Vector3::new(1, 0, 0).cross(Vector3::new(0, 1, 0)),
+            Vector3::new(0, 0, 1)
+        );
+    }
+
+    #[test]
+    fn distances_between_points_of_any_type() {
+        let integer = Point2::new(3_u8, 4_u8);
+        let float = Point2::new(0.0_f32, 0.0_f32);
+        assert_eq!(integer.distance_to(&float), 5.0);
+        assert_eq!(integer.distance_from_origin(), 5.0);
+        assert_eq!(Point3::new(1, 2, 2).distance_from_origin(), 3.0);
+    }
+
+    #[test]
+    fn float_points_round_to_integer_points() {
+        assert_eq!(Point2::new(1.4, -2.6).round(), Some(Point2::new(1, -3)));
+        assert_eq!(Point2::new(f64::NAN, 0.0).round(), None);
+        assert_eq!(Point3::new(1e30, 0.0, 0.0).round(), None);
+        assert_eq!(Point2::new(2_i32, 3).to_f64(), Point2::new(2.0, 3.0));
+    }
+
+    #[test]
+    fn polygon_area_and_containment() {
+        // an L shape, a 2x2 square without its top right 1x1 corner
+        let l_shape = Polygon::new(vec![
+            Point2::new(0, 0),
+            Point2::new(2, 0),
+            Point2::new(2, 1),
+            Point2::new(1, 1),
+            Point2::new(1, 2),
+            Point2::new(0, 2),
+        ])
+        .unwrap();
+        assert_eq!(l_shape.signed_area(), 3.0);
+        assert_eq!(l_shape.perimeter(), 8.0);
+        assert!(l_shape.contains(&Point2::new(0.5, 1.5)));
+        assert!(!l_shape.contains(&Point2::new(1.5, 1.5)));
+        // on an edge and on a vertex count as inside
+        assert!(l_shape.contains(&Point2::new(2, 0)));
+        assert!(l_shape.contains(&Point2::new(1.0, 1.5)));
+        assert!(Polygon::new(vec![Point2::new(0, 0), Point2::new(1, 1)]).is_none());
+    }
+}
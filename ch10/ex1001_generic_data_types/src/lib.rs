@@ -0,0 +1,8 @@
+//! the library part of the generic data types example
+//! the generic functions and structs of main.rs grew into modules of their own
+
+/// min, max, median, quantiles, mean, variance and histograms over generic slices
+pub mod statistics;
+
+/// points, vectors and polygons over any number type
+pub mod geometry;
@@ -1,8 +1,9 @@
-/// continuing with the largest function example from the previous section
-/// we've now two functions that both find the largest value in a slice
-/// one takes a reference to a list of i32s, the other one to a list of chars
+//! continuing with the largest function example from the previous section
+//! we've now two functions that both find the largest value in a slice
+//! one takes a reference to a list of i32s, the other one to a list of chars
 
-use std::cmp::PartialOrd;
+use ex1001_generic_data_types::geometry::{Point2, Polygon};
+use ex1001_generic_data_types::statistics;
 
 /// defining a generic largest function
 /// define a generic function, place type name declarations inside angle brackets <>
@@ -19,16 +20,13 @@ fn _generic_fn<T>(_list: &[T]) -> &T {
 /// to enable comparisons the standard library has the std::cmp::PartialOrd trait 
 /// that we need to implement on these types
 /// we restrict the types valid for T to only those that implement PartialOrd
-fn find_largest<T: PartialOrd>(list: &[T]) -> &T {
-    let mut largest = &list[0];
-
-    for item in list {
-        if item > largest {
-            largest = item;
-        }
-    }
-
-    largest
+///
+/// the first version indexed &list[0] and panicked on an empty slice
+/// the generic largest function now is statistics::max in the library (src/statistics.rs)
+/// it returns an Option, None for an empty slice, next to min, median, mean and a histogram
+/// and an error for values that have no order, PartialOrd allows those
+fn find_largest<T: PartialOrd>(list: &[T]) -> Result<Option<&T>, statistics::Incomparable> {
+    statistics::max(list)
 }
 
 /// Generic Types in Struct Definitions
@@ -84,21 +82,21 @@ impl<X1, Y1> Point99<X1, Y1> {
     }
 }
 
-/// Generic Types in Enum Definitions
-/// we can define enums to hold generic data types in their variants
-/// an example for this is the Option<T> enum comes with the standard library
-/// the Option<T> enum is generic over type T and has two variants
-/// Some which holds one value of type T
-/// and a None that doesn’t hold any value
-/// we can use this abstraction no matter what the type of the optional value is
+// Generic Types in Enum Definitions
+// we can define enums to hold generic data types in their variants
+// an example for this is the Option<T> enum comes with the standard library
+// the Option<T> enum is generic over type T and has two variants
+// Some which holds one value of type T
+// and a None that doesn’t hold any value
+// we can use this abstraction no matter what the type of the optional value is
 /* enum Option<T> {
     Some(T),
     None,
 } */
-/// an example for Enums with multiple generic types is the Result<T, E> enum
-/// the Result enum is generic over two types, T and E, and has two variants: 
-/// Ok, which holds a value of type T
-/// and Err which holds a value of type E
+// an example for Enums with multiple generic types is the Result<T, E> enum
+// the Result enum is generic over two types, T and E, and has two variants: 
+// Ok, which holds a value of type T
+// and Err which holds a value of type E
 /* enum Result<T, E> {
     Ok(T),
     Err(E),
@@ -106,12 +104,23 @@ impl<X1, Y1> Point99<X1, Y1> {
 
 fn main() {
     let number_list = vec![23, 76, 33, 44, 96, 15, 28];
-    let largest_number = find_largest(&number_list);
-    println!("The largest number in the i32 list is {}.", largest_number);
+    if let Ok(Some(largest_number)) = find_largest(&number_list) {
+        println!("The largest number in the i32 list is {}.", largest_number);
+    }
 
     let char_list = vec!['c', 'h', 'l', 'q', 'p', 'y', 'r'];
-    let largest_char = find_largest(&char_list);
-    println!("The largest char in the char list is {}.", largest_char);
+    if let Ok(Some(largest_char)) = find_largest(&char_list) {
+        println!("The largest char in the char list is {}.", largest_char);
+    }
+
+    let empty: Vec<i32> = Vec::new();
+    println!("The largest number of an empty list is {:?}.", find_largest(&empty));
+    if let Some(summary) = statistics::Summary::of(&number_list) {
+        println!("{:?}", summary);
+    }
+    if let Some(histogram) = statistics::Histogram::new(&number_list, 4) {
+        print!("{}", histogram);
+    }
 
     // initiating a struct with only one generic parameters
     let point = Point1 { x: 9, y: 4 }; // both integer
@@ -133,4 +142,13 @@ fn main() {
 
     println!("p3.x = {}, p3.y = {}", p3.x, p3.y);
 
+    // the geometry module of the library has points for every number type
+    // distances work between points of different types, like Point mixes types
+    let integer_point = Point2::new(9, 4);
+    let float_point = Point2::new(9.4_f32, 4.5);
+    println!("the distance between them is {}", integer_point.distance_to(&float_point));
+    if let Some(triangle) = Polygon::new(vec![Point2::new(0, 0), Point2::new(4, 0), Point2::new(0, 3)]) {
+        println!("the triangle has an area of {} and contains (1, 1): {}", triangle.area(), triangle.contains(&Point2::new(1, 1)));
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,377 @@
+// statistics over slices, grown from the generic find_largest function in main.rs
+// find_largest indexes list[0] and panics on an empty slice
+// the functions here return None instead, there is no largest value of nothing
+//
+// PartialOrd is enough to find the largest or the smallest value
+// but for floats it is only partial: f64::NAN is neither smaller, equal nor larger than anything
+// a NaN would make the result depend on where it is in the slice
+// so every function skips values that can't be compared with themselves
+// and Summary counts how many were skipped
+//
+// other types are partial because two different values may have no order, like the sets {1} and {2}
+// there is no largest of those, so the functions return an Incomparable error when they meet two of them
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+/// two values of the slice that have no order, by their index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Incomparable {
+    pub first: usize,
+    pub second: usize,
+}
+
+impl fmt::Display for Incomparable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the values at {} and {} can't be compared", self.first, self.second)
+    }
+}
+
+impl Error for Incomparable {}
+
+// a value that can be ordered, NaN can't even be compared with itself
+fn is_ordered<T: PartialOrd>(value: &T) -> bool {
+    value.partial_cmp(value).is_some()
+}
+
+// two values with their index, an error if they have no order
+fn compare<T: PartialOrd>(a: (usize, &T), b: (usize, &T)) -> Result<Ordering, Incomparable> {
+    a.1.partial_cmp(b.1).ok_or(Incomparable {
+        first: a.0.min(b.0),
+        second: a.0.max(b.0),
+    })
+}
+
+// the value that the ordering of a new value against the kept one says to keep
+fn extreme<T: PartialOrd>(list: &[T], keep: Ordering) -> Result<Option<(usize, &T)>, Incomparable> {
+    let mut kept: Option<(usize, &T)> = None;
+    for item in list.iter().enumerate().filter(|(_, item)| is_ordered(*item)) {
+        match kept {
+            Some(value) if compare(item, value)? != keep => {}
+            _ => kept = Some(item),
+        }
+    }
+    Ok(kept)
+}
+
+/// the index and the value of the largest value, on a tie the first one
+/// Ok(None) for an empty slice
+pub fn argmax<T: PartialOrd>(list: &[T]) -> Result<Option<(usize, &T)>, Incomparable> {
+    extreme(list, Ordering::Greater)
+}
+
+/// the index and the value of the smallest value, on a tie the first one
+/// Ok(None) for an empty slice
+pub fn argmin<T: PartialOrd>(list: &[T]) -> Result<Option<(usize, &T)>, Incomparable> {
+    extreme(list, Ordering::Less)
+}
+
+/// the largest value, Ok(None) for an empty slice
+pub fn max<T: PartialOrd>(list: &[T]) -> Result<Option<&T>, Incomparable> {
+    Ok(argmax(list)?.map(|(_, value)| value))
+}
+
+/// the smallest value, Ok(None) for an empty slice
+pub fn min<T: PartialOrd>(list: &[T]) -> Result<Option<&T>, Incomparable> {
+    Ok(argmin(list)?.map(|(_, value)| value))
+}
+
+/// the value below which a share q of the values lie, q between 0 and 1
+/// uses the nearest rank, so the result is always one of the values
+/// the values are sorted by sort_checked, which takes O(n log n)
+/// selection would be O(n) but needs a total order before it starts, which can't be checked in O(n)
+/// Ok(None) for an empty slice or a q outside of 0 to 1
+pub fn quantile<T: PartialOrd>(list: &[T], q: f64) -> Result<Option<&T>, Incomparable> {
+    if !(0.0..=1.0).contains(&q) {
+        return Ok(None);
+    }
+    let ordered: Vec<(usize, &T)> = list
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| is_ordered(*item))
+        .collect();
+    if ordered.is_empty() {
+        return Ok(None);
+    }
+    let rank = ((q * ordered.len() as f64).ceil() as usize).clamp(1, ordered.len());
+    let sorted = sort_checked(ordered)?;
+    Ok(Some(sorted[rank - 1].1))
+}
+
+// a merge sort that stops at the first two values without an order
+// every two neighbours of the result were compared, directly or when they were merged,
+// so when it gets through all values are ordered against each other like a total order
+// the sorts of std may panic or give any order when the comparison isn't total
+fn sort_checked<T: PartialOrd>(items: Vec<(usize, &T)>) -> Result<Vec<(usize, &T)>, Incomparable> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let mut left = items;
+    let right = left.split_off(left.len() / 2);
+    let mut left = sort_checked(left)?.into_iter().peekable();
+    let mut right = sort_checked(right)?.into_iter().peekable();
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    while let (Some(&a), Some(&b)) = (left.peek(), right.peek()) {
+        // on a tie the left one goes first, so the sort is stable
+        if compare(b, a)? == Ordering::Less {
+            merged.push(b);
+            right.next();
+        } else {
+            merged.push(a);
+            left.next();
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/// numbers that can be turned into an f64 for averages
+/// the integers of up to 32 bits fit into an f64 exactly
+/// larger integers are rounded, like `as f64` does
+pub trait Number: Copy + PartialOrd {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_number {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+/// the middle value, the mean of the two middle values for an even count
+pub fn median<T: Number>(list: &[T]) -> Option<f64> {
+    let mut ordered: Vec<f64> = list.iter().map(|n| n.to_f64()).filter(|n| !n.is_nan()).collect();
+    let len = ordered.len();
+    if len == 0 {
+        return None;
+    }
+    let (lower, upper, _) = ordered.select_nth_unstable_by(len / 2, f64::total_cmp);
+    let upper = *upper;
+    if len % 2 == 1 {
+        Some(upper)
+    } else {
+        // the other middle value is the largest of the lower half
+        let lower = lower.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Some((lower + upper) / 2.0)
+    }
+}
+
+// mean and variance are calculated in f64, not in the type of the values
+// summing large integers in their own type overflows, an f64 goes up to about 1.8e308
+// the variance takes a second pass over the values, which is more precise than summing squares
+#[derive(Debug, Default, Clone, Copy)]
+struct Moments {
+    count: usize,
+    mean: f64,
+    // the sum of the squared differences from the mean
+    squares: f64,
+}
+
+impl Moments {
+    fn of<T: Number>(list: &[T]) -> Moments {
+        let values = || list.iter().map(|n| n.to_f64()).filter(|n| !n.is_nan());
+        let count = values().count();
+        if count == 0 {
+            return Moments::default();
+        }
+        let mean = values().sum::<f64>() / count as f64;
+        let squares = values().map(|n| (n - mean).powi(2)).sum();
+        Moments { count, mean, squares }
+    }
+}
+
+/// the arithmetic mean, None for an empty slice
+pub fn mean<T: Number>(list: &[T]) -> Option<f64> {
+    let moments = Moments::of(list);
+    (moments.count > 0).then_some(moments.mean)
+}
+
+/// the population variance, None for an empty slice
+pub fn variance<T: Number>(list: &[T]) -> Option<f64> {
+    let moments = Moments::of(list);
+    (moments.count > 0).then(|| moments.squares / moments.count as f64)
+}
+
+/// the sample variance, it needs at least two values
+pub fn sample_variance<T: Number>(list: &[T]) -> Option<f64> {
+    let moments = Moments::of(list);
+    (moments.count > 1).then(|| moments.squares / (moments.count - 1) as f64)
+}
+
+pub fn standard_deviation<T: Number>(list: &[T]) -> Option<f64> {
+    variance(list).map(f64::sqrt)
+}
+
+/// the values counted in bins of the same width between the smallest and the largest value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub min: f64,
+    pub max: f64,
+    pub width: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// None for an empty slice or zero bins
+    /// the largest value goes into the last bin, if all values are equal they all go into the first one
+    pub fn new<T: Number>(list: &[T], bins: usize) -> Option<Histogram> {
+        let values: Vec<f64> = list.iter().map(|n| n.to_f64()).filter(|n| !n.is_nan()).collect();
+        if values.is_empty() || bins == 0 {
+            return None;
+        }
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let width = (max - min) / bins as f64;
+        let mut counts = vec![0; bins];
+        for value in values {
+            let bin = if width > 0.0 {
+                (((value - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            counts[bin] += 1;
+        }
+        Some(Histogram { min, max, width, counts })
+    }
+
+    /// the lower and the upper bound of a bin
+    pub fn bounds(&self, bin: usize) -> (f64, f64) {
+        let lower = self.min + self.width * bin as f64;
+        (lower, lower + self.width)
+    }
+}
+
+impl fmt::Display for Histogram {
+    // one line per bin with a bar of # characters
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (bin, count) in self.counts.iter().enumerate() {
+            let (lower, upper) = self.bounds(bin);
+            writeln!(f, "{:>10.2} .. {:<10.2} {:>5} {}", lower, upper, count, "#".repeat(*count))?;
+        }
+        Ok(())
+    }
+}
+
+/// the statistics of a list of numbers at a glance
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    // the values that were skipped because they are NaN
+    pub nan: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub standard_deviation: f64,
+}
+
+impl Summary {
+    pub fn of<T: Number>(list: &[T]) -> Option<Summary> {
+        let moments = Moments::of(list);
+        if moments.count == 0 {
+            return None;
+        }
+        Some(Summary {
+            count: moments.count,
+            nan: list.len() - moments.count,
+            // numbers without NaN are always ordered
+            min: min(list).ok()??.to_f64(),
+            max: max(list).ok()??.to_f64(),
+            mean: moments.mean,
+            median: median(list)?,
+            standard_deviation: (moments.squares / moments.count as f64).sqrt(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_slices_have_no_largest_value() {
+        let empty: [i32; 0] = [];
+        assert_eq!(max(&empty), Ok(None));
+        assert_eq!(argmin(&empty), Ok(None));
+        assert_eq!(mean(&empty), None);
+        assert_eq!(quantile(&empty, 0.5), Ok(None));
+        assert_eq!(max(&['c', 'y', 'r']), Ok(Some(&'y')));
+    }
+
+    #[test]
+    fn nan_is_skipped_wherever_it_is() {
+        let values = [f64::NAN, 3.0, 1.0, f64::NAN, 2.0];
+        assert_eq!(argmax(&values), Ok(Some((1, &3.0))));
+        assert_eq!(argmin(&values), Ok(Some((2, &1.0))));
+        assert_eq!(median(&values), Some(2.0));
+        let summary = Summary::of(&values).unwrap();
+        assert_eq!((summary.count, summary.nan), (3, 2));
+        assert_eq!(max(&[f64::NAN]), Ok(None));
+    }
+
+    // ordered by inclusion, like sets
+    #[derive(Debug, PartialEq)]
+    struct Set(u8);
+
+    impl PartialOrd for Set {
+        fn partial_cmp(&self, other: &Set) -> Option<Ordering> {
+            match (self.0 & other.0 == self.0, self.0 & other.0 == other.0) {
+                (true, true) => Some(Ordering::Equal),
+                (true, false) => Some(Ordering::Less),
+                (false, true) => Some(Ordering::Greater),
+                (false, false) => None,
+            }
+        }
+    }
+
+    #[test]
+    fn values_without_an_order_are_an_error() {
+        let chain = [Set(0b001), Set(0b111), Set(0b011)];
+        assert_eq!(max(&chain), Ok(Some(&Set(0b111))));
+        assert_eq!(quantile(&chain, 0.5), Ok(Some(&Set(0b011))));
+        let sets = [Set(0b011), Set(0b001), Set(0b100)];
+        let error = Incomparable { first: 0, second: 2 };
+        assert_eq!(argmax(&sets), Err(error));
+        assert_eq!(min(&sets), Err(Incomparable { first: 1, second: 2 }));
+        assert!(quantile(&sets, 1.0).is_err());
+        // 0b011 and 0b101 are both below 0b111, but not ordered against each other
+        let diamond = [Set(0b111), Set(0b011), Set(0b001), Set(0b101)];
+        assert_eq!(quantile(&diamond, 0.5), Err(Incomparable { first: 1, second: 3 }));
+        assert_eq!(error.to_string(), "the values at 0 and 2 can't be compared");
+    }
+
+    #[test]
+    fn quantiles_and_medians_by_selection() {
+        let values = [23, 76, 33, 44, 96, 15, 28];
+        assert_eq!(quantile(&values, 0.0), Ok(Some(&15)));
+        assert_eq!(quantile(&values, 0.5), Ok(Some(&33)));
+        assert_eq!(quantile(&values, 1.0), Ok(Some(&96)));
+        assert_eq!(quantile(&values, 1.5), Ok(None));
+        assert_eq!(median(&[4, 1, 3, 2]), Some(2.5));
+    }
+
+    #[test]
+    fn large_integers_do_not_overflow_the_mean() {
+        let values = [i64::MAX, i64::MAX, i64::MAX];
+        assert_eq!(mean(&values), Some(i64::MAX as f64));
+        assert_eq!(variance(&[2, 4, 4, 4, 5, 5, 7, 9]), Some(4.0));
+        assert_eq!(sample_variance(&[1]), None);
+    }
+
+    #[test]
+    fn histogram_bins_cover_the_range() {
+        let histogram = Histogram::new(&[1, 2, 2, 3, 9, 10], 3).unwrap();
+        assert_eq!(histogram.counts, [4, 0, 2]);
+        assert_eq!(histogram.bounds(1), (4.0, 7.0));
+        assert_eq!(Histogram::new(&[5, 5], 4).unwrap().counts, [2, 0, 0, 0]);
+    }
+}
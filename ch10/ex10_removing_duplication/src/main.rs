@@ -1,26 +1,33 @@
-/// Removing Duplication by Extracting a Function
-/// example: removing duplication of code without involving generic types
-/// the goal here is to extract a function that replaces specific values with a placeholder
-/// that represents multiple values
+//! Removing Duplication by Extracting a Function
+//! example: removing duplication of code without involving generic types
+//! the goal here is to extract a function that replaces specific values with a placeholder
+//! that represents multiple values
 
 fn main() {
     // short program the finds the largest number in a list
     let number_list = vec![23, 76, 33, 44, 96, 15, 28];
 
-    let largest_number = find_largest(&number_list);
-    println!("The largest number in the list (1) is {}.", largest_number);
+    if let Some(largest_number) = find_largest(&number_list) {
+        println!("The largest number in the list (1) is {}.", largest_number);
+    }
 
     // duplicating code to find number in a second list
     let number_list = vec![88, 129, 654, 729, 12, 59, 109];
 
-    let largest_number = find_largest(&number_list);
-    println!("The largest number in the list (2) is {}.", largest_number);
+    if let Some(largest_number) = find_largest(&number_list) {
+        println!("The largest number in the list (2) is {}.", largest_number);
+    }
+
+    // an empty list has no largest number
+    println!("The largest number in an empty list is {:?}.", find_largest(&[]));
 
 }
 
 // parameter list represents any concrete slice of i32 values
-fn find_largest(list: &[i32]) -> &i32 {
-    let mut largest_number = &list[0];
+// an empty slice has no first number to start with, so the result is an Option
+// the generic version with more statistics is in ch10/ex1001_generic_data_types/src/statistics.rs
+fn find_largest(list: &[i32]) -> Option<&i32> {
+    let mut largest_number = list.first()?;
 
     for num in list {
         if num > largest_number {
@@ -28,5 +35,5 @@ fn find_largest(list: &[i32]) -> &i32 {
         }
     }
 
-    largest_number
+    Some(largest_number)
 }
\ No newline at end of file
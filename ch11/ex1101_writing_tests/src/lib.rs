@@ -14,9 +14,23 @@ struct Rectangle {
 }
 
 impl Rectangle {
+    // u64 because width * height of two u32 values can overflow a u32
+    fn area(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
     fn can_hold(&self, other: &Rectangle) -> bool {
         self.width > other.width && self.height > other.height
     }
+
+    // the other rectangle may also be turned by 90° to fit
+    fn can_hold_rotated(&self, other: &Rectangle) -> bool {
+        let rotated = Rectangle {
+            width: other.height,
+            height: other.width,
+        };
+        self.can_hold(other) || self.can_hold(&rotated)
+    }
 }
 
 #[cfg(test)]
@@ -64,6 +78,30 @@ mod tests {
         // we need to negate that result before we pass it to the assert! macro
         assert!(!smaller.can_hold(&larger));
     }
+
+    #[test]
+    fn rotated_rectangle_fits() {
+        let door = Rectangle {
+            width: 10,
+            height: 30,
+        };
+        let plank = Rectangle {
+            width: 25,
+            height: 5,
+        };
+        assert!(!door.can_hold(&plank));
+        assert!(door.can_hold_rotated(&plank));
+        assert!(!plank.can_hold_rotated(&door));
+    }
+
+    #[test]
+    fn area_does_not_overflow() {
+        let huge = Rectangle {
+            width: 70_000,
+            height: 70_000,
+        };
+        assert_eq!(huge.area(), 4_900_000_000);
+    }
     
     // testing equality with the assert_eq! and assert_ne! macros
     // verifying functionality by testing for equality 
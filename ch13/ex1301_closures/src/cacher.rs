@@ -0,0 +1,397 @@
+// memoization of an expensive closure, like expensive_closure in main.rs
+// the Cacher holds the closure and the values it already calculated
+// the closure only gets called for an argument the first time it is asked for
+//
+// a Cacher can hold a limited number of values, the least recently used one makes room for a new one
+// and values can expire after a time to live, then the closure is called again
+// SharedCacher does the same for many threads, threads that ask for the same argument
+// wait for the one thread that calculates it instead of calculating it again
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// how well a cache works
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    // the value was in the cache
+    pub hits: u64,
+    // the closure had to be called
+    pub misses: u64,
+    // values dropped to stay within the capacity
+    pub evictions: u64,
+    // values dropped because they were older than the time to live
+    pub expirations: u64,
+}
+
+impl Stats {
+    /// the share of lookups that were hits, 0 before the first lookup
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+// the values of a cache in least recently used order
+// both cachers keep their values in here, the Cacher the values themselves
+// and the SharedCacher a cell that one thread fills while the others wait
+#[derive(Debug)]
+struct Entries<K, T> {
+    values: HashMap<K, Entry<T>>,
+    // every lookup gets the next tick, the smallest tick is the least recently used key
+    by_use: BTreeMap<u64, K>,
+    tick: u64,
+    capacity: Option<usize>,
+    ttl: Option<Duration>,
+    stats: Stats,
+}
+
+#[derive(Debug)]
+struct Entry<T> {
+    value: T,
+    last_used: u64,
+    stored_at: Instant,
+}
+
+impl<K: Eq + Hash + Clone, T: Clone> Entries<K, T> {
+    fn new() -> Entries<K, T> {
+        Entries {
+            values: HashMap::new(),
+            by_use: BTreeMap::new(),
+            tick: 0,
+            capacity: None,
+            ttl: None,
+            stats: Stats::default(),
+        }
+    }
+
+    fn expired(&self, entry: &Entry<T>, now: Instant) -> bool {
+        self.ttl
+            .is_some_and(|ttl| now.saturating_duration_since(entry.stored_at) >= ttl)
+    }
+
+    // there is a value for the key that didn't expire, without counting as a use
+    fn contains(&self, key: &K, now: Instant) -> bool {
+        self.values.get(key).is_some_and(|entry| !self.expired(entry, now))
+    }
+
+    // the value stored for the key, unless it expired
+    fn get(&mut self, key: &K, now: Instant) -> Option<T> {
+        let entry = self.values.get(key)?;
+        if self.expired(entry, now) {
+            self.remove(key);
+            self.stats.expirations += 1;
+            return None;
+        }
+        self.tick += 1;
+        let entry = self.values.get_mut(key)?;
+        self.by_use.remove(&entry.last_used);
+        entry.last_used = self.tick;
+        self.by_use.insert(self.tick, key.clone());
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, key: K, value: T, now: Instant) {
+        self.remove(&key);
+        if let Some(capacity) = self.capacity {
+            while self.values.len() >= capacity.max(1) {
+                let Some((_, oldest)) = self.by_use.pop_first() else {
+                    break;
+                };
+                self.values.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+        }
+        self.tick += 1;
+        self.by_use.insert(self.tick, key.clone());
+        let entry = Entry {
+            value,
+            last_used: self.tick,
+            stored_at: now,
+        };
+        self.values.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &K) -> Option<T> {
+        let entry = self.values.remove(key)?;
+        self.by_use.remove(&entry.last_used);
+        Some(entry.value)
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+        self.by_use.clear();
+    }
+}
+
+/// memoizes a closure that takes one argument
+/// ```
+/// use ex1301_closures::cacher::Cacher;
+///
+/// let mut squares = Cacher::new(|x: u64| x * x).with_capacity(100);
+/// assert_eq!(squares.value(12), 144);
+/// assert_eq!(squares.value(12), 144);
+/// assert_eq!(squares.stats().hits, 1);
+/// ```
+pub struct Cacher<F, K, V>
+where
+    F: Fn(K) -> V,
+{
+    calculation: F,
+    entries: Entries<K, V>,
+}
+
+impl<F, K, V> Cacher<F, K, V>
+where
+    F: Fn(K) -> V,
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    /// a cache without a limit that keeps every value forever
+    pub fn new(calculation: F) -> Cacher<F, K, V> {
+        Cacher {
+            calculation,
+            entries: Entries::new(),
+        }
+    }
+
+    /// keeps at most capacity values, at least one
+    pub fn with_capacity(mut self, capacity: usize) -> Cacher<F, K, V> {
+        self.entries.capacity = Some(capacity);
+        self
+    }
+
+    /// values older than ttl are calculated again
+    pub fn with_ttl(mut self, ttl: Duration) -> Cacher<F, K, V> {
+        self.entries.ttl = Some(ttl);
+        self
+    }
+
+    /// the value of the closure for arg, calculated at most once while it stays cached
+    pub fn value(&mut self, arg: K) -> V {
+        self.value_at(arg, Instant::now())
+    }
+
+    /// like value, with the time passed in so expiry can be tested without sleeping
+    pub fn value_at(&mut self, arg: K, now: Instant) -> V {
+        if let Some(value) = self.entries.get(&arg, now) {
+            self.entries.stats.hits += 1;
+            return value;
+        }
+        self.entries.stats.misses += 1;
+        let value = (self.calculation)(arg.clone());
+        self.entries.insert(arg, value.clone(), now);
+        value
+    }
+
+    /// there is a value for arg that didn't expire yet
+    pub fn contains(&self, arg: &K) -> bool {
+        self.contains_at(arg, Instant::now())
+    }
+
+    /// like contains, at the given time
+    pub fn contains_at(&self, arg: &K, now: Instant) -> bool {
+        self.entries.contains(arg, now)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.values.is_empty()
+    }
+
+    /// forgets the value for arg so the next lookup calls the closure again
+    pub fn invalidate(&mut self, arg: &K) -> Option<V> {
+        self.entries.remove(arg)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.entries.stats
+    }
+}
+
+/// a Cacher that many threads can use at the same time, for example behind an Arc
+/// the closure is called once per argument even if many threads ask for it at once
+pub struct SharedCacher<F, K, V>
+where
+    F: Fn(K) -> V,
+{
+    calculation: F,
+    // the lock is only held to find the cell, never while the closure runs
+    entries: Mutex<Entries<K, Arc<OnceLock<V>>>>,
+}
+
+impl<F, K, V> SharedCacher<F, K, V>
+where
+    F: Fn(K) -> V,
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new(calculation: F) -> SharedCacher<F, K, V> {
+        SharedCacher {
+            calculation,
+            entries: Mutex::new(Entries::new()),
+        }
+    }
+
+    pub fn with_capacity(mut self, capacity: usize) -> SharedCacher<F, K, V> {
+        self.entries.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()).capacity = Some(capacity);
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> SharedCacher<F, K, V> {
+        self.entries.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()).ttl = Some(ttl);
+        self
+    }
+
+    pub fn value(&self, arg: K) -> V {
+        self.value_at(arg, Instant::now())
+    }
+
+    pub fn value_at(&self, arg: K, now: Instant) -> V {
+        let cell = {
+            let mut entries = self.lock_entries();
+            match entries.get(&arg, now) {
+                Some(cell) => cell,
+                None => {
+                    let cell = Arc::new(OnceLock::new());
+                    entries.insert(arg.clone(), Arc::clone(&cell), now);
+                    cell
+                }
+            }
+        };
+        // the first thread to get here runs the closure, the others block until it is done
+        // a thread that finds the value already there counts as a hit, even if it had to wait
+        let mut calculated = false;
+        let value = cell
+            .get_or_init(|| {
+                calculated = true;
+                (self.calculation)(arg)
+            })
+            .clone();
+        let mut entries = self.lock_entries();
+        if calculated {
+            entries.stats.misses += 1;
+        } else {
+            entries.stats.hits += 1;
+        }
+        value
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock_entries().values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock_entries().values.is_empty()
+    }
+
+    pub fn invalidate(&self, arg: &K) {
+        self.lock_entries().remove(arg);
+    }
+
+    pub fn clear(&self) {
+        self.lock_entries().clear();
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.lock_entries().stats
+    }
+
+    // a panic in the closure happens outside the lock, so the entries are never left half updated
+    fn lock_entries(&self) -> std::sync::MutexGuard<'_, Entries<K, Arc<OnceLock<V>>>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::thread;
+
+    #[test]
+    fn the_closure_runs_once_per_argument() {
+        let calls = Cell::new(0);
+        let mut cacher = Cacher::new(|x: u32| {
+            calls.set(calls.get() + 1);
+            x + 1
+        });
+        assert_eq!(cacher.value(1), 2);
+        assert_eq!(cacher.value(1), 2);
+        assert_eq!(cacher.value(2), 3);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(
+            cacher.stats(),
+            Stats {
+                hits: 1,
+                misses: 2,
+                ..Stats::default()
+            }
+        );
+    }
+
+    #[test]
+    fn the_least_recently_used_value_is_evicted() {
+        let mut cacher = Cacher::new(|s: &str| s.len()).with_capacity(2);
+        cacher.value("a");
+        cacher.value("bb");
+        // using "a" again makes "bb" the least recently used
+        cacher.value("a");
+        cacher.value("ccc");
+        assert!(cacher.contains(&"a"));
+        assert!(!cacher.contains(&"bb"));
+        assert_eq!(cacher.len(), 2);
+        assert_eq!(cacher.stats().evictions, 1);
+    }
+
+    #[test]
+    fn values_expire_after_the_time_to_live() {
+        let calls = Cell::new(0);
+        let mut cacher = Cacher::new(|x: u32| {
+            calls.set(calls.get() + 1);
+            x * 2
+        })
+        .with_ttl(Duration::from_secs(10));
+        let start = Instant::now();
+        cacher.value_at(4, start);
+        cacher.value_at(4, start + Duration::from_secs(9));
+        assert_eq!(calls.get(), 1);
+        // an expired value isn't there anymore even before it is asked for
+        assert!(cacher.contains_at(&4, start + Duration::from_secs(9)));
+        assert!(!cacher.contains_at(&4, start + Duration::from_secs(10)));
+        cacher.value_at(4, start + Duration::from_secs(10));
+        assert_eq!(calls.get(), 2);
+        assert_eq!(cacher.stats().expirations, 1);
+    }
+
+    #[test]
+    fn concurrent_callers_share_one_calculation() {
+        let calls = AtomicU32::new(0);
+        let cacher = SharedCacher::new(|x: u64| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(50));
+            x * x
+        });
+        // scoped threads can borrow the cacher and the counter
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| assert_eq!(cacher.value(7), 49));
+            }
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cacher.stats().misses, 1);
+        assert_eq!(cacher.stats().hits, 7);
+    }
+}
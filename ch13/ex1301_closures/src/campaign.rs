@@ -0,0 +1,307 @@
+// a giveaway campaign hands out the shirts of an inventory to the users who claim them
+// every shirt given away leaves the inventory, so the campaign ends when the stock does
+// users may prefer a color, when that color is out of stock the fallback decides what happens
+// every shirt that is handed out is written into the ledger
+use std::collections::VecDeque;
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::inventory::{Inventory, ShirtColor, Sku};
+
+/// what happens when the preferred color of a user is out of stock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fallback {
+    // the user gets the most stocked color, like Inventory::giveaway does without a preference
+    MostStocked,
+    // the user gets a random shirt, every shirt in stock has the same chance
+    Random,
+    // the user waits until the preferred color is restocked
+    Waitlist,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub fallback: Fallback,
+    // how many shirts one user may get, waiting claims count too
+    pub per_user_limit: u32,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            fallback: Fallback::MostStocked,
+            per_user_limit: 1,
+        }
+    }
+}
+
+/// one shirt that was handed out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grant {
+    // grants are numbered in the order they were handed out, starting with 1
+    pub number: u32,
+    pub user: String,
+    pub sku: Sku,
+    pub preference: Option<ShirtColor>,
+}
+
+impl Grant {
+    /// true if the user got another color than the one they asked for
+    pub fn fell_back(&self) -> bool {
+        self.preference.is_some_and(|color| color != self.sku.color)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Claim {
+    Granted(Grant),
+    // the user is number position in the line for the color
+    Waitlisted { position: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CampaignError {
+    LimitReached { user: String, limit: u32 },
+    OutOfStock,
+}
+
+impl fmt::Display for CampaignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CampaignError::LimitReached { user, limit } => {
+                write!(f, "{} already claimed the limit of {} shirts", user, limit)
+            }
+            CampaignError::OutOfStock => write!(f, "the campaign is out of shirts"),
+        }
+    }
+}
+
+impl std::error::Error for CampaignError {}
+
+#[derive(Debug)]
+pub struct Campaign {
+    inventory: Inventory,
+    rules: Rules,
+    rng: StdRng,
+    ledger: Vec<Grant>,
+    // users waiting for a color, None waits for any color
+    waitlist: VecDeque<(String, Option<ShirtColor>)>,
+}
+
+impl Campaign {
+    /// the seed makes the random fallback reproducible
+    pub fn new(inventory: Inventory, rules: Rules, seed: u64) -> Campaign {
+        Campaign {
+            inventory,
+            rules,
+            rng: StdRng::seed_from_u64(seed),
+            ledger: Vec::new(),
+            waitlist: VecDeque::new(),
+        }
+    }
+
+    pub fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    /// every shirt handed out so far, in order
+    pub fn ledger(&self) -> &[Grant] {
+        &self.ledger
+    }
+
+    /// the shirts one user received
+    pub fn given_to(&self, user: &str) -> Vec<&Grant> {
+        self.ledger.iter().filter(|grant| grant.user == user).collect()
+    }
+
+    pub fn waiting(&self) -> impl Iterator<Item = (&str, Option<ShirtColor>)> {
+        self.waitlist.iter().map(|(user, color)| (user.as_str(), *color))
+    }
+
+    /// a user claims a shirt
+    /// the limit is checked, the shirt is taken from the inventory and written into the ledger
+    /// in a single call, so a failed claim changes nothing
+    pub fn claim(&mut self, user: &str, preference: Option<ShirtColor>) -> Result<Claim, CampaignError> {
+        let claimed = self.ledger.iter().filter(|grant| grant.user == user).count()
+            + self.waitlist.iter().filter(|(waiting, _)| waiting == user).count();
+        if claimed as u32 >= self.rules.per_user_limit {
+            return Err(CampaignError::LimitReached {
+                user: String::from(user),
+                limit: self.rules.per_user_limit,
+            });
+        }
+
+        // the closure only runs if the preferred color isn't in stock
+        let sku = preference
+            .and_then(|color| self.inventory.take(color))
+            .or_else(|| match self.rules.fallback {
+                Fallback::MostStocked => self
+                    .inventory
+                    .most_stocked()
+                    .and_then(|color| self.inventory.take(color)),
+                Fallback::Random => self.take_random(),
+                Fallback::Waitlist if preference.is_none() => self
+                    .inventory
+                    .most_stocked()
+                    .and_then(|color| self.inventory.take(color)),
+                Fallback::Waitlist => None,
+            });
+
+        match sku {
+            Some(sku) => Ok(Claim::Granted(self.record(user, sku, preference))),
+            None if self.rules.fallback == Fallback::Waitlist => {
+                self.waitlist.push_back((String::from(user), preference));
+                let position = self
+                    .waitlist
+                    .iter()
+                    .filter(|(_, color)| *color == preference)
+                    .count();
+                Ok(Claim::Waitlisted { position })
+            }
+            None => Err(CampaignError::OutOfStock),
+        }
+    }
+
+    /// adds shirts to the inventory and serves the waiting users in the order they claimed
+    /// returns the shirts that were handed out to them
+    pub fn restock(&mut self, sku: Sku, quantity: u32) -> Result<Vec<Grant>, crate::inventory::InventoryError> {
+        self.inventory.add_stock(sku, quantity)?;
+        let mut granted = Vec::new();
+        let mut still_waiting = VecDeque::new();
+        while let Some((user, preference)) = self.waitlist.pop_front() {
+            let sku = match preference {
+                Some(color) => self.inventory.take(color),
+                None => self
+                    .inventory
+                    .most_stocked()
+                    .and_then(|color| self.inventory.take(color)),
+            };
+            match sku {
+                Some(sku) => granted.push(self.record(&user, sku, preference)),
+                None => still_waiting.push_back((user, preference)),
+            }
+        }
+        self.waitlist = still_waiting;
+        Ok(granted)
+    }
+
+    // picks one of the shirts in stock, so a SKU with more stock is more likely
+    fn take_random(&mut self) -> Option<Sku> {
+        let total = self.inventory.total();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = self.rng.gen_range(0..total);
+        let code = self.inventory.items().find_map(|(sku, quantity)| {
+            if pick < quantity {
+                Some(sku.code.clone())
+            } else {
+                pick -= quantity;
+                None
+            }
+        })?;
+        self.inventory.give_away(&code).ok()
+    }
+
+    fn record(&mut self, user: &str, sku: Sku, preference: Option<ShirtColor>) -> Grant {
+        let grant = Grant {
+            number: self.ledger.len() as u32 + 1,
+            user: String::from(user),
+            sku,
+            preference,
+        };
+        self.ledger.push(grant.clone());
+        grant
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::ShirtSize;
+
+    fn inventory() -> Inventory {
+        let mut inventory = Inventory::new();
+        inventory.add_stock(Sku::tee(ShirtColor::Red, ShirtSize::M), 1).unwrap();
+        inventory.add_stock(Sku::tee(ShirtColor::Blue, ShirtSize::M), 2).unwrap();
+        inventory
+    }
+
+    #[test]
+    fn every_shirt_given_away_leaves_the_inventory() {
+        let mut campaign = Campaign::new(inventory(), Rules::default(), 1);
+        for user in ["ann", "bob", "cy"] {
+            assert!(matches!(campaign.claim(user, Some(ShirtColor::Red)), Ok(Claim::Granted(_))));
+        }
+        assert_eq!(campaign.inventory().total(), 0);
+        assert_eq!(campaign.claim("dee", None), Err(CampaignError::OutOfStock));
+
+        let ledger = campaign.ledger();
+        assert_eq!(ledger.len(), 3);
+        assert!(!ledger[0].fell_back());
+        assert!(ledger[1].fell_back() && ledger[1].sku.color == ShirtColor::Blue);
+    }
+
+    #[test]
+    fn users_can_not_claim_more_than_the_limit() {
+        let rules = Rules {
+            per_user_limit: 2,
+            ..Rules::default()
+        };
+        let mut campaign = Campaign::new(inventory(), rules, 1);
+        campaign.claim("ann", None).unwrap();
+        campaign.claim("ann", None).unwrap();
+        assert_eq!(
+            campaign.claim("ann", None),
+            Err(CampaignError::LimitReached {
+                user: String::from("ann"),
+                limit: 2
+            })
+        );
+        assert_eq!(campaign.given_to("ann").len(), 2);
+        assert_eq!(campaign.inventory().total(), 1);
+    }
+
+    #[test]
+    fn waiting_users_are_served_when_their_color_is_restocked() {
+        let rules = Rules {
+            fallback: Fallback::Waitlist,
+            ..Rules::default()
+        };
+        let mut campaign = Campaign::new(inventory(), rules, 1);
+        campaign.claim("ann", Some(ShirtColor::Red)).unwrap();
+        assert_eq!(
+            campaign.claim("bob", Some(ShirtColor::Red)),
+            Ok(Claim::Waitlisted { position: 1 })
+        );
+        // waiting counts against the limit
+        assert!(campaign.claim("bob", Some(ShirtColor::Blue)).is_err());
+
+        let granted = campaign
+            .restock(Sku::tee(ShirtColor::Red, ShirtSize::L), 1)
+            .unwrap();
+        assert_eq!(granted.len(), 1);
+        assert_eq!(granted[0].user, "bob");
+        assert_eq!(granted[0].sku.size, ShirtSize::L);
+        assert_eq!(campaign.waiting().count(), 0);
+    }
+
+    #[test]
+    fn the_random_fallback_is_reproducible() {
+        let rules = Rules {
+            fallback: Fallback::Random,
+            per_user_limit: 3,
+        };
+        let picks = |seed| {
+            let mut campaign = Campaign::new(inventory(), rules, seed);
+            for _ in 0..3 {
+                campaign.claim("ann", Some(ShirtColor::Green)).unwrap();
+            }
+            campaign.ledger().to_vec()
+        };
+        assert_eq!(picks(42), picks(42));
+        assert_eq!(picks(42).len(), 3);
+    }
+}
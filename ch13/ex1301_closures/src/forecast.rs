@@ -0,0 +1,255 @@
+// forecasting how many shirts of every color will be given away
+// most_stocked only looks at the stock right now, the forecast looks at the giveaway history
+// of the inventory (Inventory::demand) to see when a color is about to run out
+//
+// the demand of the next periods is estimated with a moving average or with exponential smoothing
+// the reorder point is the stock that lasts until a reorder arrives, plus the safety stock
+// when the projected stock of a color falls below the safety stock within the horizon an alert is raised
+use std::fmt;
+
+use crate::inventory::{Inventory, ShirtColor};
+
+/// how the demand of the next period is estimated from the history
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    // the average of the last window periods
+    MovingAverage { window: usize },
+    // every period pulls the estimate towards its demand by alpha, between 0 and 1
+    // a large alpha follows changes quickly, a small one smooths out noise
+    ExponentialSmoothing { alpha: f64 },
+}
+
+/// why a method can't estimate the demand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForecastError {
+    // NaN can't be clamped between 0 and 1, it would make every estimate NaN
+    InvalidAlpha(f64),
+}
+
+impl fmt::Display for ForecastError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ForecastError::InvalidAlpha(alpha) => write!(f, "alpha is {}, it has to be between 0 and 1", alpha),
+        }
+    }
+}
+
+impl std::error::Error for ForecastError {}
+
+impl Method {
+    /// the expected demand per period, 0 without any history
+    pub fn estimate(&self, demand: &[u32]) -> Result<f64, ForecastError> {
+        Ok(match *self {
+            Method::MovingAverage { window } => {
+                let recent = &demand[demand.len().saturating_sub(window.max(1))..];
+                if recent.is_empty() {
+                    0.0
+                } else {
+                    recent.iter().map(|&d| f64::from(d)).sum::<f64>() / recent.len() as f64
+                }
+            }
+            Method::ExponentialSmoothing { alpha } => {
+                if alpha.is_nan() {
+                    return Err(ForecastError::InvalidAlpha(alpha));
+                }
+                let alpha = alpha.clamp(0.0, 1.0);
+                // the first period is the starting estimate
+                let mut periods = demand.iter().map(|&d| f64::from(d));
+                let first = periods.next().unwrap_or(0.0);
+                periods.fold(first, |estimate, d| alpha * d + (1.0 - alpha) * estimate)
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Policy {
+    // the periods between ordering shirts and having them in stock
+    pub lead_time: u32,
+    // the stock that should always be left for an unexpected rush
+    pub safety_stock: u32,
+    // the number of periods to project
+    pub horizon: u32,
+}
+
+impl Default for Policy {
+    fn default() -> Policy {
+        Policy {
+            lead_time: 2,
+            safety_stock: 5,
+            horizon: 8,
+        }
+    }
+}
+
+/// the projected stock of a color falls below the safety stock
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub color: ShirtColor,
+    // the first period, counted from 1, that ends below the safety stock
+    pub period: u32,
+    pub projected_stock: f64,
+    pub safety_stock: u32,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} falls to {:.1} shirts in period {}, below the safety stock of {}",
+            self.color, self.projected_stock, self.period, self.safety_stock
+        )
+    }
+}
+
+/// the forecast for one color
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    pub color: ShirtColor,
+    pub on_hand: u32,
+    pub demand_per_period: f64,
+    // order more shirts once the stock is at or below this
+    pub reorder_point: u32,
+    // the stock at the end of every period of the horizon, never below 0
+    pub projected: Vec<f64>,
+}
+
+impl Forecast {
+    pub fn needs_reorder(&self) -> bool {
+        self.on_hand <= self.reorder_point
+    }
+
+    pub fn alert(&self, policy: &Policy) -> Option<Alert> {
+        let safety = f64::from(policy.safety_stock);
+        self.projected
+            .iter()
+            .position(|&stock| stock < safety)
+            .map(|index| Alert {
+                color: self.color,
+                period: index as u32 + 1,
+                projected_stock: self.projected[index],
+                safety_stock: policy.safety_stock,
+            })
+    }
+}
+
+/// forecasts every color the inventory has a SKU of, in declaration order
+pub fn forecast(inventory: &Inventory, method: Method, policy: &Policy) -> Result<Vec<Forecast>, ForecastError> {
+    ShirtColor::ALL
+        .iter()
+        .filter(|&&color| inventory.items().any(|(sku, _)| sku.color == color))
+        .map(|&color| {
+            let on_hand = inventory.stock_of(color);
+            let demand_per_period = method.estimate(&inventory.demand(color))?;
+            // the cast saturates at u32::MAX for a huge lead time
+            let lead_time_demand = (demand_per_period * f64::from(policy.lead_time)).ceil() as u32;
+            let projected = (1..=policy.horizon)
+                .map(|period| (f64::from(on_hand) - demand_per_period * f64::from(period)).max(0.0))
+                .collect();
+            Ok(Forecast {
+                color,
+                on_hand,
+                demand_per_period,
+                reorder_point: lead_time_demand.saturating_add(policy.safety_stock),
+                projected,
+            })
+        })
+        .collect()
+}
+
+/// every alert of the forecasts, the color that runs low first comes first
+pub fn alerts(forecasts: &[Forecast], policy: &Policy) -> Vec<Alert> {
+    let mut alerts: Vec<Alert> = forecasts.iter().filter_map(|forecast| forecast.alert(policy)).collect();
+    // sort_by_key is stable, so colors in the same period stay in declaration order
+    alerts.sort_by_key(|alert| alert.period);
+    alerts
+}
+
+/// one line per color and period of the horizon
+pub fn to_csv(forecasts: &[Forecast], policy: &Policy) -> String {
+    let mut csv = String::from("color,period,demand,projected_stock,reorder_point,below_safety_stock\n");
+    for forecast in forecasts {
+        for (index, stock) in forecast.projected.iter().enumerate() {
+            csv.push_str(&format!(
+                "{:?},{},{:.2},{:.2},{},{}\n",
+                forecast.color,
+                index + 1,
+                forecast.demand_per_period,
+                stock,
+                forecast.reorder_point,
+                *stock < f64::from(policy.safety_stock)
+            ));
+        }
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::{ShirtSize, Sku};
+
+    // gives away the shirts of every period and ends it
+    fn with_history(stock: u32, periods: &[u32]) -> Inventory {
+        let mut inventory = Inventory::new();
+        inventory.add_stock(Sku::tee(ShirtColor::Red, ShirtSize::M), stock).unwrap();
+        for &given in periods {
+            for _ in 0..given {
+                inventory.take(ShirtColor::Red).unwrap();
+            }
+            inventory.end_period();
+        }
+        inventory
+    }
+
+    #[test]
+    fn both_methods_estimate_the_demand() {
+        let demand = [2, 4, 6, 8];
+        assert_eq!(Method::MovingAverage { window: 2 }.estimate(&demand), Ok(7.0));
+        assert_eq!(Method::MovingAverage { window: 10 }.estimate(&demand), Ok(5.0));
+        // 2, then 3, 4.5, 6.25
+        assert_eq!(Method::ExponentialSmoothing { alpha: 0.5 }.estimate(&demand), Ok(6.25));
+        assert_eq!(Method::ExponentialSmoothing { alpha: 0.5 }.estimate(&[]), Ok(0.0));
+        assert!(Method::ExponentialSmoothing { alpha: f64::NAN }.estimate(&demand).is_err());
+    }
+
+    #[test]
+    fn the_reorder_point_covers_the_lead_time() {
+        let mut inventory = with_history(40, &[4, 4, 4]);
+        inventory.add_stock(Sku::tee(ShirtColor::Blue, ShirtSize::S), 10).unwrap();
+        let policy = Policy::default();
+        let forecasts = forecast(&inventory, Method::MovingAverage { window: 3 }, &policy).unwrap();
+        let red = &forecasts[0];
+        assert_eq!(red.on_hand, 28);
+        assert_eq!(red.reorder_point, 4 * 2 + 5);
+        assert!(!red.needs_reorder());
+        assert_eq!(red.projected[..3], [24.0, 20.0, 16.0]);
+        // colors that were never given away have no demand
+        assert_eq!(forecasts[1].demand_per_period, 0.0);
+
+        // a reorder point too large to count stays at u32::MAX
+        let endless = Policy {
+            lead_time: u32::MAX,
+            safety_stock: u32::MAX,
+            ..policy
+        };
+        let forecasts = forecast(&inventory, Method::MovingAverage { window: 3 }, &endless).unwrap();
+        assert_eq!(forecasts[0].reorder_point, u32::MAX);
+    }
+
+    #[test]
+    fn alerts_name_the_first_period_below_the_safety_stock() {
+        let inventory = with_history(30, &[5, 5]);
+        let policy = Policy::default();
+        let forecasts = forecast(&inventory, Method::MovingAverage { window: 2 }, &policy).unwrap();
+        let alerts = alerts(&forecasts, &policy);
+        assert_eq!(alerts.len(), 1);
+        // 20 in stock, 5 a period, 0 after period 4 which is the first below 5
+        assert_eq!(alerts[0].period, 4);
+        assert_eq!(alerts[0].projected_stock, 0.0);
+
+        let csv = to_csv(&forecasts, &policy);
+        assert_eq!(csv.lines().count(), 1 + 8);
+        assert!(csv.contains("\nRed,4,5.00,0.00,15,true\n"));
+    }
+}
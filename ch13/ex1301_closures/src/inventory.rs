@@ -0,0 +1,342 @@
+// the inventory of the t-shirt giveaway
+// every kind of shirt is a SKU (stock keeping unit) with a color and a size
+// and the inventory counts how many shirts of every SKU are in stock
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// the available shirt colors
+/// the order of the variants is used to break ties, see Inventory::most_stocked
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum ShirtColor {
+    Red,
+    Blue,
+    Green,
+    Yellow,
+    Black,
+    White,
+}
+
+impl ShirtColor {
+    pub const ALL: [ShirtColor; 6] = [
+        ShirtColor::Red,
+        ShirtColor::Blue,
+        ShirtColor::Green,
+        ShirtColor::Yellow,
+        ShirtColor::Black,
+        ShirtColor::White,
+    ];
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum ShirtSize {
+    XS,
+    S,
+    M,
+    L,
+    XL,
+    XXL,
+}
+
+/// one kind of shirt, identified by its code
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Sku {
+    pub code: String,
+    pub color: ShirtColor,
+    pub size: ShirtSize,
+}
+
+impl Sku {
+    pub fn new(code: &str, color: ShirtColor, size: ShirtSize) -> Sku {
+        Sku {
+            code: String::from(code),
+            color,
+            size,
+        }
+    }
+
+    /// the SKU of the regular tee, with a code like "TEE-RED-M"
+    pub fn tee(color: ShirtColor, size: ShirtSize) -> Sku {
+        let code = format!("TEE-{:?}-{:?}", color, size).to_uppercase();
+        Sku::new(&code, color, size)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum InventoryError {
+    UnknownSku(String),
+    // the code is already used by a SKU with a different color or size
+    SkuMismatch { code: String },
+    NotEnoughStock { code: String, requested: u32, available: u32 },
+    // the inventory can't count more than u32::MAX shirts in total
+    TooMuchStock { code: String, quantity: u32 },
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InventoryError::UnknownSku(code) => write!(f, "there is no SKU {}", code),
+            InventoryError::SkuMismatch { code } => {
+                write!(f, "SKU {} is already stocked with a different color or size", code)
+            }
+            InventoryError::NotEnoughStock {
+                code,
+                requested,
+                available,
+            } => write!(f, "{} of {} requested but only {} in stock", requested, code, available),
+            InventoryError::TooMuchStock { code, quantity } => {
+                write!(f, "{} more of {} would be more shirts than the inventory can count", quantity, code)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+/// defining struct to represent the company’s inventory
+/// the SKUs are kept sorted by code so listing them is always in the same order
+/// every shirt given away is counted per color and period, see src/forecast.rs
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Inventory {
+    stock: BTreeMap<String, (Sku, u32)>,
+    // the shirts given away in every period that ended, oldest first
+    history: Vec<BTreeMap<ShirtColor, u32>>,
+    // the shirts given away in the period that is still going on
+    current: BTreeMap<ShirtColor, u32>,
+}
+
+impl Inventory {
+    pub fn new() -> Inventory {
+        Inventory::default()
+    }
+
+    /// one shirt per entry, all in size M, like the shirts vector of the original example
+    pub fn from_colors(shirts: &[ShirtColor]) -> Inventory {
+        let mut inventory = Inventory::new();
+        for &color in shirts {
+            inventory
+                .add_stock(Sku::tee(color, ShirtSize::M), 1)
+                .expect("tee SKUs always match their code");
+        }
+        inventory
+    }
+
+    /// adds shirts of a SKU, a new SKU is added to the inventory
+    /// the total over all SKUs always fits in a u32, so total() and stock_of() can't overflow
+    pub fn add_stock(&mut self, sku: Sku, quantity: u32) -> Result<(), InventoryError> {
+        if self.total().checked_add(quantity).is_none() {
+            return Err(InventoryError::TooMuchStock { code: sku.code, quantity });
+        }
+        match self.stock.get_mut(&sku.code) {
+            Some((known, _)) if *known != sku => Err(InventoryError::SkuMismatch { code: sku.code }),
+            Some((_, stocked)) => {
+                // can't overflow, the total including this SKU fits
+                *stocked += quantity;
+                Ok(())
+            }
+            None => {
+                self.stock.insert(sku.code.clone(), (sku, quantity));
+                Ok(())
+            }
+        }
+    }
+
+    /// takes shirts of a SKU out of the inventory, all of them or none
+    pub fn remove_stock(&mut self, code: &str, quantity: u32) -> Result<(), InventoryError> {
+        let (_, stocked) = self
+            .stock
+            .get_mut(code)
+            .ok_or_else(|| InventoryError::UnknownSku(String::from(code)))?;
+        if *stocked < quantity {
+            return Err(InventoryError::NotEnoughStock {
+                code: String::from(code),
+                requested: quantity,
+                available: *stocked,
+            });
+        }
+        *stocked -= quantity;
+        Ok(())
+    }
+
+    pub fn sku(&self, code: &str) -> Option<&Sku> {
+        self.stock.get(code).map(|(sku, _)| sku)
+    }
+
+    pub fn quantity(&self, code: &str) -> u32 {
+        self.stock.get(code).map_or(0, |(_, quantity)| *quantity)
+    }
+
+    /// every SKU with its quantity, sorted by code
+    pub fn items(&self) -> impl DoubleEndedIterator<Item = (&Sku, u32)> {
+        self.stock.values().map(|(sku, quantity)| (sku, *quantity))
+    }
+
+    /// never overflows, add_stock keeps the total within a u32
+    pub fn total(&self) -> u32 {
+        self.items().map(|(_, quantity)| quantity).sum()
+    }
+
+    pub fn stock_of(&self, color: ShirtColor) -> u32 {
+        self.items()
+            .filter(|(sku, _)| sku.color == color)
+            .map(|(_, quantity)| quantity)
+            .sum()
+    }
+
+    /// takes one shirt of a color out of the inventory
+    /// it comes from the SKU of that color with the most stock, on a tie the first code
+    pub fn take(&mut self, color: ShirtColor) -> Option<Sku> {
+        let (sku, _) = self
+            .items()
+            .filter(|(sku, quantity)| sku.color == color && *quantity > 0)
+            .rev()
+            .max_by_key(|&(_, quantity)| quantity)?;
+        let code = sku.code.clone();
+        self.give_away(&code).ok()
+    }
+
+    /// takes one shirt of a SKU out of the inventory and counts it as given away
+    /// remove_stock doesn't count, it is for shirts that leave the stock in other ways
+    pub fn give_away(&mut self, code: &str) -> Result<Sku, InventoryError> {
+        self.remove_stock(code, 1)?;
+        let sku = self.stock[code].0.clone();
+        *self.current.entry(sku.color).or_insert(0) += 1;
+        Ok(sku)
+    }
+
+    /// ends the current period of the giveaway history, like a day or a week
+    pub fn end_period(&mut self) {
+        self.history.push(std::mem::take(&mut self.current));
+    }
+
+    /// the shirts of a color given away in every period that ended, oldest first
+    pub fn demand(&self, color: ShirtColor) -> Vec<u32> {
+        self.history
+            .iter()
+            .map(|period| period.get(&color).copied().unwrap_or(0))
+            .collect()
+    }
+
+    /// the number of periods that ended
+    pub fn periods(&self) -> usize {
+        self.history.len()
+    }
+
+    // giveaway gets the user preference as an Option
+    // it only decides on a color, the shirt stays in stock
+    // a giveaway campaign (src/campaign.rs) takes the shirt out with take
+    pub fn giveaway(&self, user_preference: Option<ShirtColor>) -> Option<ShirtColor> {
+        // if the user doesn't have any preference he gets the "most stocked"
+        // if the Option<T> is the Some variant, or_else returns it unchanged
+        // is it the None variant a closure gets called & returns the value returned by the closure
+        user_preference.or_else(|| self.most_stocked())
+    }
+
+    /// the color with the most shirts in stock, over all sizes
+    /// on a tie the color that comes first in ShirtColor wins, so Red before Blue
+    /// None if nothing is in stock at all
+    pub fn most_stocked(&self) -> Option<ShirtColor> {
+        self.most_stocked_by(|sku| sku.color)
+    }
+
+    /// the most stocked value of any property of the SKUs
+    /// the closure picks the property, for example |sku| sku.size
+    /// on a tie the smallest value wins and keys without stock are never returned
+    pub fn most_stocked_by<K, F>(&self, key: F) -> Option<K>
+    where
+        K: Ord,
+        F: Fn(&Sku) -> K,
+    {
+        let mut totals: BTreeMap<K, u32> = BTreeMap::new();
+        for (sku, quantity) in self.items() {
+            *totals.entry(key(sku)).or_insert(0) += quantity;
+        }
+        // max_by_key returns the last of several equal maximums
+        // walking the sorted keys backwards turns that into the smallest key
+        totals
+            .into_iter()
+            .rev()
+            .filter(|&(_, quantity)| quantity > 0)
+            .max_by_key(|&(_, quantity)| quantity)
+            .map(|(key, _)| key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_stocked_counts_quantities_over_all_sizes() {
+        let mut inventory = Inventory::new();
+        inventory.add_stock(Sku::tee(ShirtColor::Yellow, ShirtSize::S), 2).unwrap();
+        inventory.add_stock(Sku::tee(ShirtColor::Yellow, ShirtSize::XL), 2).unwrap();
+        inventory.add_stock(Sku::tee(ShirtColor::White, ShirtSize::M), 3).unwrap();
+        assert_eq!(inventory.most_stocked(), Some(ShirtColor::Yellow));
+        assert_eq!(inventory.most_stocked_by(|sku| sku.size), Some(ShirtSize::M));
+    }
+
+    #[test]
+    fn ties_go_to_the_color_declared_first() {
+        let inventory = Inventory::from_colors(&[ShirtColor::White, ShirtColor::Blue, ShirtColor::Green]);
+        assert_eq!(inventory.most_stocked(), Some(ShirtColor::Blue));
+    }
+
+    #[test]
+    fn nothing_in_stock_means_no_most_stocked_color() {
+        let mut inventory = Inventory::from_colors(&[ShirtColor::Red]);
+        inventory.remove_stock("TEE-RED-M", 1).unwrap();
+        assert_eq!(inventory.most_stocked(), None);
+        assert_eq!(inventory.giveaway(Some(ShirtColor::Black)), Some(ShirtColor::Black));
+    }
+
+    #[test]
+    fn only_shirts_given_away_count_as_demand() {
+        let mut inventory = Inventory::from_colors(&[ShirtColor::Red, ShirtColor::Red, ShirtColor::Blue]);
+        inventory.take(ShirtColor::Red).unwrap();
+        inventory.end_period();
+        inventory.take(ShirtColor::Red).unwrap();
+        inventory.remove_stock("TEE-BLUE-M", 1).unwrap();
+        inventory.end_period();
+        assert_eq!(inventory.demand(ShirtColor::Red), [1, 1]);
+        assert_eq!(inventory.demand(ShirtColor::Blue), [0, 0]);
+        assert_eq!(inventory.periods(), 2);
+    }
+
+    #[test]
+    fn a_code_always_names_the_same_shirt() {
+        let mut inventory = Inventory::new();
+        inventory.add_stock(Sku::new("LIMITED", ShirtColor::Red, ShirtSize::S), 1).unwrap();
+        assert_eq!(
+            inventory.add_stock(Sku::new("LIMITED", ShirtColor::Blue, ShirtSize::S), 1),
+            Err(InventoryError::SkuMismatch {
+                code: String::from("LIMITED")
+            })
+        );
+        assert_eq!(
+            inventory.remove_stock("LIMITED", 2),
+            Err(InventoryError::NotEnoughStock {
+                code: String::from("LIMITED"),
+                requested: 2,
+                available: 1
+            })
+        );
+    }
+
+    #[test]
+    fn the_stock_never_overflows() {
+        let mut inventory = Inventory::new();
+        inventory.add_stock(Sku::tee(ShirtColor::Red, ShirtSize::M), u32::MAX - 1).unwrap();
+        assert_eq!(
+            inventory.add_stock(Sku::tee(ShirtColor::Red, ShirtSize::M), 2),
+            Err(InventoryError::TooMuchStock {
+                code: String::from("TEE-RED-M"),
+                quantity: 2
+            })
+        );
+        // a new SKU counts towards the same total
+        assert!(inventory.add_stock(Sku::tee(ShirtColor::Blue, ShirtSize::M), 2).is_err());
+        inventory.add_stock(Sku::tee(ShirtColor::Blue, ShirtSize::M), 1).unwrap();
+        assert_eq!(inventory.total(), u32::MAX);
+        assert_eq!(inventory.stock_of(ShirtColor::Red), u32::MAX - 1);
+    }
+}
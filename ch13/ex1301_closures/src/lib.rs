@@ -0,0 +1,24 @@
+//! the library part of the closures example
+//! the t-shirt giveaway grew from a few lines in main.rs into its own modules
+//! main.rs uses them through the public API of this library crate
+
+/// shirts kept by SKU with colors, sizes and real stock quantities
+pub mod inventory;
+
+/// a giveaway campaign that takes the shirts out of the inventory
+pub mod campaign;
+
+/// an inventory that many threads can give shirts away from at once
+pub mod shared;
+
+/// memoizes expensive closures, also across threads
+pub mod cacher;
+
+/// the rectangles of the Fn traits example
+pub mod rectangle;
+
+/// sorting and ranking lists by several keys
+pub mod sorting;
+
+/// demand forecasts and reorder alerts from the giveaway history
+pub mod forecast;
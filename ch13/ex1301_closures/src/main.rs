@@ -8,62 +8,28 @@ use std::{
     time::Duration
 };
 
-/// Capturing the Environment with Closures
-/// example: how to use closures to capture values from the environment for later use
-/// scenario: t-shirt giveaway as a promotion
-/// random person on mailing list gets limited edition t-shirt
-/// people on the mailing list can add their favorite color to their profile
-/// randow winner gets favourite color if set, otherwise t-shirt with largest stock
-
-/// defining enum for the available shirt colors
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum ShirtColor {
-    Red, 
-    Blue,
-}
-
-/// defining struct to represent the company’s inventory
-#[derive(Debug)]
-struct Inventory {
-    shirts: Vec<ShirtColor>,
-}
-
-impl Inventory {
-    // giveaway gets the user preference as an Option
-    fn giveaway(&self, user_preference: Option<ShirtColor>) -> ShirtColor {
-        // if the user doesn't have any preference he gets the "most stocked"
-        // if the Option<T> is the Some variant, unwrap_or_else returns the Some value
-        // is it the None variant a closure gets called & returns the value returned by the closure
-        user_preference.unwrap_or_else(|| self.most_stocked())
-    }
-
-    fn most_stocked(&self) -> ShirtColor {
-        let mut num_red = 0;
-        let mut num_blue = 0;
-
-        for color in &self.shirts {
-            match color {
-                ShirtColor::Blue => num_blue += 1,
-                ShirtColor::Red => num_red += 1,
-            }
-        }
-
-        if num_blue > num_red {
-            ShirtColor::Blue
-        } else {
-            ShirtColor::Red
-        }
-    }
- }
+// Capturing the Environment with Closures
+// example: how to use closures to capture values from the environment for later use
+// scenario: t-shirt giveaway as a promotion
+// random person on mailing list gets limited edition t-shirt
+// people on the mailing list can add their favorite color to their profile
+// randow winner gets favourite color if set, otherwise t-shirt with largest stock
+// the enum for the shirt colors and the struct for the company’s inventory
+// live in the inventory module of the library crate (src/inventory.rs)
+use ex1301_closures::cacher::Cacher;
+use ex1301_closures::campaign::{Campaign, Rules};
+use ex1301_closures::forecast;
+use ex1301_closures::inventory::{Inventory, ShirtColor, ShirtSize, Sku};
+use ex1301_closures::rectangle::Rectangle;
+use ex1301_closures::shared;
+use ex1301_closures::sorting::Comparator;
 
 fn main() {
-    let store = Inventory {
-        shirts: vec![
-            ShirtColor::Blue, ShirtColor::Red, ShirtColor::Blue,
-            ShirtColor::Blue, ShirtColor::Red, ShirtColor::Blue,
-            ShirtColor::Blue, ShirtColor::Red, ShirtColor::Blue,
-        ]
-    };
+    let store = Inventory::from_colors(&[
+        ShirtColor::Blue, ShirtColor::Red, ShirtColor::Blue,
+        ShirtColor::Blue, ShirtColor::Red, ShirtColor::Blue,
+        ShirtColor::Blue, ShirtColor::Red, ShirtColor::Blue,
+    ]);
     println!("{:?}", store);
 
     let user_preference1 = Some(ShirtColor::Red);
@@ -78,6 +44,38 @@ fn main() {
     let giveaway3 = store.giveaway(user_preference3);
     println!("The user (3) with preference {:?} gets {:?}", user_preference3, giveaway3);
 
+    // the inventory counts real quantities per SKU, in any color and size
+    let mut store = Inventory::new();
+    store.add_stock(Sku::tee(ShirtColor::Green, ShirtSize::L), 4).unwrap();
+    store.add_stock(Sku::tee(ShirtColor::Black, ShirtSize::M), 3).unwrap();
+    store.add_stock(Sku::tee(ShirtColor::Black, ShirtSize::L), 1).unwrap();
+    // Green and Black both have 4 shirts, the tie goes to the color declared first
+    println!("The most stocked color is {:?}", store.most_stocked());
+    // a closure picks the property to count by
+    println!("The most stocked size is {:?}", store.most_stocked_by(|sku| sku.size));
+    // an empty inventory has no most stocked color instead of defaulting to Red
+    println!("An empty store gives away {:?}", Inventory::new().giveaway(None));
+
+    // giveaway only picks a color, a campaign actually hands out the shirts
+    // so the stock goes down with every claim
+    let mut campaign = Campaign::new(store, Rules::default(), 42);
+    let claim = campaign.claim("user (4)", Some(ShirtColor::Red));
+    println!("The user (4) with preference Some(Red) gets {:?}", claim);
+    println!("{} shirts are left after the campaign started", campaign.inventory().total());
+
+    // the giveaway history of the inventory tells when a color is about to run out
+    let mut history = campaign.inventory().clone();
+    history.end_period();
+    let policy = forecast::Policy::default();
+    match forecast::forecast(&history, forecast::Method::ExponentialSmoothing { alpha: 0.5 }, &policy) {
+        Ok(forecasts) => {
+            for alert in forecast::alerts(&forecasts, &policy) {
+                println!("{}", alert);
+            }
+        }
+        Err(error) => println!("{}", error),
+    }
+
     // another closure example
     // closures are relevant only within a narrow context 
     // therefore the compiler can infer the types of the parameters and the return type
@@ -87,6 +85,15 @@ fn main() {
         thread::sleep(Duration::from_secs(2));
         num
     };
+    // the Cacher calls the closure only the first time a value is asked for
+    // the demo caches a cheap closure so running it doesn't sleep
+    let mut cached_result = Cacher::new(|num: u32| -> u32 {
+        println!("calculating...");
+        num
+    })
+    .with_capacity(16);
+    println!("{} and again {}", cached_result.value(2), cached_result.value(2));
+    println!("{:?}", cached_result.stats());
 
     // similarities between closure and function syntax:
     /* 
@@ -133,16 +140,11 @@ fn main() {
         .join()
         .unwrap();
 
-    // Moving captured values out of closures and the Fn traits
-    let mut list = [
-        Rectangle { width: 10, height: 1 },
-        Rectangle { width: 3, height: 5 },
-        Rectangle { width: 7, height: 12 },
-    ];
+    // the same move lets many threads give shirts away from one shared inventory
+    // each thread gets its own Arc and the stock is never oversold
+    let report = shared::stress_test(&Inventory::from_colors(&ShirtColor::ALL), 8, 2);
+    println!("{:?}, invariant holds: {}", report, report.invariant_holds());
 
-    list.sort_by_key(|r| r.width);
-    println!("{:#?}", list);
-    
     // example for moving captured values out of closures and the Fn traits
     // described below
     let mut list = [
@@ -157,31 +159,35 @@ fn main() {
     list.sort_by_key(|r| r.width);
     println!("{:#?}", list);
 
+    // sorting by more than one key, every key is a closure like the one above
+    // by width, then the highest first, then by area
+    let by_size = Comparator::new()
+        .then(|r: &Rectangle| r.width)
+        .then_desc(|r: &Rectangle| r.height)
+        .then(|r: &Rectangle| r.area());
+    by_size.sort(&mut list);
+    println!("{:?}", list);
+    for ranked in Comparator::new().then_desc(|r: &Rectangle| r.area()).rank(&list) {
+        println!("{}. {:?}", ranked.rank, ranked.item);
+    }
 }
 
-/// Moving captured values out of closures and the Fn traits
-/// a closure body can
-/// -> move a captured value out of the closure
-/// -> mutate a captured value
-/// -> neither move nor mutate a captured value
-/// -> capture nothing from the environment at all
-/// 
-/// depending on the way a closure captures and handles values from the environment 
-/// it can implement different traits
-/// (1) FnOnce  -> applies to closures that can be called once
-/// all closures implement at least this trait
-/// a closure that moves captured values out of its body will only implement FnOnce
-/// (2) FnMut -> applies to closures that don’t move captured values out of their body
-/// this closures might mutate the captured values
-/// can be called more than once
-/// (3) Fn -> applies to closures that don’t move captured values out of their body 
-/// AND that don’t mutate captured values
-/// as well as closures that capture nothing from their environment
-/// can be called more than once without mutating their environment
-
-#[derive(Debug)]
-#[allow(dead_code)]
-struct Rectangle {
-    width: u32,
-    height: u32,
-}
+// Moving captured values out of closures and the Fn traits (Rectangle lives in src/rectangle.rs)
+// a closure body can
+// -> move a captured value out of the closure
+// -> mutate a captured value
+// -> neither move nor mutate a captured value
+// -> capture nothing from the environment at all
+// 
+// depending on the way a closure captures and handles values from the environment 
+// it can implement different traits
+// (1) FnOnce  -> applies to closures that can be called once
+// all closures implement at least this trait
+// a closure that moves captured values out of its body will only implement FnOnce
+// (2) FnMut -> applies to closures that don’t move captured values out of their body
+// this closures might mutate the captured values
+// can be called more than once
+// (3) Fn -> applies to closures that don’t move captured values out of their body 
+// AND that don’t mutate captured values
+// as well as closures that capture nothing from their environment
+// can be called more than once without mutating their environment
@@ -0,0 +1,310 @@
+// an inventory that many threads can give shirts away from at the same time
+// it is shared between the threads behind an Arc, just like the list moved into the thread in main.rs
+//
+// the stock of every SKU is an atomic counter
+// taking a shirt is a compare-and-swap that only succeeds while the counter is above zero
+// so two threads can never both take the last shirt and the stock is never oversold
+// the map of SKUs only needs a write lock when a new SKU is added
+//
+// like Inventory the shared inventory never holds more than u32::MAX shirts in total
+// restocking reserves room in the total first and only then adds to the SKU
+// so the stock of all SKUs together never goes past the total
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+use crate::inventory::{Inventory, InventoryError, ShirtColor, Sku};
+
+#[derive(Debug)]
+struct Slot {
+    sku: Sku,
+    stock: AtomicU32,
+}
+
+impl Slot {
+    // decrements the stock unless it already is zero
+    fn take_one(&self) -> bool {
+        self.stock
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |stock| stock.checked_sub(1))
+            .is_ok()
+    }
+
+    // increments the stock unless it would overflow
+    fn add(&self, quantity: u32) -> bool {
+        self.stock
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |stock| stock.checked_add(quantity))
+            .is_ok()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SharedInventory {
+    slots: RwLock<BTreeMap<String, Arc<Slot>>>,
+    // the shirts in stock plus the ones being restocked right now
+    reserved: AtomicU32,
+    given_out: AtomicU64,
+}
+
+impl SharedInventory {
+    pub fn new(inventory: &Inventory) -> SharedInventory {
+        let slots = inventory
+            .items()
+            .map(|(sku, quantity)| {
+                let slot = Slot {
+                    sku: sku.clone(),
+                    stock: AtomicU32::new(quantity),
+                };
+                (sku.code.clone(), Arc::new(slot))
+            })
+            .collect();
+        SharedInventory {
+            slots: RwLock::new(slots),
+            reserved: AtomicU32::new(inventory.total()),
+            given_out: AtomicU64::new(0),
+        }
+    }
+
+    /// gives away one shirt of the preferred color
+    /// without a preference, or if that color is gone, one of the most stocked color
+    /// None only when the whole inventory is empty
+    pub fn giveaway(&self, preference: Option<ShirtColor>) -> Option<Sku> {
+        if let Some(sku) = preference.and_then(|color| self.take(color)) {
+            return Some(sku);
+        }
+        // other threads may empty the most stocked color between looking and taking
+        // so we look again until we get a shirt or everything is gone
+        loop {
+            let color = self.snapshot().most_stocked()?;
+            if let Some(sku) = self.take(color) {
+                return Some(sku);
+            }
+        }
+    }
+
+    /// takes one shirt of a color, from the SKU with the most stock
+    pub fn take(&self, color: ShirtColor) -> Option<Sku> {
+        let mut candidates: Vec<Arc<Slot>> = self
+            .read_slots()
+            .values()
+            .filter(|slot| slot.sku.color == color)
+            .cloned()
+            .collect();
+        candidates.sort_by_key(|slot| std::cmp::Reverse(slot.stock.load(Ordering::Acquire)));
+        // if another thread takes the last shirt of a SKU first we move on to the next one
+        candidates.into_iter().find(|slot| slot.take_one()).map(|slot| {
+            self.reserved.fetch_sub(1, Ordering::AcqRel);
+            self.given_out.fetch_add(1, Ordering::AcqRel);
+            slot.sku.clone()
+        })
+    }
+
+    /// adds shirts while other threads keep giving shirts away
+    /// fails without changing anything if the total stock wouldn't fit in a u32
+    pub fn restock(&self, sku: Sku, quantity: u32) -> Result<(), InventoryError> {
+        let slot = self.slot_for(&sku)?;
+        let too_much = || InventoryError::TooMuchStock {
+            code: sku.code.clone(),
+            quantity,
+        };
+        self.reserved
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |reserved| reserved.checked_add(quantity))
+            .map_err(|_| too_much())?;
+        // the reservation leaves room for the shirts so this only fails if the counters went wrong
+        if !slot.add(quantity) {
+            self.reserved.fetch_sub(quantity, Ordering::AcqRel);
+            return Err(too_much());
+        }
+        Ok(())
+    }
+
+    // the slot of a SKU, added with no stock if the SKU is new
+    fn slot_for(&self, sku: &Sku) -> Result<Arc<Slot>, InventoryError> {
+        // the read lock has to be released before the write lock is taken
+        let known = self.read_slots().get(&sku.code).cloned();
+        let slot = match known {
+            Some(slot) => slot,
+            None => {
+                let mut slots = self.slots.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+                // another thread may have added the SKU while we waited for the write lock
+                let slot = slots.entry(sku.code.clone()).or_insert_with(|| {
+                    Arc::new(Slot {
+                        sku: sku.clone(),
+                        stock: AtomicU32::new(0),
+                    })
+                });
+                Arc::clone(slot)
+            }
+        };
+        if slot.sku != *sku {
+            return Err(InventoryError::SkuMismatch { code: sku.code.clone() });
+        }
+        Ok(slot)
+    }
+
+    /// can't overflow, the stock of all SKUs never goes past the reserved total
+    pub fn total(&self) -> u32 {
+        self.read_slots()
+            .values()
+            .map(|slot| slot.stock.load(Ordering::Acquire))
+            .sum()
+    }
+
+    pub fn given_out(&self) -> u64 {
+        self.given_out.load(Ordering::Acquire)
+    }
+
+    /// a copy of the current stock, other threads may change it right after
+    pub fn snapshot(&self) -> Inventory {
+        let mut inventory = Inventory::new();
+        for slot in self.read_slots().values() {
+            // the SKUs are read one after the other while other threads move shirts around
+            // so near u32::MAX the copy is capped to what an Inventory can count
+            let quantity = slot.stock.load(Ordering::Acquire).min(u32::MAX - inventory.total());
+            inventory
+                .add_stock(slot.sku.clone(), quantity)
+                .expect("every code is stocked once and the quantity fits");
+        }
+        inventory
+    }
+
+    // a thread that panicked while holding the lock can't have left the map half updated
+    // because it is only ever changed by a single insert, so the poison is ignored
+    fn read_slots(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, Arc<Slot>>> {
+        self.slots.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// the outcome of a stress test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StressReport {
+    pub initial_stock: u64,
+    // the shirts the threads report they got
+    pub given_out: u64,
+    // the shirts the shared inventory counted as given out
+    pub counted_out: u64,
+    pub remaining: u64,
+    // the claims that came back empty because everything was gone
+    pub turned_away: u64,
+}
+
+impl StressReport {
+    /// nothing was oversold, nothing got lost and the inventory counted every shirt the threads got
+    pub fn invariant_holds(&self) -> bool {
+        self.given_out + self.remaining == self.initial_stock && self.given_out == self.counted_out
+    }
+}
+
+/// lets many threads claim shirts from one shared inventory at the same time
+/// every thread cycles through the colors as its preferences, with no preference in between
+/// afterwards the shirts the threads got plus the remaining stock must equal the initial stock
+pub fn stress_test(inventory: &Inventory, threads: usize, claims_per_thread: usize) -> StressReport {
+    let shared = Arc::new(SharedInventory::new(inventory));
+    let initial_stock = u64::from(shared.total());
+
+    let handles: Vec<_> = (0..threads)
+        .map(|thread_number| {
+            let shared = Arc::clone(&shared);
+            // the closure takes ownership of its own Arc with move
+            thread::spawn(move || {
+                let mut got = 0_u64;
+                let mut turned_away = 0_u64;
+                for claim in 0..claims_per_thread {
+                    let index = (thread_number + claim) % (ShirtColor::ALL.len() + 1);
+                    let preference = ShirtColor::ALL.get(index).copied();
+                    match shared.giveaway(preference) {
+                        Some(_) => got += 1,
+                        None => turned_away += 1,
+                    }
+                }
+                (got, turned_away)
+            })
+        })
+        .collect();
+
+    let mut report = StressReport {
+        initial_stock,
+        given_out: 0,
+        counted_out: 0,
+        remaining: 0,
+        turned_away: 0,
+    };
+    for handle in handles {
+        let (got, turned_away) = handle.join().expect("a giveaway thread panicked");
+        report.given_out += got;
+        report.turned_away += turned_away;
+    }
+    report.remaining = u64::from(shared.total());
+    report.counted_out = shared.given_out();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::ShirtSize;
+
+    fn inventory() -> Inventory {
+        let mut inventory = Inventory::new();
+        for (index, color) in ShirtColor::ALL.iter().enumerate() {
+            inventory
+                .add_stock(Sku::tee(*color, ShirtSize::M), 50 + index as u32 * 10)
+                .unwrap();
+            inventory.add_stock(Sku::tee(*color, ShirtSize::L), 25).unwrap();
+        }
+        inventory
+    }
+
+    #[test]
+    fn more_claims_than_shirts_never_oversell() {
+        let inventory = inventory();
+        let report = stress_test(&inventory, 16, 100);
+        assert!(report.invariant_holds(), "{:?}", report);
+        // 1600 claims for 600 shirts empty the inventory
+        assert_eq!(report.given_out, 600);
+        assert_eq!(report.remaining, 0);
+        assert_eq!(report.turned_away, 1600 - 600);
+    }
+
+    #[test]
+    fn fewer_claims_than_shirts_leave_the_rest_in_stock() {
+        let report = stress_test(&inventory(), 8, 50);
+        assert!(report.invariant_holds(), "{:?}", report);
+        assert_eq!(report.given_out, 400);
+        assert_eq!(report.remaining, 200);
+    }
+
+    #[test]
+    fn restocking_while_giving_away() {
+        let shared = Arc::new(SharedInventory::new(&Inventory::new()));
+        let giver = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || (0..1000).filter(|_| shared.giveaway(None).is_some()).count())
+        };
+        for _ in 0..100 {
+            shared.restock(Sku::tee(ShirtColor::Red, ShirtSize::S), 5).unwrap();
+        }
+        let given = giver.join().unwrap() as u32;
+        assert_eq!(given + shared.total(), 500);
+    }
+
+    #[test]
+    fn restocking_past_u32_max_is_an_error() {
+        let shared = SharedInventory::new(&Inventory::new());
+        shared.restock(Sku::tee(ShirtColor::Red, ShirtSize::S), u32::MAX - 1).unwrap();
+        // the limit is on the whole inventory, not only on one SKU
+        let result = shared.restock(Sku::tee(ShirtColor::Blue, ShirtSize::S), 2);
+        assert_eq!(
+            result,
+            Err(InventoryError::TooMuchStock {
+                code: String::from("TEE-BLUE-S"),
+                quantity: 2
+            })
+        );
+        assert_eq!(shared.total(), u32::MAX - 1);
+        shared.giveaway(Some(ShirtColor::Red)).unwrap();
+        shared.restock(Sku::tee(ShirtColor::Blue, ShirtSize::S), 2).unwrap();
+        assert_eq!(shared.total(), u32::MAX);
+        assert_eq!(shared.snapshot().total(), u32::MAX);
+    }
+}
@@ -0,0 +1,308 @@
+// sorting lists by several keys, like sort_by_key in main.rs but with more than one key
+// a Comparator is built from closures that pick a key from an item
+// the first key decides, on a tie the next key decides and so on
+//
+//     let by_size = Comparator::new()
+//         .then(|r: &Rectangle| r.width)
+//         .then_desc(|r: &Rectangle| r.height)
+//         .then(|r: &Rectangle| r.area());
+//
+// the closures are stored as trait objects (Box<dyn Fn>) because every closure has its own type
+// and a Comparator holds any number of them
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+/// where items without a key go, independent of the direction and of reverse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nulls {
+    First,
+    Last,
+}
+
+// how a key compared two items
+// reverse turns the order of keys around but leaves the nulls where they were asked to go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    ByKey(Ordering),
+    ByNulls(Ordering),
+}
+
+impl Decision {
+    fn ordering(self, reversed: bool) -> Ordering {
+        match self {
+            Decision::ByKey(ordering) if reversed => ordering.reverse(),
+            Decision::ByKey(ordering) | Decision::ByNulls(ordering) => ordering,
+        }
+    }
+}
+
+type Compare<'a, T> = Box<dyn Fn(&T, &T) -> Decision + 'a>;
+
+/// compares items by a list of keys
+pub struct Comparator<'a, T> {
+    keys: Vec<Compare<'a, T>>,
+    reversed: bool,
+}
+
+impl<'a, T> Default for Comparator<'a, T> {
+    fn default() -> Comparator<'a, T> {
+        Comparator {
+            keys: Vec::new(),
+            reversed: false,
+        }
+    }
+}
+
+impl<'a, T> Comparator<'a, T> {
+    /// without keys all items are equal, so sorting keeps the order
+    pub fn new() -> Comparator<'a, T> {
+        Comparator::default()
+    }
+
+    /// the next key, smallest first
+    pub fn then<K, F>(self, key: F) -> Comparator<'a, T>
+    where
+        K: Ord,
+        F: Fn(&T) -> K + 'a,
+    {
+        self.then_in(Direction::Ascending, key)
+    }
+
+    /// the next key, largest first
+    pub fn then_desc<K, F>(self, key: F) -> Comparator<'a, T>
+    where
+        K: Ord,
+        F: Fn(&T) -> K + 'a,
+    {
+        self.then_in(Direction::Descending, key)
+    }
+
+    pub fn then_in<K, F>(mut self, direction: Direction, key: F) -> Comparator<'a, T>
+    where
+        K: Ord,
+        F: Fn(&T) -> K + 'a,
+    {
+        self.keys.push(Box::new(move |a, b| {
+            Decision::ByKey(directed(key(a).cmp(&key(b)), direction))
+        }));
+        self
+    }
+
+    /// the next key for items that may not have one
+    /// the direction only orders the items that have a key, nulls says where the others go
+    pub fn then_option<K, F>(mut self, direction: Direction, nulls: Nulls, key: F) -> Comparator<'a, T>
+    where
+        K: Ord,
+        F: Fn(&T) -> Option<K> + 'a,
+    {
+        self.keys.push(Box::new(move |a, b| match (key(a), key(b)) {
+            (Some(a), Some(b)) => Decision::ByKey(directed(a.cmp(&b), direction)),
+            (None, None) => Decision::ByKey(Ordering::Equal),
+            (None, Some(_)) if nulls == Nulls::First => Decision::ByNulls(Ordering::Less),
+            (None, Some(_)) => Decision::ByNulls(Ordering::Greater),
+            (Some(_), None) if nulls == Nulls::First => Decision::ByNulls(Ordering::Greater),
+            (Some(_), None) => Decision::ByNulls(Ordering::Less),
+        }));
+        self
+    }
+
+    /// turns the order of every key around, items that compare equal stay equal
+    /// items without a key stay first or last, as then_option asked for
+    pub fn reverse(mut self) -> Comparator<'a, T> {
+        self.reversed = !self.reversed;
+        self
+    }
+
+    pub fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.keys
+            .iter()
+            .map(|compare| compare(a, b).ordering(self.reversed))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// sorts the items, items that compare equal keep their order
+    pub fn sort(&self, items: &mut [T]) {
+        items.sort_by(|a, b| self.compare(a, b));
+    }
+
+    /// the first k items in sorted order, without sorting the whole list
+    /// a heap keeps the k best items seen so far, so this takes O(n log k)
+    /// items that compare equal come in the order of the list, like with sort
+    pub fn top_k<'b>(&self, items: &'b [T], k: usize) -> Vec<&'b T> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        for (index, item) in items.iter().enumerate() {
+            // the heap is a max-heap, so its top is the worst of the items kept
+            heap.push(Candidate {
+                item,
+                index,
+                comparator: self,
+            });
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|candidate| candidate.item)
+            .collect()
+    }
+
+    /// the items in sorted order with their rank
+    /// items that compare equal share a rank
+    pub fn rank<'b>(&self, items: &'b [T]) -> Vec<Ranked<'b, T>> {
+        let mut sorted: Vec<&T> = items.iter().collect();
+        sorted.sort_by(|a, b| self.compare(a, b));
+
+        let mut ranked: Vec<Ranked<T>> = Vec::with_capacity(sorted.len());
+        for (position, item) in sorted.into_iter().enumerate() {
+            let (rank, dense_rank) = match ranked.last() {
+                Some(previous) if self.compare(previous.item, item).is_eq() => {
+                    (previous.rank, previous.dense_rank)
+                }
+                Some(previous) => (position + 1, previous.dense_rank + 1),
+                None => (1, 1),
+            };
+            ranked.push(Ranked {
+                rank,
+                dense_rank,
+                item,
+            });
+        }
+        ranked
+    }
+}
+
+fn directed(ordering: Ordering, direction: Direction) -> Ordering {
+    match direction {
+        Direction::Ascending => ordering,
+        Direction::Descending => ordering.reverse(),
+    }
+}
+
+/// an item with its rank in a sorted list
+#[derive(Debug, PartialEq, Eq)]
+pub struct Ranked<'a, T> {
+    // competition ranking, after two items ranked 1 comes 3, like 1 1 3
+    pub rank: usize,
+    // dense ranking, after two items ranked 1 comes 2, like 1 1 2
+    pub dense_rank: usize,
+    pub item: &'a T,
+}
+
+// an item in the heap of top_k, ordered by the comparator and then by its position in the list
+struct Candidate<'a, 'b, 'c, T> {
+    item: &'b T,
+    index: usize,
+    comparator: &'c Comparator<'a, T>,
+}
+
+impl<T> Ord for Candidate<'_, '_, '_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.comparator
+            .compare(self.item, other.item)
+            .then_with(|| self.index.cmp(&other.index))
+    }
+}
+
+impl<T> PartialOrd for Candidate<'_, '_, '_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> PartialEq for Candidate<'_, '_, '_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<T> Eq for Candidate<'_, '_, '_, T> {}
+
+/// a shortcut for the largest values of a single key, largest first
+pub fn largest_by_key<T, K, F>(items: &[T], k: usize, key: F) -> Vec<&T>
+where
+    K: Ord,
+    F: Fn(&T) -> K,
+{
+    Comparator::new().then(move |item| Reverse(key(item))).top_k(items, k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rectangle::Rectangle;
+
+    fn rectangles() -> Vec<Rectangle> {
+        vec![
+            Rectangle::new(10, 1),
+            Rectangle::new(3, 5),
+            Rectangle::new(7, 12),
+            Rectangle::new(3, 9),
+            Rectangle::new(7, 12),
+        ]
+    }
+
+    #[test]
+    fn later_keys_break_ties_of_earlier_ones() {
+        let mut list = rectangles();
+        Comparator::new()
+            .then(|r: &Rectangle| r.width)
+            .then_desc(|r: &Rectangle| r.height)
+            .sort(&mut list);
+        let sides: Vec<(u32, u32)> = list.iter().map(|r| (r.width, r.height)).collect();
+        assert_eq!(sides, [(3, 9), (3, 5), (7, 12), (7, 12), (10, 1)]);
+    }
+
+    #[test]
+    fn nulls_stay_where_they_were_asked_to_go() {
+        let mut values = vec![Some(2), None, Some(5), Some(1)];
+        Comparator::new()
+            .then_option(Direction::Descending, Nulls::Last, |v: &Option<i32>| *v)
+            .sort(&mut values);
+        assert_eq!(values, [Some(5), Some(2), Some(1), None]);
+        // reverse turns the values around but the nulls stay first
+        Comparator::new()
+            .then_option(Direction::Ascending, Nulls::First, |v: &Option<i32>| *v)
+            .reverse()
+            .sort(&mut values);
+        assert_eq!(values, [None, Some(5), Some(2), Some(1)]);
+        Comparator::new()
+            .then_option(Direction::Descending, Nulls::Last, |v: &Option<i32>| *v)
+            .reverse()
+            .sort(&mut values);
+        assert_eq!(values, [Some(1), Some(2), Some(5), None]);
+    }
+
+    #[test]
+    fn top_k_matches_the_start_of_a_stable_sort() {
+        let list = rectangles();
+        let by_area = Comparator::new().then_desc(|r: &Rectangle| r.area());
+        let top: Vec<*const Rectangle> = by_area.top_k(&list, 3).into_iter().map(|r| r as *const _).collect();
+        // the two 7x12 rectangles tie, the one earlier in the list comes first
+        assert_eq!(top, [&list[2] as *const _, &list[4], &list[3]]);
+        assert_eq!(by_area.top_k(&list, 10).len(), 5);
+        assert_eq!(largest_by_key(&list, 1, |r| r.width), [&list[0]]);
+    }
+
+    #[test]
+    fn ties_share_a_rank() {
+        let list = rectangles();
+        let ranks: Vec<(usize, usize)> = Comparator::new()
+            .then_desc(|r: &Rectangle| r.width)
+            .rank(&list)
+            .iter()
+            .map(|ranked| (ranked.rank, ranked.dense_rank))
+            .collect();
+        assert_eq!(ranks, [(1, 1), (2, 2), (2, 2), (4, 3), (4, 3)]);
+    }
+}